@@ -0,0 +1,18 @@
+use std::{env, path::PathBuf};
+
+fn main() {
+    let crate_dir = PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap());
+    let config = cbindgen::Config::from_file(crate_dir.join("cbindgen.toml")).unwrap();
+
+    // The header is checked in, so that C and C++ projects can use it without
+    // building the crate first
+    cbindgen::Builder::new()
+        .with_crate(&crate_dir)
+        .with_config(config)
+        .generate()
+        .expect("Failed to generate chip8.h")
+        .write_to_file(crate_dir.join("include/chip8.h"));
+
+    println!("cargo:rerun-if-changed=src");
+    println!("cargo:rerun-if-changed=cbindgen.toml");
+}
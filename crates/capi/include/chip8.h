@@ -0,0 +1,171 @@
+/* A C API for the CHIP-8 emulator. Generated by cbindgen, do not edit. */
+
+#ifndef CHIP8_H
+#define CHIP8_H
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+#define CHIP8_DISPLAY_WIDTH 64
+
+#define CHIP8_DISPLAY_HEIGHT 32
+
+/**
+ * Large enough for any save state.
+ */
+#define CHIP8_MAX_STATE_SIZE 4678
+
+typedef enum Chip8Result {
+  CHIP8_RESULT_OK = 0,
+  /**
+   * A required pointer was null.
+   */
+  CHIP8_RESULT_NULL_POINTER,
+  /**
+   * The ROM doesn't fit in memory.
+   */
+  CHIP8_RESULT_ROM_TOO_LARGE,
+  /**
+   * The machine hit an opcode that isn't a known instruction.
+   */
+  CHIP8_RESULT_UNKNOWN_INSTRUCTION,
+  /**
+   * The machine tried to return from a subroutine with an empty call stack.
+   */
+  CHIP8_RESULT_STACK_UNDERFLOW,
+  /**
+   * The buffer passed in is too small.
+   */
+  CHIP8_RESULT_BUFFER_TOO_SMALL,
+  /**
+   * The data passed to `chip8_load_state` isn't a valid save state.
+   */
+  CHIP8_RESULT_INVALID_STATE,
+  /**
+   * The key isn't from 0x0 to 0xF.
+   */
+  CHIP8_RESULT_INVALID_KEY,
+  /**
+   * The machine tried to call a subroutine with a full call stack.
+   */
+  CHIP8_RESULT_STACK_OVERFLOW,
+  /**
+   * The machine called a machine code subroutine, which needs the 1802 core.
+   */
+  CHIP8_RESULT_MACHINE_CODE,
+  /**
+   * A machine code subroutine didn't return to the interpreter.
+   */
+  CHIP8_RESULT_MACHINE_CODE_TIMEOUT,
+  /**
+   * The memory size, load address or fonts don't make a valid memory layout.
+   */
+  CHIP8_RESULT_INVALID_LAYOUT,
+} Chip8Result;
+
+typedef enum Chip8Quirks {
+  CHIP8_QUIRKS_MODERN,
+  CHIP8_QUIRKS_COSMAC_VIP,
+  CHIP8_QUIRKS_SUPERCHIP,
+} Chip8Quirks;
+
+/**
+ * An emulated machine. Only ever used through a pointer.
+ */
+typedef struct Chip8 Chip8;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * Returns a static, human readable description of a result.
+ */
+const char *chip8_result_message(enum Chip8Result result);
+
+/**
+ * Creates a machine with no ROM loaded. Free it with `chip8_free`.
+ */
+struct Chip8 *chip8_new(enum Chip8Quirks quirks);
+
+/**
+ * Frees a machine created by `chip8_new`. Does nothing if `chip8` is null.
+ *
+ * # Safety
+ *
+ * `chip8` must be null or a handle from `chip8_new` that hasn't been freed yet.
+ */
+void chip8_free(struct Chip8 *chip8);
+
+/**
+ * Resets the machine and loads a ROM of `len` bytes.
+ *
+ * # Safety
+ *
+ * `chip8` must be a valid handle, and `rom` must point to `len` readable bytes.
+ */
+enum Chip8Result chip8_load_rom(struct Chip8 *chip8, const uint8_t *rom, size_t len);
+
+/**
+ * Runs one 60 Hz frame: decrements the timers, then executes `cycles`
+ * instructions, stopping at the first error.
+ *
+ * # Safety
+ *
+ * `chip8` must be a valid handle.
+ */
+enum Chip8Result chip8_run_frame(struct Chip8 *chip8, size_t cycles);
+
+/**
+ * Copies the display into `pixels`, one byte per pixel row by row, with 1 for lit
+ * pixels and 0 for unlit ones. `len` must be at least
+ * `CHIP8_DISPLAY_WIDTH * CHIP8_DISPLAY_HEIGHT`.
+ *
+ * # Safety
+ *
+ * `chip8` must be a valid handle, and `pixels` must point to `len` writable bytes.
+ */
+enum Chip8Result chip8_get_display(const struct Chip8 *chip8, uint8_t *pixels, size_t len);
+
+/**
+ * Presses or releases a key on the keypad, from 0x0 to 0xF.
+ *
+ * # Safety
+ *
+ * `chip8` must be a valid handle.
+ */
+enum Chip8Result chip8_set_key(struct Chip8 *chip8, uint8_t key, bool pressed);
+
+/**
+ * Saves the machine's state into `buffer`, and its size into `size`. A buffer
+ * of `CHIP8_MAX_STATE_SIZE` bytes is always large enough. If `buffer` is too
+ * small, nothing is written to it, but `size` is still set.
+ *
+ * # Safety
+ *
+ * `chip8` must be a valid handle, `buffer` must point to `capacity` writable
+ * bytes, and `size` must be writable.
+ */
+enum Chip8Result chip8_save_state(const struct Chip8 *chip8,
+                                  uint8_t *buffer,
+                                  size_t capacity,
+                                  size_t *size);
+
+/**
+ * Restores a state saved with `chip8_save_state`. If the state is invalid, the
+ * machine is left unchanged.
+ *
+ * # Safety
+ *
+ * `chip8` must be a valid handle, and `state` must point to `len` readable bytes.
+ */
+enum Chip8Result chip8_load_state(struct Chip8 *chip8, const uint8_t *state, size_t len);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* CHIP8_H */
@@ -0,0 +1,300 @@
+//! A C API for embedding the emulator, with the header generated into
+//! `include/chip8.h` by cbindgen.
+//!
+//! Every function takes a handle created by [`chip8_new`], and those that can fail
+//! return a [`Chip8Result`].
+
+use std::{ffi::c_char, ptr, slice};
+
+use machine::{
+    Drivers, Error, LoadError, Machine, Quirks, StateError, DISPLAY_HEIGHT as HEIGHT,
+    DISPLAY_WIDTH as WIDTH,
+};
+
+// These are written out so that cbindgen can put them in the header
+pub const CHIP8_DISPLAY_WIDTH: usize = 64;
+pub const CHIP8_DISPLAY_HEIGHT: usize = 32;
+/// Large enough for any save state.
+pub const CHIP8_MAX_STATE_SIZE: usize = 4678;
+
+const _: () = assert!(CHIP8_DISPLAY_WIDTH == WIDTH && CHIP8_DISPLAY_HEIGHT == HEIGHT);
+const _: () = assert!(CHIP8_MAX_STATE_SIZE == machine::state_size(machine::STACK_DEPTH));
+
+/// An emulated machine. Only ever used through a pointer.
+pub struct Chip8 {
+    machine: Machine,
+    quirks: Quirks,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Chip8Quirks {
+    Modern,
+    CosmacVip,
+    Superchip,
+}
+
+impl Chip8Quirks {
+    fn quirks(self) -> Quirks {
+        match self {
+            Self::Modern => Quirks::modern_chip8(),
+            Self::CosmacVip => Quirks::cosmac_vip(),
+            Self::Superchip => Quirks::superchip(),
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Chip8Result {
+    Ok = 0,
+    /// A required pointer was null.
+    NullPointer,
+    /// The ROM doesn't fit in memory.
+    RomTooLarge,
+    /// The machine hit an opcode that isn't a known instruction.
+    UnknownInstruction,
+    /// The machine tried to return from a subroutine with an empty call stack.
+    StackUnderflow,
+    /// The buffer passed in is too small.
+    BufferTooSmall,
+    /// The data passed to `chip8_load_state` isn't a valid save state.
+    InvalidState,
+    /// The key isn't from 0x0 to 0xF.
+    InvalidKey,
+    /// The machine tried to call a subroutine with a full call stack.
+    StackOverflow,
+    /// The machine called a machine code subroutine, which needs the 1802 core.
+    MachineCode,
+    /// A machine code subroutine didn't return to the interpreter.
+    MachineCodeTimeout,
+    /// The memory size, load address or fonts don't make a valid memory layout.
+    InvalidLayout,
+}
+
+impl From<Error> for Chip8Result {
+    fn from(err: Error) -> Self {
+        match err {
+            Error::UnknownInstruction { .. } => Self::UnknownInstruction,
+            Error::StackUnderflow { .. } => Self::StackUnderflow,
+            Error::StackOverflow { .. } => Self::StackOverflow,
+            Error::MachineCode { .. } => Self::MachineCode,
+            Error::MachineCodeTimeout { .. } => Self::MachineCodeTimeout,
+        }
+    }
+}
+
+impl From<LoadError> for Chip8Result {
+    fn from(err: LoadError) -> Self {
+        match err {
+            LoadError::RomTooLarge { .. } => Self::RomTooLarge,
+            // The default layout is always valid, but these can't be allowed to
+            // panic across the FFI boundary if that ever changes
+            LoadError::InvalidMemorySize { .. }
+            | LoadError::LoadAddressOutOfRange { .. }
+            | LoadError::FontOutOfRange { .. }
+            | LoadError::FontOverlapsRom { .. }
+            | LoadError::FontsOverlap { .. } => Self::InvalidLayout,
+        }
+    }
+}
+
+impl From<StateError> for Chip8Result {
+    fn from(_: StateError) -> Self {
+        Self::InvalidState
+    }
+}
+
+/// Returns a static, human readable description of a result.
+#[no_mangle]
+pub extern "C" fn chip8_result_message(result: Chip8Result) -> *const c_char {
+    let message = match result {
+        Chip8Result::Ok => c"Success",
+        Chip8Result::NullPointer => c"A required pointer was null",
+        Chip8Result::RomTooLarge => c"The ROM doesn't fit in memory",
+        Chip8Result::UnknownInstruction => c"Unknown instruction",
+        Chip8Result::StackUnderflow => c"Attempted to return from a subroutine with an empty stack",
+        Chip8Result::BufferTooSmall => c"The buffer is too small",
+        Chip8Result::InvalidState => c"Not a valid save state",
+        Chip8Result::InvalidKey => c"Keys are from 0x0 to 0xF",
+        Chip8Result::StackOverflow => c"Attempted to call a subroutine with a full stack",
+        Chip8Result::MachineCode => c"Called a machine code subroutine without the 1802 core",
+        Chip8Result::MachineCodeTimeout => c"A machine code subroutine didn't return",
+        Chip8Result::InvalidLayout => c"The memory layout is invalid",
+    };
+    message.as_ptr()
+}
+
+/// Creates a machine with no ROM loaded. Free it with `chip8_free`.
+#[no_mangle]
+pub extern "C" fn chip8_new(quirks: Chip8Quirks) -> *mut Chip8 {
+    let quirks = quirks.quirks();
+    Box::into_raw(Box::new(Chip8 {
+        // An empty ROM always fits
+        machine: Machine::from_rom(&[], quirks, Drivers::noop()).unwrap(),
+        quirks,
+    }))
+}
+
+/// Frees a machine created by `chip8_new`. Does nothing if `chip8` is null.
+///
+/// # Safety
+///
+/// `chip8` must be null or a handle from `chip8_new` that hasn't been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn chip8_free(chip8: *mut Chip8) {
+    if !chip8.is_null() {
+        drop(Box::from_raw(chip8));
+    }
+}
+
+/// Resets the machine and loads a ROM of `len` bytes.
+///
+/// # Safety
+///
+/// `chip8` must be a valid handle, and `rom` must point to `len` readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn chip8_load_rom(
+    chip8: *mut Chip8,
+    rom: *const u8,
+    len: usize,
+) -> Chip8Result {
+    let Some(chip8) = chip8.as_mut() else {
+        return Chip8Result::NullPointer;
+    };
+    if rom.is_null() {
+        return Chip8Result::NullPointer;
+    }
+
+    let rom = slice::from_raw_parts(rom, len);
+    match Machine::from_rom(rom, chip8.quirks, Drivers::noop()) {
+        Ok(machine) => {
+            chip8.machine = machine;
+            Chip8Result::Ok
+        }
+        Err(err) => err.into(),
+    }
+}
+
+/// Runs one 60 Hz frame: decrements the timers, then executes `cycles`
+/// instructions, stopping at the first error.
+///
+/// # Safety
+///
+/// `chip8` must be a valid handle.
+#[no_mangle]
+pub unsafe extern "C" fn chip8_run_frame(chip8: *mut Chip8, cycles: usize) -> Chip8Result {
+    let Some(chip8) = chip8.as_mut() else {
+        return Chip8Result::NullPointer;
+    };
+
+    match chip8.machine.run_frame(cycles) {
+        Ok(()) => Chip8Result::Ok,
+        Err(err) => err.into(),
+    }
+}
+
+/// Copies the display into `pixels`, one byte per pixel row by row, with 1 for lit
+/// pixels and 0 for unlit ones. `len` must be at least
+/// `CHIP8_DISPLAY_WIDTH * CHIP8_DISPLAY_HEIGHT`.
+///
+/// # Safety
+///
+/// `chip8` must be a valid handle, and `pixels` must point to `len` writable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn chip8_get_display(
+    chip8: *const Chip8,
+    pixels: *mut u8,
+    len: usize,
+) -> Chip8Result {
+    let Some(chip8) = chip8.as_ref() else {
+        return Chip8Result::NullPointer;
+    };
+    if pixels.is_null() {
+        return Chip8Result::NullPointer;
+    }
+    if len < WIDTH * HEIGHT {
+        return Chip8Result::BufferTooSmall;
+    }
+
+    let pixels = slice::from_raw_parts_mut(pixels, WIDTH * HEIGHT);
+    for (pixel, (_, on)) in pixels.iter_mut().zip(chip8.machine.display.iter()) {
+        *pixel = on as u8;
+    }
+    Chip8Result::Ok
+}
+
+/// Presses or releases a key on the keypad, from 0x0 to 0xF.
+///
+/// # Safety
+///
+/// `chip8` must be a valid handle.
+#[no_mangle]
+pub unsafe extern "C" fn chip8_set_key(chip8: *mut Chip8, key: u8, pressed: bool) -> Chip8Result {
+    let Some(chip8) = chip8.as_mut() else {
+        return Chip8Result::NullPointer;
+    };
+    if key > 0xF {
+        return Chip8Result::InvalidKey;
+    }
+
+    chip8.machine.set_key(key, pressed);
+    Chip8Result::Ok
+}
+
+/// Saves the machine's state into `buffer`, and its size into `size`. A buffer
+/// of `CHIP8_MAX_STATE_SIZE` bytes is always large enough. If `buffer` is too
+/// small, nothing is written to it, but `size` is still set.
+///
+/// # Safety
+///
+/// `chip8` must be a valid handle, `buffer` must point to `capacity` writable
+/// bytes, and `size` must be writable.
+#[no_mangle]
+pub unsafe extern "C" fn chip8_save_state(
+    chip8: *const Chip8,
+    buffer: *mut u8,
+    capacity: usize,
+    size: *mut usize,
+) -> Chip8Result {
+    let Some(chip8) = chip8.as_ref() else {
+        return Chip8Result::NullPointer;
+    };
+    if buffer.is_null() || size.is_null() {
+        return Chip8Result::NullPointer;
+    }
+
+    let state = chip8.machine.save_state();
+    size.write(state.len());
+    if capacity < state.len() {
+        return Chip8Result::BufferTooSmall;
+    }
+
+    ptr::copy_nonoverlapping(state.as_ptr(), buffer, state.len());
+    Chip8Result::Ok
+}
+
+/// Restores a state saved with `chip8_save_state`. If the state is invalid, the
+/// machine is left unchanged.
+///
+/// # Safety
+///
+/// `chip8` must be a valid handle, and `state` must point to `len` readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn chip8_load_state(
+    chip8: *mut Chip8,
+    state: *const u8,
+    len: usize,
+) -> Chip8Result {
+    let Some(chip8) = chip8.as_mut() else {
+        return Chip8Result::NullPointer;
+    };
+    if state.is_null() {
+        return Chip8Result::NullPointer;
+    }
+
+    match chip8.machine.load_state(slice::from_raw_parts(state, len)) {
+        Ok(()) => Chip8Result::Ok,
+        Err(err) => err.into(),
+    }
+}
@@ -0,0 +1,75 @@
+/* Exercises the C API. Takes the path of the IBM logo ROM, and exits with a
+ * non-zero status if anything fails. */
+
+#include <stdio.h>
+#include <string.h>
+
+#include "chip8.h"
+
+#define CHECK(expr)                                                           \
+  do {                                                                        \
+    if (!(expr)) {                                                            \
+      fprintf(stderr, "%s:%d: check failed: %s\n", __FILE__, __LINE__, #expr); \
+      return 1;                                                               \
+    }                                                                         \
+  } while (0)
+
+#define PIXEL(pixels, x, y) ((pixels)[(y) * CHIP8_DISPLAY_WIDTH + (x)])
+
+int main(int argc, char **argv) {
+  CHECK(argc == 2);
+
+  static uint8_t rom[4096];
+  FILE *file = fopen(argv[1], "rb");
+  CHECK(file != NULL);
+  size_t rom_len = fread(rom, 1, sizeof(rom), file);
+  fclose(file);
+
+  Chip8 *chip8 = chip8_new(CHIP8_QUIRKS_MODERN);
+  CHECK(chip8 != NULL);
+  CHECK(chip8_load_rom(chip8, rom, rom_len) == CHIP8_RESULT_OK);
+  CHECK(chip8_load_rom(chip8, rom, sizeof(rom)) == CHIP8_RESULT_ROM_TOO_LARGE);
+  CHECK(chip8_load_rom(chip8, rom, rom_len) == CHIP8_RESULT_OK);
+
+  uint8_t state[CHIP8_MAX_STATE_SIZE];
+  size_t state_size = 0;
+  CHECK(chip8_save_state(chip8, state, sizeof(state), &state_size) == CHIP8_RESULT_OK);
+  CHECK(state_size > 0 && state_size <= CHIP8_MAX_STATE_SIZE);
+  CHECK(chip8_save_state(chip8, state, 1, &state_size) == CHIP8_RESULT_BUFFER_TOO_SMALL);
+
+  for (int i = 0; i < 10; i++) {
+    CHECK(chip8_run_frame(chip8, 11) == CHIP8_RESULT_OK);
+  }
+
+  uint8_t pixels[CHIP8_DISPLAY_WIDTH * CHIP8_DISPLAY_HEIGHT];
+  CHECK(chip8_get_display(chip8, pixels, 10) == CHIP8_RESULT_BUFFER_TOO_SMALL);
+  CHECK(chip8_get_display(chip8, pixels, sizeof(pixels)) == CHIP8_RESULT_OK);
+  /* The top left corner of the "I" */
+  CHECK(PIXEL(pixels, 12, 8) == 1);
+  CHECK(PIXEL(pixels, 0, 0) == 0);
+
+  /* Going back to the state saved before anything was drawn clears the display */
+  CHECK(chip8_load_state(chip8, state, state_size) == CHIP8_RESULT_OK);
+  CHECK(chip8_get_display(chip8, pixels, sizeof(pixels)) == CHIP8_RESULT_OK);
+  CHECK(PIXEL(pixels, 12, 8) == 0);
+  CHECK(chip8_load_state(chip8, state, 3) == CHIP8_RESULT_INVALID_STATE);
+
+  CHECK(chip8_set_key(chip8, 0xF, true) == CHIP8_RESULT_OK);
+  CHECK(chip8_set_key(chip8, 0x10, true) == CHIP8_RESULT_INVALID_KEY);
+
+  /* FFFF isn't an instruction */
+  const uint8_t bad_rom[] = {0xFF, 0xFF};
+  CHECK(chip8_load_rom(chip8, bad_rom, sizeof(bad_rom)) == CHIP8_RESULT_OK);
+  Chip8Result result = chip8_run_frame(chip8, 1);
+  CHECK(result == CHIP8_RESULT_UNKNOWN_INSTRUCTION);
+  CHECK(strcmp(chip8_result_message(result), "Unknown instruction") == 0);
+  CHECK(strcmp(chip8_result_message(CHIP8_RESULT_INVALID_LAYOUT),
+               "The memory layout is invalid") == 0);
+
+  CHECK(chip8_run_frame(NULL, 1) == CHIP8_RESULT_NULL_POINTER);
+
+  chip8_free(chip8);
+  chip8_free(NULL);
+
+  return 0;
+}
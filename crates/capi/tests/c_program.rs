@@ -0,0 +1,36 @@
+// Builds with the system C compiler and links with Unix-style flags
+#![cfg(unix)]
+
+use std::{env, path::Path, process::Command};
+
+/// Compiles `tests/c/test.c` against the generated header and the core's shared
+/// library, then runs it.
+#[test]
+fn c_program() {
+    let crate_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+    // Cargo builds the library into the same directory as the test binaries
+    let exe = env::current_exe().unwrap();
+    let lib_dir = exe.parent().unwrap();
+    let program = lib_dir.join("chip8_capi_test");
+
+    let cc = env::var("CC").unwrap_or_else(|_| "cc".to_owned());
+    let status = Command::new(&cc)
+        .arg(crate_dir.join("tests/c/test.c"))
+        .arg("-std=c99")
+        .args(["-Wall", "-Wextra", "-Werror"])
+        .arg("-I")
+        .arg(crate_dir.join("include"))
+        .arg("-L")
+        .arg(lib_dir)
+        .arg(format!("-Wl,-rpath,{}", lib_dir.display()))
+        .arg("-lchip8_capi")
+        .arg("-o")
+        .arg(&program)
+        .status()
+        .unwrap_or_else(|err| panic!("Failed to run {cc}: {err}"));
+    assert!(status.success(), "Failed to compile the C program");
+
+    let rom = crate_dir.join("../../roms/ibm-logo.ch8");
+    let status = Command::new(&program).arg(rom).status().unwrap();
+    assert!(status.success(), "The C program failed");
+}
@@ -0,0 +1,87 @@
+use std::thread;
+
+use crate::{Env, Observation, Step};
+
+/// Many copies of an environment, stepped in parallel across threads.
+pub struct BatchEnv {
+    envs: Vec<Env>,
+    threads: usize,
+}
+
+impl BatchEnv {
+    pub fn new(envs: Vec<Env>) -> Self {
+        let threads = thread::available_parallelism().map_or(1, |threads| threads.get());
+        Self { envs, threads }
+    }
+
+    pub fn len(&self) -> usize {
+        self.envs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.envs.is_empty()
+    }
+
+    pub fn envs(&self) -> &[Env] {
+        &self.envs
+    }
+
+    pub fn envs_mut(&mut self) -> &mut [Env] {
+        &mut self.envs
+    }
+
+    /// Resets every environment, each with its own seed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there isn't one seed per environment.
+    pub fn reset(&mut self, seeds: &[u64]) -> Vec<Observation> {
+        assert_eq!(
+            seeds.len(),
+            self.envs.len(),
+            "Expected one seed per environment"
+        );
+        self.map(seeds, Env::reset)
+    }
+
+    /// Takes one step in every environment, each with its own action. Environments
+    /// aren't reset when they're done, so that's up to the caller.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there isn't one action per environment.
+    pub fn step(&mut self, actions: &[usize]) -> Vec<Result<Step, machine::Error>> {
+        assert_eq!(
+            actions.len(),
+            self.envs.len(),
+            "Expected one action per environment"
+        );
+        self.map(actions, Env::step)
+    }
+
+    /// Calls `f` on each environment and its input, splitting the environments
+    /// evenly between the threads.
+    fn map<I: Copy + Sync, O: Send>(&mut self, inputs: &[I], f: fn(&mut Env, I) -> O) -> Vec<O> {
+        let chunk_size = self.envs.len().div_ceil(self.threads).max(1);
+        thread::scope(|scope| {
+            let handles: Vec<_> = self
+                .envs
+                .chunks_mut(chunk_size)
+                .zip(inputs.chunks(chunk_size))
+                .map(|(envs, inputs)| {
+                    scope.spawn(move || {
+                        envs.iter_mut()
+                            .zip(inputs)
+                            .map(|(env, input)| f(env, *input))
+                            .collect::<Vec<_>>()
+                    })
+                })
+                .collect();
+
+            handles
+                .into_iter()
+                .flat_map(|handle| handle.join().unwrap())
+                .collect()
+        })
+    }
+}
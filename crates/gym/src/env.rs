@@ -0,0 +1,137 @@
+use machine::{Display, Drivers, Machine, Quirks, CYCLES_PER_SECOND};
+
+use crate::{Error, Spec};
+
+/// What the agent sees: the display after each step.
+pub type Observation = Display;
+
+/// The result of taking an action.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Step {
+    pub observation: Observation,
+    pub reward: f32,
+    /// Whether the episode is over, after which the environment needs to be reset.
+    pub done: bool,
+}
+
+/// A Gym-style environment for playing a ROM, one step at a time.
+pub struct Env {
+    rom: Vec<u8>,
+    spec: Spec,
+    keypads: Vec<u16>,
+    pub quirks: Quirks,
+    /// The number of frames each action is held for.
+    pub frame_skip: usize,
+    pub cycles_per_frame: usize,
+
+    machine: Machine,
+    steps: usize,
+    /// The value of each of the spec's rewards as of the last step.
+    reward_values: Vec<u8>,
+}
+
+impl Env {
+    pub fn new(rom: &[u8], spec: Spec) -> Result<Self, Error> {
+        let quirks = Quirks::modern_chip8();
+        let machine = Machine::from_rom(rom, quirks, Drivers::noop()).map_err(Error::Load)?;
+        if let Some(location) = spec.invalid_location() {
+            return Err(Error::InvalidLocation(location));
+        }
+        if spec.actions.is_empty() {
+            return Err(Error::NoActions);
+        }
+
+        let mut env = Self {
+            rom: rom.to_vec(),
+            keypads: spec.keypads(),
+            spec,
+            quirks,
+            frame_skip: 4,
+            cycles_per_frame: CYCLES_PER_SECOND / 60,
+
+            machine,
+            steps: 0,
+            reward_values: Vec::new(),
+        };
+        env.reset(0);
+
+        Ok(env)
+    }
+
+    pub fn spec(&self) -> &Spec {
+        &self.spec
+    }
+
+    /// The number of actions, which are numbered from 0.
+    pub fn action_count(&self) -> usize {
+        self.keypads.len()
+    }
+
+    pub fn machine(&self) -> &Machine {
+        &self.machine
+    }
+
+    /// Restarts the ROM, with CXNN's random numbers drawn from `seed`.
+    pub fn reset(&mut self, seed: u64) -> Observation {
+        self.machine = Machine::from_rom(&self.rom, self.quirks, Drivers::noop())
+            .expect("the ROM already loaded when the environment was created");
+        self.machine.rng = fastrand::Rng::with_seed(seed);
+        self.steps = 0;
+        self.reward_values = self.read_reward_values();
+
+        self.machine.display.clone()
+    }
+
+    /// Holds down the keys for `action` for `frame_skip` frames, or until the
+    /// episode ends, and returns the reward earned in that time.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `action` isn't less than [`Env::action_count`].
+    pub fn step(&mut self, action: usize) -> Result<Step, machine::Error> {
+        self.machine.keypad = self.keypads[action];
+        self.steps += 1;
+
+        let mut reward = 0.0;
+        let mut done = false;
+        for _ in 0..self.frame_skip.max(1) {
+            self.machine.run_frame(self.cycles_per_frame)?;
+
+            let values = self.read_reward_values();
+            for ((spec, value), previous) in self
+                .spec
+                .rewards
+                .iter()
+                .zip(&values)
+                .zip(&self.reward_values)
+            {
+                reward += spec.scale * f32::from(value.wrapping_sub(*previous) as i8);
+            }
+            self.reward_values = values;
+
+            done = self
+                .spec
+                .done
+                .iter()
+                .any(|condition| condition.is_met(&self.machine));
+            if done {
+                break;
+            }
+        }
+        done |= self.spec.max_steps.is_some_and(|max| self.steps >= max);
+
+        Ok(Step {
+            observation: self.machine.display.clone(),
+            reward,
+            done,
+        })
+    }
+
+    fn read_reward_values(&self) -> Vec<u8> {
+        self.spec
+            .rewards
+            .iter()
+            .map(|reward| reward.location.read(&self.machine))
+            .collect()
+    }
+}
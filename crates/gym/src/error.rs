@@ -0,0 +1,30 @@
+use std::fmt;
+
+use machine::LoadError;
+
+use crate::Location;
+
+/// An error raised while setting up an environment.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Error {
+    /// The ROM couldn't be loaded.
+    Load(LoadError),
+    /// The spec reads from outside of memory or the registers.
+    InvalidLocation(Location),
+    /// The spec has no actions for the agent to take.
+    NoActions,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Load(err) => err.fmt(f),
+            Self::InvalidLocation(location) => {
+                write!(f, "Spec reads from {location:?}, which doesn't exist")
+            }
+            Self::NoActions => write!(f, "Spec has no actions"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
@@ -0,0 +1,12 @@
+//! A Gym-style reinforcement learning environment, for training and benchmarking
+//! agents on CHIP-8 games.
+
+mod batch;
+mod env;
+mod error;
+mod spec;
+
+pub use batch::BatchEnv;
+pub use env::{Env, Observation, Step};
+pub use error::Error;
+pub use spec::{Condition, Location, Reward, Spec, Test};
@@ -0,0 +1,162 @@
+use serde::Deserialize;
+
+use machine::{Machine, MEMORY_SIZE};
+
+/// A byte of machine state that a ROM keeps its score or lives in.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum Location {
+    Memory(u16),
+    Register(u8),
+}
+
+impl Location {
+    pub fn read(self, machine: &Machine) -> u8 {
+        match self {
+            Self::Memory(address) => machine.memory[address as usize],
+            Self::Register(x) => machine.registers[x as usize],
+        }
+    }
+
+    fn is_valid(self) -> bool {
+        match self {
+            Self::Memory(address) => (address as usize) < MEMORY_SIZE,
+            Self::Register(x) => x < 16,
+        }
+    }
+}
+
+/// Rewards the change in a value since the last step, such as a score.
+///
+/// The change is taken modulo 256 and read as signed, so a counter that wraps
+/// from 255 to 0 counts as going up by one, and a change of more than 127 in
+/// either direction comes out the other way round. Scores that go up a little
+/// at a time and wrap around are rewarded correctly, while a score that resets
+/// from 200 to 0 counts as an increase of 56.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct Reward {
+    #[serde(flatten)]
+    pub location: Location,
+    /// What each increase in the value is worth. A negative scale penalizes
+    /// increases instead, for example in the opponent's score.
+    #[serde(default = "default_scale")]
+    pub scale: f32,
+}
+
+fn default_scale() -> f32 {
+    1.0
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum Test {
+    Equals(u8),
+    NotEquals(u8),
+    AtLeast(u8),
+    AtMost(u8),
+}
+
+impl Test {
+    fn matches(self, value: u8) -> bool {
+        match self {
+            Self::Equals(expected) => value == expected,
+            Self::NotEquals(expected) => value != expected,
+            Self::AtLeast(min) => value >= min,
+            Self::AtMost(max) => value <= max,
+        }
+    }
+}
+
+/// Ends the episode once a value passes a test, such as a score reaching its
+/// maximum or the lives running out.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq)]
+pub struct Condition {
+    #[serde(flatten)]
+    pub location: Location,
+    #[serde(flatten)]
+    pub test: Test,
+}
+
+impl Condition {
+    pub fn is_met(&self, machine: &Machine) -> bool {
+        self.test.matches(self.location.read(machine))
+    }
+}
+
+/// Describes how to play a particular ROM: which keys the agent can press, and
+/// where the ROM keeps the values that decide the reward and when it's over.
+///
+/// Specs are usually written in TOML:
+///
+/// ```toml
+/// # Each action holds down a set of keys. Without this, there's a no-op action
+/// # followed by one action for each of the 16 keys.
+/// actions = [[], [0x1], [0x4]]
+/// max-steps = 10000
+///
+/// [[reward]]
+/// register = 0xE
+///
+/// [[reward]]
+/// memory = 0x2F0
+/// scale = -1.0
+///
+/// [[done]]
+/// register = 0xE
+/// at-least = 9
+/// ```
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct Spec {
+    #[serde(default = "default_actions")]
+    pub actions: Vec<Vec<u8>>,
+    #[serde(default, rename = "reward")]
+    pub rewards: Vec<Reward>,
+    /// The episode ends when any of these conditions is met.
+    #[serde(default)]
+    pub done: Vec<Condition>,
+    /// Ends the episode after this many steps, even if no condition is met.
+    pub max_steps: Option<usize>,
+}
+
+fn default_actions() -> Vec<Vec<u8>> {
+    core::iter::once(Vec::new())
+        .chain((0..16).map(|key| vec![key]))
+        .collect()
+}
+
+impl Default for Spec {
+    /// The default actions, with no rewards and no end.
+    fn default() -> Self {
+        Self {
+            actions: default_actions(),
+            rewards: Vec::new(),
+            done: Vec::new(),
+            max_steps: None,
+        }
+    }
+}
+
+impl Spec {
+    pub fn from_toml(toml: &str) -> Result<Self, toml::de::Error> {
+        toml::from_str(toml)
+    }
+
+    /// The keypad bitmask held down for each action.
+    pub(crate) fn keypads(&self) -> Vec<u16> {
+        self.actions
+            .iter()
+            .map(|keys| keys.iter().fold(0, |keypad, key| keypad | 1 << (key & 0xF)))
+            .collect()
+    }
+
+    /// Returns the first location that's outside of memory or the registers.
+    pub(crate) fn invalid_location(&self) -> Option<Location> {
+        self.rewards
+            .iter()
+            .map(|reward| reward.location)
+            .chain(self.done.iter().map(|condition| condition.location))
+            .find(|location| !location.is_valid())
+    }
+}
@@ -0,0 +1,229 @@
+use gym::{BatchEnv, Condition, Env, Error, Location, Reward, Spec, Test};
+
+/// Adds 1 to V1 each cycle while key 5 is held.
+const COUNTER: [u8; 8] = [
+    0x60, 0x05, // V0 = 5
+    0xE0, 0xA1, // Skip the next instruction if key V0 isn't pressed
+    0x71, 0x01, // V1 += 1
+    0x12, 0x02, // Jump back to the key check
+];
+
+/// Puts a new random number in V2 each cycle.
+const RANDOM: [u8; 4] = [
+    0xC2, 0xFF, // V2 = random
+    0x12, 0x00, // Jump back
+];
+
+fn counter_spec() -> Spec {
+    Spec {
+        actions: vec![vec![], vec![0x5]],
+        rewards: vec![Reward {
+            location: Location::Register(1),
+            scale: 0.5,
+        }],
+        done: vec![Condition {
+            location: Location::Register(1),
+            test: Test::AtLeast(100),
+        }],
+        max_steps: None,
+    }
+}
+
+#[test]
+fn rewards_changes_in_value() {
+    let mut env = Env::new(&COUNTER, counter_spec()).unwrap();
+    env.frame_skip = 1;
+    env.reset(0);
+
+    let step = env.step(0).unwrap();
+    assert_eq!(step.reward, 0.0);
+    assert!(!step.done);
+
+    let mut total = 0.0;
+    loop {
+        let step = env.step(1).unwrap();
+        assert!(step.reward > 0.0);
+        total += step.reward;
+        if step.done {
+            break;
+        }
+    }
+    let count = env.machine().registers[1];
+    assert!(count >= 100);
+    assert_eq!(total, count as f32 * 0.5);
+}
+
+#[test]
+fn holds_actions_for_frame_skip() {
+    let mut env = Env::new(&COUNTER, counter_spec()).unwrap();
+    env.frame_skip = 1;
+    let one_frame = env.step(1).unwrap().reward;
+
+    env.frame_skip = 3;
+    env.reset(0);
+    let three_frames = env.step(1).unwrap().reward;
+
+    // The key check loop takes three cycles, so each frame doesn't add quite the
+    // same amount
+    assert!((three_frames - 3.0 * one_frame).abs() <= 1.0);
+}
+
+#[test]
+fn ends_after_max_steps() {
+    let spec = Spec {
+        max_steps: Some(3),
+        ..Spec::default()
+    };
+    let mut env = Env::new(&COUNTER, spec).unwrap();
+    assert_eq!(env.action_count(), 17);
+
+    assert!(!env.step(0).unwrap().done);
+    assert!(!env.step(0).unwrap().done);
+    assert!(env.step(0).unwrap().done);
+    env.reset(0);
+    assert!(!env.step(0).unwrap().done);
+}
+
+#[test]
+fn observes_display() {
+    let rom = include_bytes!("../../../roms/ibm-logo.ch8");
+    let mut env = Env::new(rom, Spec::default()).unwrap();
+    assert_eq!(env.reset(0).lit_pixels().count(), 0);
+
+    let step = env.step(0).unwrap();
+    assert!(step.observation.get(12, 8));
+}
+
+#[test]
+fn seeds_random_numbers() {
+    let spec = Spec {
+        rewards: vec![Reward {
+            location: Location::Register(2),
+            scale: 1.0,
+        }],
+        ..Spec::default()
+    };
+    let mut env = Env::new(&RANDOM, spec).unwrap();
+    let mut rewards = |seed| {
+        env.reset(seed);
+        (0..10)
+            .map(|_| env.step(0).unwrap().reward)
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(rewards(1), rewards(1));
+    assert_ne!(rewards(1), rewards(2));
+}
+
+#[test]
+fn steps_batches_in_parallel() {
+    let envs = (0..8)
+        .map(|_| Env::new(&COUNTER, counter_spec()).unwrap())
+        .collect();
+    let mut batch = BatchEnv::new(envs);
+    let mut single = Env::new(&COUNTER, counter_spec()).unwrap();
+
+    batch.reset(&[0; 8]);
+    let actions = [0, 1, 0, 1, 1, 1, 0, 0];
+    let steps = batch.step(&actions);
+    assert_eq!(steps.len(), 8);
+
+    for (step, action) in steps.into_iter().zip(actions) {
+        single.reset(0);
+        assert_eq!(step.unwrap(), single.step(action).unwrap());
+    }
+}
+
+#[test]
+fn parses_spec() {
+    let spec = Spec::from_toml(
+        r#"
+            actions = [[], [0x1, 0x4]]
+            max-steps = 1000
+
+            [[reward]]
+            register = 0xE
+
+            [[reward]]
+            memory = 0x2F0
+            scale = -1.0
+
+            [[done]]
+            register = 0xE
+            at-least = 9
+        "#,
+    )
+    .unwrap();
+
+    assert_eq!(
+        spec,
+        Spec {
+            actions: vec![vec![], vec![0x1, 0x4]],
+            rewards: vec![
+                Reward {
+                    location: Location::Register(0xE),
+                    scale: 1.0,
+                },
+                Reward {
+                    location: Location::Memory(0x2F0),
+                    scale: -1.0,
+                },
+            ],
+            done: vec![Condition {
+                location: Location::Register(0xE),
+                test: Test::AtLeast(9),
+            }],
+            max_steps: Some(1000),
+        }
+    );
+}
+
+#[test]
+fn rejects_invalid_spec() {
+    let spec = Spec {
+        done: vec![Condition {
+            location: Location::Memory(0x1000),
+            test: Test::Equals(0),
+        }],
+        ..Spec::default()
+    };
+    assert_eq!(
+        Env::new(&COUNTER, spec).err(),
+        Some(Error::InvalidLocation(Location::Memory(0x1000)))
+    );
+
+    let spec = Spec {
+        actions: Vec::new(),
+        ..Spec::default()
+    };
+    assert_eq!(Env::new(&COUNTER, spec).err(), Some(Error::NoActions));
+}
+
+#[test]
+fn rewards_counters_that_wrap_around() {
+    let spec = Spec {
+        done: Vec::new(),
+        max_steps: Some(100),
+        ..counter_spec()
+    };
+    let mut env = Env::new(&COUNTER, spec).unwrap();
+    env.frame_skip = 1;
+
+    let mut total = 0.0;
+    let mut wraps = 0;
+    loop {
+        let before = env.machine().registers[1];
+        let step = env.step(1).unwrap();
+        if env.machine().registers[1] < before {
+            wraps += 1;
+        }
+        assert!(step.reward > 0.0);
+        total += step.reward;
+        if step.done {
+            break;
+        }
+    }
+    assert!(wraps > 0);
+    let count = wraps * 256 + env.machine().registers[1] as u32;
+    assert_eq!(total, count as f32 * 0.5);
+}
@@ -0,0 +1,34 @@
+use machine::SquareWave;
+
+pub const SAMPLE_RATE: f64 = 44100.0;
+
+const VOLUME: f32 = (i16::MAX / 5) as f32;
+
+/// Turns the machine's beep into the interleaved stereo frames libretro takes.
+pub struct Beeper {
+    wave: SquareWave,
+}
+
+impl Default for Beeper {
+    fn default() -> Self {
+        Self {
+            wave: SquareWave::new(SAMPLE_RATE as f32),
+        }
+    }
+}
+
+impl Beeper {
+    /// Fills `frames` with the beep on both channels if `playing`, or with silence
+    /// otherwise.
+    pub fn fill(&mut self, frames: &mut [[i16; 2]], playing: bool) {
+        if !playing {
+            frames.fill([0; 2]);
+            self.wave.reset();
+            return;
+        }
+
+        for frame in frames {
+            *frame = [(self.wave.next_sample() * VOLUME) as i16; 2];
+        }
+    }
+}
@@ -0,0 +1,112 @@
+use std::ffi::{c_void, CString};
+
+use libretro_sys::{Message, ENVIRONMENT_SET_MESSAGE};
+use machine::{Drivers, LoadError, Machine, Palette, DISPLAY_HEIGHT, DISPLAY_WIDTH};
+
+use crate::{
+    audio::{Beeper, SAMPLE_RATE},
+    options::Options,
+    Callbacks,
+};
+
+/// The number of stereo audio frames generated each video frame.
+const AUDIO_FRAMES: usize = (SAMPLE_RATE / 60.0) as usize;
+/// How long error messages stay on screen, in frames.
+const MESSAGE_FRAMES: u32 = 180;
+
+/// A loaded ROM and the machine running it.
+pub struct Game {
+    pub machine: Machine,
+    rom: Vec<u8>,
+    options: Options,
+    /// The display as XRGB8888 pixels, only redrawn where it's damaged.
+    pixels: Vec<u32>,
+    beeper: Beeper,
+    audio: Vec<[i16; 2]>,
+}
+
+impl Game {
+    pub fn new(rom: &[u8], options: Options) -> Result<Self, LoadError> {
+        Ok(Self {
+            machine: Machine::from_rom(rom, options.quirks, Drivers::noop())?,
+            rom: rom.to_vec(),
+            options,
+            pixels: vec![0; DISPLAY_WIDTH * DISPLAY_HEIGHT],
+            beeper: Beeper::default(),
+            audio: vec![[0; 2]; AUDIO_FRAMES],
+        })
+    }
+
+    /// Restarts the ROM from scratch, as if it had just been loaded.
+    pub fn reset(&mut self) {
+        // The ROM already loaded once, so it can't fail to load again
+        if let Ok(game) = Self::new(&self.rom, self.options) {
+            *self = game;
+        }
+    }
+
+    pub fn set_options(&mut self, options: Options) {
+        self.options = options;
+        self.machine.quirks = options.quirks;
+    }
+
+    pub fn run(&mut self, callbacks: &Callbacks) {
+        if let Some(input_state) = callbacks.input_state {
+            self.machine.keypad = crate::input::keypad(input_state);
+        }
+
+        if let Err(err) = self.machine.run_frame(self.options.cycles_per_frame) {
+            if let Some(environment) = callbacks.environment {
+                show_message(environment, &err.to_string());
+            }
+        }
+
+        let frame = self.machine.take_frame();
+        let palette = Palette::CLASSIC;
+        let [background, foreground] = [palette.background, palette.foreground]
+            .map(|color| u32::from_be_bytes([0, color.r, color.g, color.b]));
+        for y in frame.damage.rows() {
+            for x in 0..DISPLAY_WIDTH {
+                self.pixels[y * DISPLAY_WIDTH + x] = if frame.display.get(x, y) {
+                    foreground
+                } else {
+                    background
+                };
+            }
+        }
+        if let Some(video_refresh) = callbacks.video_refresh {
+            // SAFETY: `pixels` holds exactly one frame, and the frontend doesn't
+            // keep the pointer past the call
+            unsafe {
+                video_refresh(
+                    self.pixels.as_ptr() as *const c_void,
+                    DISPLAY_WIDTH as u32,
+                    DISPLAY_HEIGHT as u32,
+                    DISPLAY_WIDTH * size_of::<u32>(),
+                );
+            }
+        }
+
+        self.beeper.fill(&mut self.audio, self.machine.st > 0);
+        if let Some(audio_sample_batch) = callbacks.audio_sample_batch {
+            // SAFETY: `audio` holds `audio.len()` interleaved stereo frames
+            unsafe {
+                audio_sample_batch(self.audio.as_ptr() as *const i16, self.audio.len());
+            }
+        }
+    }
+}
+
+pub fn show_message(environment: crate::Environment, text: &str) {
+    let Ok(text) = CString::new(text) else {
+        return;
+    };
+    let message = Message {
+        msg: text.as_ptr(),
+        frames: MESSAGE_FRAMES,
+    };
+    // SAFETY: the frontend copies the message before returning
+    unsafe {
+        environment(ENVIRONMENT_SET_MESSAGE, &message as *const _ as *mut c_void);
+    }
+}
@@ -0,0 +1,101 @@
+use std::ffi::c_void;
+
+use libretro_sys::{
+    InputDescriptor, InputStateFn, Key, DEVICE_ID_JOYPAD_A, DEVICE_ID_JOYPAD_B,
+    DEVICE_ID_JOYPAD_DOWN, DEVICE_ID_JOYPAD_L, DEVICE_ID_JOYPAD_L2, DEVICE_ID_JOYPAD_L3,
+    DEVICE_ID_JOYPAD_LEFT, DEVICE_ID_JOYPAD_R, DEVICE_ID_JOYPAD_R2, DEVICE_ID_JOYPAD_R3,
+    DEVICE_ID_JOYPAD_RIGHT, DEVICE_ID_JOYPAD_SELECT, DEVICE_ID_JOYPAD_START, DEVICE_ID_JOYPAD_UP,
+    DEVICE_ID_JOYPAD_X, DEVICE_ID_JOYPAD_Y, DEVICE_JOYPAD, DEVICE_KEYBOARD,
+    ENVIRONMENT_SET_INPUT_DESCRIPTORS,
+};
+
+use crate::Environment;
+
+/// The CHIP-8 key pressed by each RetroPad button. Most games move with 2, 4, 6
+/// and 8 and act with 5, so those get the D-pad and A. Frontends let players remap
+/// the buttons per game.
+const JOYPAD: [(u32, u8, &std::ffi::CStr); 16] = [
+    (DEVICE_ID_JOYPAD_UP, 0x2, c"Key 2"),
+    (DEVICE_ID_JOYPAD_DOWN, 0x8, c"Key 8"),
+    (DEVICE_ID_JOYPAD_LEFT, 0x4, c"Key 4"),
+    (DEVICE_ID_JOYPAD_RIGHT, 0x6, c"Key 6"),
+    (DEVICE_ID_JOYPAD_A, 0x5, c"Key 5"),
+    (DEVICE_ID_JOYPAD_B, 0x0, c"Key 0"),
+    (DEVICE_ID_JOYPAD_X, 0x1, c"Key 1"),
+    (DEVICE_ID_JOYPAD_Y, 0x3, c"Key 3"),
+    (DEVICE_ID_JOYPAD_L, 0x7, c"Key 7"),
+    (DEVICE_ID_JOYPAD_R, 0x9, c"Key 9"),
+    (DEVICE_ID_JOYPAD_L2, 0xA, c"Key A"),
+    (DEVICE_ID_JOYPAD_R2, 0xB, c"Key B"),
+    (DEVICE_ID_JOYPAD_L3, 0xC, c"Key C"),
+    (DEVICE_ID_JOYPAD_R3, 0xD, c"Key D"),
+    (DEVICE_ID_JOYPAD_SELECT, 0xE, c"Key E"),
+    (DEVICE_ID_JOYPAD_START, 0xF, c"Key F"),
+];
+
+/// The keyboard uses the same 4x4 block as the other frontends, in keypad order.
+const KEYBOARD: [Key; 16] = [
+    Key::X,
+    Key::Number_1,
+    Key::Number_2,
+    Key::Number_3,
+    Key::Q,
+    Key::W,
+    Key::E,
+    Key::A,
+    Key::S,
+    Key::D,
+    Key::Z,
+    Key::C,
+    Key::Number_4,
+    Key::R,
+    Key::F,
+    Key::V,
+];
+
+/// Tells the frontend what each button does, for its remapping menu.
+pub fn declare(environment: Environment) {
+    let descriptors: Vec<_> = JOYPAD
+        .iter()
+        .map(|(id, _, description)| InputDescriptor {
+            port: 0,
+            device: DEVICE_JOYPAD,
+            index: 0,
+            id: *id,
+            description: description.as_ptr(),
+        })
+        .chain([InputDescriptor {
+            port: 0,
+            device: 0,
+            index: 0,
+            id: 0,
+            description: std::ptr::null(),
+        }])
+        .collect();
+    // SAFETY: the frontend copies the descriptors before returning
+    unsafe {
+        environment(
+            ENVIRONMENT_SET_INPUT_DESCRIPTORS,
+            descriptors.as_ptr() as *mut c_void,
+        );
+    }
+}
+
+/// Returns the state of the keypad from the first player's RetroPad and the
+/// keyboard, as a bitmask with bit `n` set if key `n` is held down.
+pub fn keypad(input_state: InputStateFn) -> u16 {
+    // SAFETY: the frontend's input callback is safe to call during `retro_run`
+    let pressed = |device, id| unsafe { input_state(0, device, 0, id) } != 0;
+
+    let joypad = JOYPAD
+        .iter()
+        .filter(|(id, _, _)| pressed(DEVICE_JOYPAD, *id))
+        .fold(0, |keypad, (_, key, _)| keypad | 1 << key);
+    let keyboard = KEYBOARD
+        .iter()
+        .enumerate()
+        .filter(|(_, key)| pressed(DEVICE_KEYBOARD, key.to_uint()))
+        .fold(0, |keypad, (key, _)| keypad | 1 << key);
+
+    joypad | keyboard
+}
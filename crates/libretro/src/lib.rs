@@ -0,0 +1,295 @@
+//! A libretro core, so that the emulator can run in RetroArch and other libretro
+//! frontends. See <https://docs.libretro.com/development/cores/developing-cores/>
+//! for what each function is for.
+
+mod audio;
+mod game;
+mod input;
+mod options;
+
+use std::{
+    ffi::{c_char, c_uint, c_void},
+    sync::{Mutex, MutexGuard},
+};
+
+use libretro_sys::{
+    AudioSampleBatchFn, AudioSampleFn, EnvironmentFn, GameGeometry, GameInfo, InputPollFn,
+    InputStateFn, PixelFormat, Region, SystemAvInfo, SystemInfo, SystemTiming, VideoRefreshFn,
+    API_VERSION, ENVIRONMENT_GET_VARIABLE_UPDATE, ENVIRONMENT_SET_PIXEL_FORMAT, MEMORY_SYSTEM_RAM,
+};
+use machine::{state_size, DISPLAY_HEIGHT, DISPLAY_WIDTH, MEMORY_SIZE, STACK_DEPTH};
+
+use audio::SAMPLE_RATE;
+use game::{show_message, Game};
+use options::Options;
+
+type Environment = EnvironmentFn;
+
+/// The functions the frontend has given us to talk back to it.
+pub struct Callbacks {
+    environment: Option<EnvironmentFn>,
+    video_refresh: Option<VideoRefreshFn>,
+    audio_sample_batch: Option<AudioSampleBatchFn>,
+    input_poll: Option<InputPollFn>,
+    input_state: Option<InputStateFn>,
+}
+
+/// Everything the core keeps between calls. The libretro API is a set of free
+/// functions, so this lives in a global.
+struct Core {
+    callbacks: Callbacks,
+    game: Option<Game>,
+}
+
+static CORE: Mutex<Core> = Mutex::new(Core {
+    callbacks: Callbacks {
+        environment: None,
+        video_refresh: None,
+        audio_sample_batch: None,
+        input_poll: None,
+        input_state: None,
+    },
+    game: None,
+});
+
+fn core() -> MutexGuard<'static, Core> {
+    // A panic can't unwind across the FFI boundary anyway, so a poisoned lock just
+    // means a previous call aborted partway through
+    CORE.lock().unwrap_or_else(|err| err.into_inner())
+}
+
+#[no_mangle]
+pub extern "C" fn retro_api_version() -> c_uint {
+    API_VERSION
+}
+
+#[no_mangle]
+pub extern "C" fn retro_set_environment(environment: EnvironmentFn) {
+    core().callbacks.environment = Some(environment);
+    Options::declare(environment);
+    input::declare(environment);
+}
+
+#[no_mangle]
+pub extern "C" fn retro_set_video_refresh(video_refresh: VideoRefreshFn) {
+    core().callbacks.video_refresh = Some(video_refresh);
+}
+
+/// Unused, since all of a frame's audio is sent at once with the batch callback.
+#[no_mangle]
+pub extern "C" fn retro_set_audio_sample(_audio_sample: AudioSampleFn) {}
+
+#[no_mangle]
+pub extern "C" fn retro_set_audio_sample_batch(audio_sample_batch: AudioSampleBatchFn) {
+    core().callbacks.audio_sample_batch = Some(audio_sample_batch);
+}
+
+#[no_mangle]
+pub extern "C" fn retro_set_input_poll(input_poll: InputPollFn) {
+    core().callbacks.input_poll = Some(input_poll);
+}
+
+#[no_mangle]
+pub extern "C" fn retro_set_input_state(input_state: InputStateFn) {
+    core().callbacks.input_state = Some(input_state);
+}
+
+#[no_mangle]
+pub extern "C" fn retro_init() {}
+
+#[no_mangle]
+pub extern "C" fn retro_deinit() {
+    core().game = None;
+}
+
+/// # Safety
+///
+/// `info` must point to a `retro_system_info` to fill in.
+#[no_mangle]
+pub unsafe extern "C" fn retro_get_system_info(info: *mut SystemInfo) {
+    info.write(SystemInfo {
+        library_name: c"CHIP-8".as_ptr(),
+        library_version: concat!(env!("CARGO_PKG_VERSION"), "\0").as_ptr() as *const c_char,
+        valid_extensions: c"ch8|c8".as_ptr(),
+        need_fullpath: false,
+        block_extract: false,
+    });
+}
+
+/// # Safety
+///
+/// `info` must point to a `retro_system_av_info` to fill in.
+#[no_mangle]
+pub unsafe extern "C" fn retro_get_system_av_info(info: *mut SystemAvInfo) {
+    info.write(SystemAvInfo {
+        geometry: GameGeometry {
+            base_width: DISPLAY_WIDTH as c_uint,
+            base_height: DISPLAY_HEIGHT as c_uint,
+            max_width: DISPLAY_WIDTH as c_uint,
+            max_height: DISPLAY_HEIGHT as c_uint,
+            aspect_ratio: DISPLAY_WIDTH as f32 / DISPLAY_HEIGHT as f32,
+        },
+        timing: SystemTiming {
+            fps: 60.0,
+            sample_rate: SAMPLE_RATE,
+        },
+    });
+}
+
+#[no_mangle]
+pub extern "C" fn retro_set_controller_port_device(_port: c_uint, _device: c_uint) {}
+
+#[no_mangle]
+pub extern "C" fn retro_reset() {
+    if let Some(game) = &mut core().game {
+        game.reset();
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn retro_run() {
+    let core = &mut *core();
+    let Some(game) = &mut core.game else {
+        return;
+    };
+
+    if let Some(environment) = core.callbacks.environment {
+        let mut updated = false;
+        // SAFETY: GET_VARIABLE_UPDATE writes a bool
+        unsafe {
+            environment(
+                ENVIRONMENT_GET_VARIABLE_UPDATE,
+                &mut updated as *mut bool as *mut c_void,
+            );
+        }
+        if updated {
+            game.set_options(Options::load(environment));
+        }
+    }
+
+    if let Some(input_poll) = core.callbacks.input_poll {
+        // SAFETY: the frontend's callbacks are safe to call during `retro_run`
+        unsafe { input_poll() };
+    }
+    game.run(&core.callbacks);
+}
+
+/// Large enough for any save state, which frontends need to know up front.
+#[no_mangle]
+pub extern "C" fn retro_serialize_size() -> usize {
+    state_size(STACK_DEPTH)
+}
+
+/// # Safety
+///
+/// `data` must point to `size` writable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn retro_serialize(data: *mut c_void, size: usize) -> bool {
+    let Some(game) = &core().game else {
+        return false;
+    };
+
+    let state = game.machine.save_state();
+    let Some(padding) = size.checked_sub(state.len()) else {
+        return false;
+    };
+    let data = data as *mut u8;
+    data.copy_from_nonoverlapping(state.as_ptr(), state.len());
+    data.add(state.len()).write_bytes(0, padding);
+
+    true
+}
+
+/// # Safety
+///
+/// `data` must point to `size` readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn retro_unserialize(data: *const c_void, size: usize) -> bool {
+    let Some(game) = &mut core().game else {
+        return false;
+    };
+
+    let state = std::slice::from_raw_parts(data as *const u8, size);
+    game.machine.load_state(state).is_ok()
+}
+
+#[no_mangle]
+pub extern "C" fn retro_cheat_reset() {}
+
+#[no_mangle]
+pub extern "C" fn retro_cheat_set(_index: c_uint, _enabled: bool, _code: *const c_char) {}
+
+/// # Safety
+///
+/// `game` must be null or point to a valid `retro_game_info`, with `size` bytes
+/// of ROM at `data`.
+#[no_mangle]
+pub unsafe extern "C" fn retro_load_game(game: *const GameInfo) -> bool {
+    let core = &mut *core();
+    let Some(environment) = core.callbacks.environment else {
+        return false;
+    };
+    let Some(game) = game.as_ref() else {
+        return false;
+    };
+    if game.data.is_null() {
+        return false;
+    }
+
+    let mut format = PixelFormat::ARGB8888;
+    if !environment(
+        ENVIRONMENT_SET_PIXEL_FORMAT,
+        &mut format as *mut PixelFormat as *mut c_void,
+    ) {
+        return false;
+    }
+
+    let rom = std::slice::from_raw_parts(game.data as *const u8, game.size);
+    match Game::new(rom, Options::load(environment)) {
+        Ok(game) => {
+            core.game = Some(game);
+            true
+        }
+        Err(err) => {
+            show_message(environment, &err.to_string());
+            false
+        }
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn retro_load_game_special(
+    _game_type: c_uint,
+    _info: *const GameInfo,
+    _num_info: usize,
+) -> bool {
+    false
+}
+
+#[no_mangle]
+pub extern "C" fn retro_unload_game() {
+    core().game = None;
+}
+
+#[no_mangle]
+pub extern "C" fn retro_get_region() -> c_uint {
+    Region::NTSC.to_uint()
+}
+
+/// Exposes the machine's memory, for frontends' cheat search and achievements.
+#[no_mangle]
+pub extern "C" fn retro_get_memory_data(id: c_uint) -> *mut c_void {
+    match &mut core().game {
+        // The game lives in a static until it's unloaded, so the pointer stays valid
+        Some(game) if id == MEMORY_SYSTEM_RAM => game.machine.memory.as_mut_ptr() as *mut c_void,
+        _ => std::ptr::null_mut(),
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn retro_get_memory_size(id: c_uint) -> usize {
+    match &core().game {
+        Some(_) if id == MEMORY_SYSTEM_RAM => MEMORY_SIZE,
+        _ => 0,
+    }
+}
@@ -0,0 +1,93 @@
+use std::ffi::{c_void, CStr};
+
+use libretro_sys::{Variable, ENVIRONMENT_GET_VARIABLE, ENVIRONMENT_SET_VARIABLES};
+use machine::{Quirks, CYCLES_PER_SECOND};
+
+use crate::Environment;
+
+const QUIRKS_KEY: &CStr = c"chip8_quirks";
+const CYCLES_PER_FRAME_KEY: &CStr = c"chip8_cycles_per_frame";
+
+/// The settings shown in the frontend's core options menu.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Options {
+    pub quirks: Quirks,
+    pub cycles_per_frame: usize,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            quirks: Quirks::modern_chip8(),
+            cycles_per_frame: CYCLES_PER_SECOND / 60,
+        }
+    }
+}
+
+impl Options {
+    /// Tells the frontend which options there are. The first value of each is the
+    /// default, and must match [`Options::default`].
+    pub fn declare(environment: Environment) {
+        let variables = [
+            Variable {
+                key: QUIRKS_KEY.as_ptr(),
+                value: c"Quirks; modern|cosmac-vip|superchip".as_ptr(),
+            },
+            Variable {
+                key: CYCLES_PER_FRAME_KEY.as_ptr(),
+                value: c"Cycles per frame; 11|8|15|20|30|50|100|200|500|1000".as_ptr(),
+            },
+            Variable {
+                key: std::ptr::null(),
+                value: std::ptr::null(),
+            },
+        ];
+        // SAFETY: the frontend copies the variables before returning
+        unsafe {
+            environment(ENVIRONMENT_SET_VARIABLES, variables.as_ptr() as *mut c_void);
+        }
+    }
+
+    /// Reads the current value of each option from the frontend, falling back to
+    /// the default for any it doesn't know about.
+    pub fn load(environment: Environment) -> Self {
+        let default = Self::default();
+        let quirks = match get(environment, QUIRKS_KEY).as_deref() {
+            Some("cosmac-vip") => Quirks::cosmac_vip(),
+            Some("superchip") => Quirks::superchip(),
+            Some("modern") => Quirks::modern_chip8(),
+            _ => default.quirks,
+        };
+        let cycles_per_frame = get(environment, CYCLES_PER_FRAME_KEY)
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(default.cycles_per_frame);
+
+        Self {
+            quirks,
+            cycles_per_frame,
+        }
+    }
+}
+
+fn get(environment: Environment, key: &CStr) -> Option<String> {
+    let mut variable = Variable {
+        key: key.as_ptr(),
+        value: std::ptr::null(),
+    };
+    // SAFETY: the frontend either leaves `value` null or points it at a string that
+    // lives until the next call, which is copied straight away
+    unsafe {
+        if !environment(
+            ENVIRONMENT_GET_VARIABLE,
+            &mut variable as *mut _ as *mut c_void,
+        ) || variable.value.is_null()
+        {
+            return None;
+        }
+        Some(
+            CStr::from_ptr(variable.value)
+                .to_string_lossy()
+                .into_owned(),
+        )
+    }
+}
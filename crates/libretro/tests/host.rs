@@ -0,0 +1,271 @@
+//! Loads the core's shared library and drives it through the libretro API, the way
+//! a frontend like RetroArch would.
+
+use std::{
+    ffi::{c_uint, c_void, CStr},
+    sync::{
+        atomic::{AtomicBool, AtomicU32, Ordering},
+        Mutex, MutexGuard, OnceLock,
+    },
+};
+
+use libloading::Library;
+use libretro_sys::{
+    AudioSampleBatchFn, AudioSampleFn, EnvironmentFn, GameInfo, InputPollFn, InputStateFn,
+    Variable, VideoRefreshFn, DEVICE_ID_JOYPAD_A, DEVICE_JOYPAD, ENVIRONMENT_GET_VARIABLE,
+    ENVIRONMENT_GET_VARIABLE_UPDATE, ENVIRONMENT_SET_INPUT_DESCRIPTORS,
+    ENVIRONMENT_SET_PIXEL_FORMAT, ENVIRONMENT_SET_VARIABLES, MEMORY_SYSTEM_RAM,
+};
+
+const IBM_LOGO: &[u8] = include_bytes!("../../../roms/ibm-logo.ch8");
+
+/// The core keeps its state in globals, and every test shares the same loaded
+/// library, so only one test can use it at a time.
+static LOCK: Mutex<()> = Mutex::new(());
+
+/// The value the frontend reports for the cycles per frame option, if any.
+static CYCLES_PER_FRAME: Mutex<Option<&CStr>> = Mutex::new(None);
+static DECLARED_OPTIONS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+static FRAME: Mutex<Vec<u32>> = Mutex::new(Vec::new());
+static AUDIO: Mutex<Vec<i16>> = Mutex::new(Vec::new());
+/// Bitmask of the RetroPad buttons held down.
+static BUTTONS: AtomicU32 = AtomicU32::new(0);
+static POLLED: AtomicBool = AtomicBool::new(false);
+
+unsafe extern "C" fn environment(cmd: c_uint, data: *mut c_void) -> bool {
+    match cmd {
+        ENVIRONMENT_SET_PIXEL_FORMAT | ENVIRONMENT_SET_INPUT_DESCRIPTORS => true,
+        ENVIRONMENT_SET_VARIABLES => {
+            let mut variable = data as *const Variable;
+            let mut declared = DECLARED_OPTIONS.lock().unwrap();
+            while !(*variable).key.is_null() {
+                declared.push(CStr::from_ptr((*variable).key).to_str().unwrap().to_owned());
+                variable = variable.add(1);
+            }
+            true
+        }
+        ENVIRONMENT_GET_VARIABLE => {
+            let variable = &mut *(data as *mut Variable);
+            let value = *CYCLES_PER_FRAME.lock().unwrap();
+            match value {
+                Some(value) if CStr::from_ptr(variable.key) == c"chip8_cycles_per_frame" => {
+                    variable.value = value.as_ptr();
+                    true
+                }
+                _ => false,
+            }
+        }
+        ENVIRONMENT_GET_VARIABLE_UPDATE => {
+            *(data as *mut bool) = false;
+            true
+        }
+        _ => false,
+    }
+}
+
+unsafe extern "C" fn video_refresh(
+    data: *const c_void,
+    width: c_uint,
+    height: c_uint,
+    pitch: usize,
+) {
+    assert_eq!(pitch, width as usize * 4);
+    let pixels = std::slice::from_raw_parts(data as *const u32, (width * height) as usize);
+    *FRAME.lock().unwrap() = pixels.to_vec();
+}
+
+unsafe extern "C" fn audio_sample(_left: i16, _right: i16) {}
+
+unsafe extern "C" fn audio_sample_batch(data: *const i16, frames: usize) -> usize {
+    *AUDIO.lock().unwrap() = std::slice::from_raw_parts(data, frames * 2).to_vec();
+    frames
+}
+
+unsafe extern "C" fn input_poll() {
+    POLLED.store(true, Ordering::SeqCst);
+}
+
+unsafe extern "C" fn input_state(port: c_uint, device: c_uint, _index: c_uint, id: c_uint) -> i16 {
+    let pressed =
+        port == 0 && device == DEVICE_JOYPAD && BUTTONS.load(Ordering::SeqCst) & (1 << id) != 0;
+    pressed as i16
+}
+
+/// Looks up a function exported by the core, loading it the first time.
+///
+/// The library is never unloaded, since the Rust runtime inside it can't be torn
+/// down safely while the test harness's threads are still running.
+///
+/// # Safety
+///
+/// `F` must be the function's actual signature.
+unsafe fn api<F: Copy>(name: &str) -> F {
+    static LIBRARY: OnceLock<Library> = OnceLock::new();
+    let library = LIBRARY.get_or_init(|| {
+        // Cargo builds the core into the same directory as the test binaries
+        let path = std::env::current_exe()
+            .unwrap()
+            .with_file_name(libloading::library_filename("chip8_libretro"));
+        Library::new(&path).unwrap_or_else(|err| panic!("Failed to load {}: {err}", path.display()))
+    });
+
+    *library
+        .get::<F>(name.as_bytes())
+        .unwrap_or_else(|err| panic!("Missing {name}: {err}"))
+}
+
+/// A ROM loaded into the core, which is unloaded again when this is dropped.
+struct Core {
+    _lock: MutexGuard<'static, ()>,
+}
+
+impl Core {
+    /// Sets up the callbacks and loads `rom`.
+    fn load(rom: &[u8]) -> Self {
+        let lock = LOCK.lock().unwrap_or_else(|err| err.into_inner());
+        DECLARED_OPTIONS.lock().unwrap().clear();
+        FRAME.lock().unwrap().clear();
+        AUDIO.lock().unwrap().clear();
+        BUTTONS.store(0, Ordering::SeqCst);
+
+        unsafe {
+            assert_eq!(
+                api::<unsafe extern "C" fn() -> c_uint>("retro_api_version")(),
+                1
+            );
+            api::<unsafe extern "C" fn(EnvironmentFn)>("retro_set_environment")(environment);
+            api::<unsafe extern "C" fn(VideoRefreshFn)>("retro_set_video_refresh")(video_refresh);
+            api::<unsafe extern "C" fn(AudioSampleFn)>("retro_set_audio_sample")(audio_sample);
+            api::<unsafe extern "C" fn(AudioSampleBatchFn)>("retro_set_audio_sample_batch")(
+                audio_sample_batch,
+            );
+            api::<unsafe extern "C" fn(InputPollFn)>("retro_set_input_poll")(input_poll);
+            api::<unsafe extern "C" fn(InputStateFn)>("retro_set_input_state")(input_state);
+            api::<unsafe extern "C" fn()>("retro_init")();
+
+            let game = GameInfo {
+                path: std::ptr::null(),
+                data: rom.as_ptr() as *const c_void,
+                size: rom.len(),
+                meta: std::ptr::null(),
+            };
+            assert!(api::<unsafe extern "C" fn(*const GameInfo) -> bool>(
+                "retro_load_game"
+            )(&game));
+        }
+
+        Self { _lock: lock }
+    }
+
+    fn run(&self, frames: usize) {
+        for _ in 0..frames {
+            unsafe { api::<unsafe extern "C" fn()>("retro_run")() };
+        }
+    }
+
+    fn serialize(&self) -> Vec<u8> {
+        unsafe {
+            let size = api::<unsafe extern "C" fn() -> usize>("retro_serialize_size")();
+            let mut state = vec![0xFF; size];
+            assert!(api::<unsafe extern "C" fn(*mut c_void, usize) -> bool>(
+                "retro_serialize"
+            )(state.as_mut_ptr() as *mut c_void, size));
+            state
+        }
+    }
+
+    fn unserialize(&self, state: &[u8]) -> bool {
+        unsafe {
+            api::<unsafe extern "C" fn(*const c_void, usize) -> bool>("retro_unserialize")(
+                state.as_ptr() as *const c_void,
+                state.len(),
+            )
+        }
+    }
+
+    fn memory(&self) -> &[u8] {
+        unsafe {
+            let data = api::<unsafe extern "C" fn(c_uint) -> *mut c_void>("retro_get_memory_data")(
+                MEMORY_SYSTEM_RAM,
+            );
+            let size = api::<unsafe extern "C" fn(c_uint) -> usize>("retro_get_memory_size")(
+                MEMORY_SYSTEM_RAM,
+            );
+            std::slice::from_raw_parts(data as *const u8, size)
+        }
+    }
+}
+
+impl Drop for Core {
+    fn drop(&mut self) {
+        unsafe {
+            api::<unsafe extern "C" fn()>("retro_unload_game")();
+            api::<unsafe extern "C" fn()>("retro_deinit")();
+        }
+    }
+}
+
+fn pixel(x: usize, y: usize) -> u32 {
+    FRAME.lock().unwrap()[y * 64 + x]
+}
+
+#[test]
+fn draws_ibm_logo() {
+    let core = Core::load(IBM_LOGO);
+    core.run(10);
+
+    assert!(POLLED.load(Ordering::SeqCst));
+    assert_eq!(FRAME.lock().unwrap().len(), 64 * 32);
+    // The top left corner of the "I" is lit white, and the corner of the screen isn't
+    assert_eq!(pixel(12, 8), 0xFFFFFF);
+    assert_eq!(pixel(0, 0), 0x000000);
+    // One frame of silence at 44.1 kHz
+    assert_eq!(AUDIO.lock().unwrap().len(), 735 * 2);
+
+    assert_eq!(core.memory().len(), 4096);
+    assert_eq!(&core.memory()[0x200..0x200 + IBM_LOGO.len()], IBM_LOGO);
+}
+
+#[test]
+fn restores_save_states() {
+    let core = Core::load(IBM_LOGO);
+    core.run(1);
+    let state = core.serialize();
+
+    core.run(10);
+    assert_ne!(core.serialize(), state);
+    assert!(core.unserialize(&state));
+    assert_eq!(core.serialize(), state);
+
+    assert!(!core.unserialize(b"not a state"));
+}
+
+#[test]
+fn reads_core_options() {
+    *CYCLES_PER_FRAME.lock().unwrap() = Some(c"1");
+    let core = Core::load(IBM_LOGO);
+    *CYCLES_PER_FRAME.lock().unwrap() = None;
+
+    assert_eq!(
+        *DECLARED_OPTIONS.lock().unwrap(),
+        ["chip8_quirks", "chip8_cycles_per_frame"]
+    );
+
+    // The logo starts drawing with the fifth instruction, which at one cycle per
+    // frame hasn't run yet
+    core.run(4);
+    assert!(FRAME.lock().unwrap().iter().all(|pixel| *pixel == 0));
+}
+
+#[test]
+fn reads_joypad() {
+    // FX0A into V0, then FX18 to start the sound timer for V0 ticks, then loop
+    let core = Core::load(&[0xF0, 0x0A, 0xF0, 0x18, 0x12, 0x04]);
+    core.run(2);
+    assert!(AUDIO.lock().unwrap().iter().all(|sample| *sample == 0));
+
+    // A is mapped to key 5
+    BUTTONS.store(1 << DEVICE_ID_JOYPAD_A, Ordering::SeqCst);
+    core.run(2);
+    assert!(AUDIO.lock().unwrap().iter().any(|sample| *sample != 0));
+}
@@ -0,0 +1,44 @@
+//! Compares running ROMs through the plain interpreter with the block cache.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use machine::{Drivers, Machine, Quirks};
+
+/// Enough cycles per frame that instruction dispatch dominates the frame overhead.
+const CYCLES_PER_FRAME: usize = 1000;
+
+const ROMS: &[(&str, &[u8])] = &[
+    ("ibm-logo", include_bytes!("../../../roms/ibm-logo.ch8")),
+    ("coraxplus", include_bytes!("../../../roms/coraxplus.ch8")),
+    ("flags", include_bytes!("../../../roms/flags.ch8")),
+    // A tight loop of arithmetic with no drawing
+    (
+        "arithmetic",
+        &[
+            0x60, 0x00, // V0 = 0
+            0x70, 0x01, // V0 += 1
+            0x81, 0x04, // V1 += V0
+            0x82, 0x12, // V2 &= V1
+            0x83, 0x23, // V3 ^= V2
+            0x12, 0x02, // Jump to 0x202
+        ],
+    ),
+];
+
+fn run_frames(c: &mut Criterion) {
+    let mut group = c.benchmark_group("run_frame");
+    for (name, rom) in ROMS {
+        for (mode, cached) in [("interpreter", false), ("block_cache", true)] {
+            let mut machine =
+                Machine::from_rom(rom, Quirks::modern_chip8(), Drivers::noop()).unwrap();
+            machine.set_block_cache(cached);
+
+            group.bench_function(BenchmarkId::new(mode, name), |b| {
+                b.iter(|| machine.run_frame(CYCLES_PER_FRAME).unwrap())
+            });
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, run_frames);
+criterion_main!(benches);
@@ -0,0 +1,122 @@
+//! Measures how many instructions per second `Machine::cycle` gets through, and
+//! how fast snapshots are, as a baseline for changes to the display or dispatch.
+
+use std::time::Duration;
+
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion, Throughput};
+use machine::{Drivers, Machine, Quirks};
+
+/// The number of instructions run from a freshly loaded ROM in each iteration.
+const CYCLES: u64 = 10_000;
+
+const ROMS: &[(&str, &[u8])] = &[
+    ("coraxplus", include_bytes!("../../../roms/coraxplus.ch8")),
+    ("flags", include_bytes!("../../../roms/flags.ch8")),
+    ("ibm-logo", include_bytes!("../../../roms/ibm-logo.ch8")),
+    ("keypad", include_bytes!("../../../roms/keypad.ch8")),
+    ("pong", include_bytes!("../../../roms/pong.ch8")),
+    ("quirks", include_bytes!("../../../roms/quirks.ch8")),
+    ("splash", include_bytes!("../../../roms/splash.ch8")),
+];
+
+const DRAW_ROMS: &[(&str, &[u8])] = &[
+    // Full height sprites stepping across the screen, mostly straddling two bytes
+    // of a row and sometimes clipped at the bottom
+    (
+        "sprites",
+        &[
+            0x60, 0x00, // V0 = 0
+            0x61, 0x00, // V1 = 0
+            0xA2, 0x0E, // I = 0x20E
+            0xD0, 0x1F, // Draw 15 rows at V0, V1
+            0x70, 0x05, // V0 += 5
+            0x71, 0x03, // V1 += 3
+            0x12, 0x04, // Jump to 0x204
+            0xFF, 0x81, 0xBD, 0xA5, 0xA5, 0xBD, 0x81, 0xFF, // Sprite
+            0x81, 0xBD, 0xA5, 0xA5, 0xBD, 0x81, 0xFF,
+        ],
+    ),
+    // The same sprite over and over in the bottom right corner, clipped on both axes
+    (
+        "clipped",
+        &[
+            0x60, 0x3C, // V0 = 60
+            0x61, 0x1C, // V1 = 28
+            0xA2, 0x0A, // I = 0x20A
+            0xD0, 0x1F, // Draw 15 rows at V0, V1
+            0x12, 0x06, // Jump to 0x206
+            0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, // Sprite
+            0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF,
+        ],
+    ),
+];
+
+/// Stores and loads every register, over and over.
+const STORE_LOAD_ROM: &[u8] = &[
+    0xA3, 0x00, // I = 0x300
+    0xFF, 0x55, // Store V0 to VF
+    0xFF, 0x65, // Load V0 to VF
+    0x12, 0x02, // Jump to 0x202
+];
+
+fn machine(rom: &[u8]) -> Machine {
+    let mut machine = Machine::from_rom(rom, Quirks::modern_chip8(), Drivers::noop()).unwrap();
+    machine.rng.seed(1);
+    machine
+}
+
+/// Benchmarks running `CYCLES` instructions of each ROM, starting from scratch
+/// every time so that every iteration does the same work.
+fn bench_cycles(c: &mut Criterion, group: &str, roms: &[(&str, &[u8])]) {
+    let mut group = c.benchmark_group(group);
+    group.throughput(Throughput::Elements(CYCLES));
+    for (name, rom) in roms {
+        group.bench_function(BenchmarkId::from_parameter(name), |b| {
+            b.iter_batched_ref(
+                || machine(rom),
+                |machine| {
+                    for _ in 0..CYCLES {
+                        machine.cycle().unwrap();
+                    }
+                },
+                BatchSize::SmallInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+fn roms(c: &mut Criterion) {
+    bench_cycles(c, "roms", ROMS);
+}
+
+fn draw(c: &mut Criterion) {
+    bench_cycles(c, "draw", DRAW_ROMS);
+}
+
+fn store_load(c: &mut Criterion) {
+    bench_cycles(c, "store_load", &[("registers", STORE_LOAD_ROM)]);
+}
+
+fn snapshot(c: &mut Criterion) {
+    let mut machine = machine(include_bytes!("../../../roms/ibm-logo.ch8"));
+    machine.run_frame(100).unwrap();
+    let state = machine.save_state();
+
+    let mut group = c.benchmark_group("snapshot");
+    group.throughput(Throughput::Bytes(state.len() as u64));
+    group.bench_function("save", |b| b.iter(|| machine.save_state()));
+    group.bench_function("load", |b| b.iter(|| machine.load_state(&state).unwrap()));
+    group.finish();
+}
+
+criterion_group! {
+    name = benches;
+    // Longer runs and a stricter noise threshold than the defaults, so that
+    // regressions of a few percent stand out
+    config = Criterion::default()
+        .measurement_time(Duration::from_secs(10))
+        .noise_threshold(0.02);
+    targets = roms, draw, store_load, snapshot
+}
+criterion_main!(benches);
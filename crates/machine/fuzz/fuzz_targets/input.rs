@@ -0,0 +1,61 @@
+//! Runs an arbitrary ROM with arbitrary quirks, feeding it a sequence of keypad
+//! states one frame at a time.
+
+#![no_main]
+
+use arbitrary::Arbitrary;
+use libfuzzer_sys::fuzz_target;
+use machine::{Machine, Platform, Quirks};
+use machine_fuzz::{assert_invariants, MAX_CYCLES};
+
+#[derive(Arbitrary, Debug)]
+struct Input {
+    quirks: [bool; 4],
+    block_cache: bool,
+    cdp1802: bool,
+    vip_memory_map: bool,
+    chip8x: bool,
+    rom: Vec<u8>,
+    /// The keys held down and the number of cycles to run for each frame.
+    frames: Vec<(u16, u8)>,
+}
+
+fuzz_target!(|input: Input| {
+    let [set_vx_to_vy, fx_incr_index, set_vf_on_fx1e_overflow, bxnn] = input.quirks;
+    let quirks = Quirks {
+        set_vx_to_vy,
+        fx_incr_index,
+        set_vf_on_fx1e_overflow,
+        bxnn,
+    };
+    let platform = if input.chip8x {
+        Platform::Chip8X
+    } else {
+        Platform::Chip8
+    };
+    let Ok(mut machine) = Machine::builder(&input.rom)
+        .platform(platform)
+        .quirks(quirks)
+        .vip_memory_map(input.vip_memory_map)
+        .cdp1802(input.cdp1802)
+        .build()
+    else {
+        return;
+    };
+    machine.rng.seed(0);
+    machine.set_block_cache(input.block_cache);
+
+    let mut cycles = 0;
+    for (keypad, frame_cycles) in input.frames {
+        machine.keypad = keypad;
+        if machine.run_frame(frame_cycles as usize).is_err() {
+            break;
+        }
+        assert_invariants(&machine);
+
+        cycles += frame_cycles as usize;
+        if cycles > MAX_CYCLES {
+            break;
+        }
+    }
+});
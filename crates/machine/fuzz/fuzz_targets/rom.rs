@@ -0,0 +1,23 @@
+//! Loads arbitrary bytes as a ROM and runs them with the default quirks.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use machine::{Drivers, Machine, Quirks};
+use machine_fuzz::{assert_invariants, MAX_CYCLES};
+
+fuzz_target!(|rom: &[u8]| {
+    // ROMs that don't fit are rejected up front, which is fine
+    let Ok(mut machine) = Machine::from_rom(rom, Quirks::modern_chip8(), Drivers::noop()) else {
+        return;
+    };
+    machine.rng.seed(0);
+
+    for _ in 0..MAX_CYCLES {
+        // Unknown opcodes and stack errors are reported, never panics
+        if machine.cycle().is_err() {
+            break;
+        }
+        assert_invariants(&machine);
+    }
+});
@@ -0,0 +1,24 @@
+//! Shared checks for the fuzz targets.
+
+use machine::{Machine, DISPLAY_HEIGHT, DISPLAY_WIDTH, MEMORY_SIZE, STACK_DEPTH};
+
+/// The upper bound on cycles per run, so that ROMs that loop forever still finish.
+pub const MAX_CYCLES: usize = 10_000;
+
+/// Panics if `machine` is in a state that no sequence of instructions should reach.
+pub fn assert_invariants(machine: &Machine) {
+    assert!(
+        (machine.pc as usize) < MEMORY_SIZE,
+        "PC {:04X} is outside memory",
+        machine.pc
+    );
+    assert!(
+        machine.stack.len() <= STACK_DEPTH,
+        "the stack grew to {} entries",
+        machine.stack.len()
+    );
+    assert_eq!(
+        (machine.display.width(), machine.display.height()),
+        (DISPLAY_WIDTH, DISPLAY_HEIGHT)
+    );
+}
@@ -0,0 +1,50 @@
+//! The tone that frontends play while the sound timer is running.
+
+/// The pitch of the beep, in Hz.
+pub const BEEP_FREQUENCY: f32 = 440.0;
+
+/// A square wave at [`BEEP_FREQUENCY`], for frontends that generate their own
+/// audio samples rather than starting and stopping a sound through an
+/// [`AudioDriver`](crate::AudioDriver).
+///
+/// ```
+/// # use machine::SquareWave;
+/// let mut wave = SquareWave::new(44100.0);
+/// let samples: Vec<f32> = (0..100).map(|_| wave.next_sample()).collect();
+/// // At 44.1 kHz, each period of a 440 Hz wave is just over 100 samples long
+/// assert!(samples[..51].iter().all(|sample| *sample == 1.0));
+/// assert!(samples[51..].iter().all(|sample| *sample == -1.0));
+/// ```
+#[derive(Clone, Debug)]
+pub struct SquareWave {
+    /// Samples per second, which the frontend can change when its output does.
+    pub sample_rate: f32,
+    /// The fraction of the current period that's been played.
+    phase: f32,
+}
+
+impl SquareWave {
+    pub const fn new(sample_rate: f32) -> Self {
+        Self {
+            sample_rate,
+            phase: 0.0,
+        }
+    }
+
+    /// Returns the next sample: 1.0 for the first half of each period, and -1.0
+    /// for the second. Frontends scale it to their own volume and sample format.
+    pub fn next_sample(&mut self) -> f32 {
+        let sample = if self.phase < 0.5 { 1.0 } else { -1.0 };
+        self.phase += BEEP_FREQUENCY / self.sample_rate;
+        if self.phase >= 1.0 {
+            self.phase -= 1.0;
+        }
+        sample
+    }
+
+    /// Goes back to the start of a period, so that every beep starts the same way.
+    /// Call this while the sound timer isn't running.
+    pub fn reset(&mut self) {
+        self.phase = 0.0;
+    }
+}
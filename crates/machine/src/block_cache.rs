@@ -0,0 +1,98 @@
+use alloc::{vec, vec::Vec};
+use core::ops::Range;
+
+use crate::{Instruction, Platform, MEMORY_SIZE};
+
+/// The most instructions decoded into a single block, so that long straight-line
+/// runs don't have to be decoded all at once.
+const MAX_BLOCK_LEN: usize = 64;
+
+/// Marks an address that no cached block starts at.
+const NO_BLOCK: u16 = u16::MAX;
+
+/// Caches the decoded instructions of each basic block, keyed by its start address,
+/// so that loops don't decode the same opcodes over and over.
+///
+/// A block runs until the first instruction that can go somewhere other than the
+/// next instruction. Every address covered by a block is tracked, so that the
+/// whole cache can be thrown away when the ROM writes over its own code.
+pub(crate) struct BlockCache {
+    blocks: Vec<Vec<Instruction>>,
+    /// The index in `blocks` of the block starting at each address, or `NO_BLOCK`.
+    starts: Vec<u16>,
+    /// Bit `n` is set if address `n` is part of a cached block.
+    code: [u64; MEMORY_SIZE / 64],
+    /// The machine's addressable memory, which writes wrap around.
+    memory_size: usize,
+    /// Decides how opcodes are decoded.
+    platform: Platform,
+}
+
+impl BlockCache {
+    pub fn new(memory_size: usize, platform: Platform) -> Self {
+        Self {
+            blocks: Vec::new(),
+            starts: vec![NO_BLOCK; memory_size],
+            code: [0; MEMORY_SIZE / 64],
+            memory_size,
+            platform,
+        }
+    }
+
+    /// Returns the index of the block starting at `pc`, decoding it from `memory`
+    /// if it isn't cached yet. The block is empty if `pc` is too close to the end of
+    /// memory to hold an instruction.
+    #[inline]
+    pub fn block_at(&mut self, memory: &[u8], pc: u16) -> usize {
+        let start = pc as usize;
+        if start < memory.len() && self.starts[start] != NO_BLOCK {
+            return self.starts[start] as usize;
+        }
+
+        let mut block = Vec::new();
+        let mut address = start;
+        while address + 1 < memory.len() && block.len() < MAX_BLOCK_LEN {
+            let opcode = ((memory[address] as u16) << 8) | memory[address + 1] as u16;
+            let instruction = self.platform.decode(opcode);
+            block.push(instruction);
+            self.mark_code(address..address + 2);
+            address += 2;
+
+            if instruction.ends_block() {
+                break;
+            }
+        }
+
+        let id = self.blocks.len();
+        self.blocks.push(block);
+        if start < memory.len() {
+            self.starts[start] = id as u16;
+        }
+        id
+    }
+
+    #[inline]
+    pub fn block(&self, id: usize) -> &[Instruction] {
+        &self.blocks[id]
+    }
+
+    /// Returns whether any address in `range` is part of a cached block. The range
+    /// can run past the end of memory, which wraps around to the start.
+    pub fn is_code(&self, range: Range<usize>) -> bool {
+        range
+            .map(|address| address % self.memory_size)
+            .any(|address| self.code[address / 64] & (1 << (address % 64)) != 0)
+    }
+
+    pub fn clear(&mut self) {
+        self.blocks.clear();
+        self.starts.fill(NO_BLOCK);
+        self.code = [0; MEMORY_SIZE / 64];
+    }
+
+    fn mark_code(&mut self, range: Range<usize>) {
+        for address in range {
+            self.code[address / 64] |= 1 << (address % 64);
+        }
+    }
+}
@@ -0,0 +1,255 @@
+use alloc::vec::Vec;
+
+use crate::{
+    cdp1802::Cdp1802,
+    fonts::{self, LARGE_FONT_SIZE, SMALL_FONT_SIZE},
+    new_rng, vip, ColorMap, Damage, Display, Drivers, LoadError, Machine, Platform, Quirks,
+    DISPLAY_HEIGHT, DISPLAY_WIDTH, FONT, FONT_START, LARGE_FONT_START, MEMORY_SIZE, PROGRAM_START,
+};
+
+/// Configures where a ROM and the font go in memory, and everything else a
+/// [`Machine`] starts with.
+///
+/// ```
+/// # use machine::{Machine, Quirks};
+/// // The ETI-660 loads programs at 0x600
+/// let machine = Machine::builder(&[0x00, 0xE0])
+///     .load_address(0x600)
+///     .quirks(Quirks::cosmac_vip())
+///     .seed(1)
+///     .build()
+///     .unwrap();
+/// assert_eq!(machine.pc, 0x600);
+/// ```
+#[must_use]
+pub struct MachineBuilder<'a> {
+    rom: &'a [u8],
+    load_address: u16,
+    font_address: u16,
+    font: [u8; SMALL_FONT_SIZE],
+    large_font_address: u16,
+    large_font: [u8; LARGE_FONT_SIZE],
+    memory_size: usize,
+    vip_memory_map: bool,
+    cdp1802: bool,
+    platform: Platform,
+    quirks: Quirks,
+    drivers: Drivers,
+    seed: Option<u64>,
+}
+
+impl<'a> MachineBuilder<'a> {
+    /// Starts from the defaults: [`PROGRAM_START`], [`FONT`] at [`FONT_START`],
+    /// [`fonts::OCTO_LARGE`] at [`LARGE_FONT_START`], [`MEMORY_SIZE`] bytes of
+    /// memory, the modern quirks and no-op drivers.
+    pub fn new(rom: &'a [u8]) -> Self {
+        Self {
+            rom,
+            load_address: PROGRAM_START,
+            font_address: FONT_START,
+            font: FONT,
+            large_font_address: LARGE_FONT_START,
+            large_font: fonts::OCTO_LARGE,
+            memory_size: MEMORY_SIZE,
+            vip_memory_map: false,
+            cdp1802: false,
+            platform: Platform::Chip8,
+            quirks: Quirks::modern_chip8(),
+            drivers: Drivers::noop(),
+            seed: None,
+        }
+    }
+
+    /// Sets the address that the ROM is loaded at and the PC starts from.
+    pub fn load_address(mut self, address: u16) -> Self {
+        self.load_address = address;
+        self
+    }
+
+    /// Sets the address of the font that FX29 points I into.
+    pub fn font_address(mut self, address: u16) -> Self {
+        self.font_address = address;
+        self
+    }
+
+    /// Replaces the built-in font, e.g. with one from [`fonts`], as 16 characters
+    /// of [`FONT_CHAR_SIZE`](crate::FONT_CHAR_SIZE) bytes.
+    pub fn font(mut self, font: [u8; SMALL_FONT_SIZE]) -> Self {
+        self.font = font;
+        self
+    }
+
+    /// Sets the address of the large font that FX30 points I into.
+    pub fn large_font_address(mut self, address: u16) -> Self {
+        self.large_font_address = address;
+        self
+    }
+
+    /// Replaces the built-in large font, as 16 characters of
+    /// [`LARGE_FONT_CHAR_SIZE`](fonts::LARGE_FONT_CHAR_SIZE) bytes.
+    pub fn large_font(mut self, font: [u8; LARGE_FONT_SIZE]) -> Self {
+        self.large_font = font;
+        self
+    }
+
+    /// Sets how much of memory is addressable, up to [`MEMORY_SIZE`]. Addresses
+    /// past the end wrap around, like on a COSMAC VIP with 2 KB of RAM.
+    pub fn memory_size(mut self, size: usize) -> Self {
+        self.memory_size = size;
+        self
+    }
+
+    /// Keeps the V registers, the call stack and the display in memory where the
+    /// COSMAC VIP's interpreter kept them ([`VIP_REGISTERS`](crate::VIP_REGISTERS),
+    /// [`VIP_STACK`](crate::VIP_STACK) and [`VIP_DISPLAY`](crate::VIP_DISPLAY),
+    /// moved down with the end of memory on smaller machines), for ROMs that read
+    /// or write them directly. FX55 and FX33 into this work area change the
+    /// registers, return addresses and display, the call stack only goes
+    /// [`VIP_STACK_DEPTH`](crate::VIP_STACK_DEPTH) deep, and the ROM can't be
+    /// loaded over it.
+    pub fn vip_memory_map(mut self, enabled: bool) -> Self {
+        self.vip_memory_map = enabled;
+        self
+    }
+
+    /// Runs the machine code subroutines called with 0NNN on an RCA 1802 core.
+    /// Without it, 0NNN fails with [`Error::MachineCode`](crate::Error::MachineCode).
+    ///
+    /// The subroutines see the memory map of a 4 KB COSMAC VIP: the V registers
+    /// are copied to [`VIP_REGISTERS`](crate::VIP_REGISTERS), the display to
+    /// [`VIP_DISPLAY`](crate::VIP_DISPLAY) and the call stack to just above
+    /// [`VIP_STACK`](crate::VIP_STACK), and anything the subroutine changes there
+    /// is copied back when it returns to the interpreter with `SEP R4` (D4). Like
+    /// with [`MachineBuilder::vip_memory_map`], the ROM can't be loaded over this
+    /// work area.
+    pub fn cdp1802(mut self, enabled: bool) -> Self {
+        self.cdp1802 = enabled;
+        self
+    }
+
+    /// Sets the system the ROM was written for, which decides how some opcodes
+    /// decode. This also moves the load address to the platform's, e.g. 0x300 for
+    /// CHIP-8X, so set the load address afterwards to override it.
+    pub fn platform(mut self, platform: Platform) -> Self {
+        self.platform = platform;
+        self.load_address = platform.load_address();
+        self
+    }
+
+    pub fn quirks(mut self, quirks: Quirks) -> Self {
+        self.quirks = quirks;
+        self
+    }
+
+    pub fn drivers(mut self, drivers: Drivers) -> Self {
+        self.drivers = drivers;
+        self
+    }
+
+    /// Seeds the random number generator used by CXNN, so that runs are repeatable.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Lays out memory and creates the machine, failing if the ROM or the fonts
+    /// don't fit, or if any of them overlap.
+    pub fn build(self) -> Result<Machine, LoadError> {
+        let memory_size = self.memory_size;
+        if memory_size == 0 || memory_size > MEMORY_SIZE {
+            return Err(LoadError::InvalidMemorySize {
+                size: memory_size,
+                max: MEMORY_SIZE,
+            });
+        }
+
+        let load_address = self.load_address as usize;
+        if load_address >= memory_size {
+            return Err(LoadError::LoadAddressOutOfRange {
+                address: self.load_address,
+                memory_size,
+            });
+        }
+        let end = if self.vip_memory_map || self.cdp1802 {
+            vip::work_area_start(memory_size)
+        } else {
+            memory_size
+        };
+        let max = end.saturating_sub(load_address);
+        if self.rom.len() > max {
+            return Err(LoadError::RomTooLarge {
+                size: self.rom.len(),
+                max,
+            });
+        }
+
+        let rom_end = load_address + self.rom.len();
+        let fonts = [
+            (self.font_address, &self.font[..]),
+            (self.large_font_address, &self.large_font[..]),
+        ];
+        for (address, font) in fonts {
+            let start = address as usize;
+            let end = start + font.len();
+            if end > memory_size {
+                return Err(LoadError::FontOutOfRange {
+                    address,
+                    memory_size,
+                });
+            }
+            if start < rom_end && load_address < end {
+                return Err(LoadError::FontOverlapsRom {
+                    font_address: address,
+                    load_address: self.load_address,
+                });
+            }
+        }
+
+        let (small, large) = (self.font_address as usize, self.large_font_address as usize);
+        if small < large + LARGE_FONT_SIZE && large < small + SMALL_FONT_SIZE {
+            return Err(LoadError::FontsOverlap {
+                font_address: self.font_address,
+                large_font_address: self.large_font_address,
+            });
+        }
+
+        let mut memory = [0; MEMORY_SIZE];
+        for (address, font) in fonts {
+            memory[address as usize..][..font.len()].copy_from_slice(font);
+        }
+        memory[load_address..rom_end].copy_from_slice(self.rom);
+
+        Ok(Machine {
+            memory,
+            display: Display::new(),
+            pc: self.load_address,
+            index: 0,
+            stack: Vec::new(),
+
+            dt: 0,
+            st: 0,
+            registers: [0; 16],
+            keypad: 0,
+
+            damage: Damage::full(DISPLAY_WIDTH, DISPLAY_HEIGHT),
+            polled_keys: 0,
+
+            keypad2: 0,
+            colors: ColorMap::new(),
+            output_port: 0,
+            input_port: None,
+
+            quirks: self.quirks,
+            drivers: self.drivers,
+            rng: self.seed.map_or_else(new_rng, fastrand::Rng::with_seed),
+            memory_size,
+            font_address: self.font_address,
+            large_font_address: self.large_font_address,
+            vip_cycles: 0,
+            block_cache: None,
+            cdp1802: self.cdp1802.then(Cdp1802::default),
+            platform: self.platform,
+            vip_memory_map: self.vip_memory_map,
+        })
+    }
+}
@@ -0,0 +1,248 @@
+//! An RCA CDP1802 core, for the machine code subroutines that 0NNN calls.
+
+/// The state of an 1802: sixteen 16-bit scratchpad registers, any of which can be
+/// the program counter (`p`) or the data pointer (`x`).
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Cdp1802 {
+    pub r: [u16; 16],
+    /// The accumulator.
+    pub d: u8,
+    /// The carry flag.
+    pub df: bool,
+    pub p: u8,
+    pub x: u8,
+    /// Where `x` and `p` are saved by MARK and interrupts.
+    pub t: u8,
+    /// Interrupt enable. Interrupts aren't emulated, but RET and DIS still set it.
+    pub ie: bool,
+    /// The Q output, which drives the VIP's speaker.
+    pub q: bool,
+}
+
+impl Cdp1802 {
+    /// Executes one instruction from `memory`, which addresses wrap around, and
+    /// returns the machine cycles it took.
+    pub fn step(&mut self, memory: &mut [u8]) -> u32 {
+        let size = memory.len();
+        let read = |memory: &[u8], address: u16| memory[address as usize % size];
+
+        let opcode = read(memory, self.r[self.p as usize]);
+        self.r[self.p as usize] = self.r[self.p as usize].wrapping_add(1);
+        let (i, n) = (opcode >> 4, (opcode & 0xF) as usize);
+        let rx = self.x as usize;
+
+        match i {
+            // IDL, which waits for DMA or an interrupt, does nothing here
+            0x0 if n == 0 => {}
+            // LDN
+            0x0 => self.d = read(memory, self.r[n]),
+            // INC, DEC
+            0x1 => self.r[n] = self.r[n].wrapping_add(1),
+            0x2 => self.r[n] = self.r[n].wrapping_sub(1),
+            0x3 => self.short_branch(memory, n),
+            // LDA
+            0x4 => {
+                self.d = read(memory, self.r[n]);
+                self.r[n] = self.r[n].wrapping_add(1);
+            }
+            // STR
+            0x5 => memory[self.r[n] as usize % size] = self.d,
+            // IRX
+            0x6 if n == 0 => self.r[rx] = self.r[rx].wrapping_add(1),
+            // OUT, which has nothing to write to, but still steps R(X)
+            0x6 if n < 8 => self.r[rx] = self.r[rx].wrapping_add(1),
+            // INP, which reads nothing but zeros
+            0x6 => {
+                self.d = 0;
+                memory[self.r[rx] as usize % size] = 0;
+            }
+            0x7 => self.op_7(memory, n),
+            // GLO, GHI, PLO, PHI
+            0x8 => self.d = self.r[n] as u8,
+            0x9 => self.d = (self.r[n] >> 8) as u8,
+            0xA => self.r[n] = (self.r[n] & 0xFF00) | self.d as u16,
+            0xB => self.r[n] = (self.r[n] & 0x00FF) | (self.d as u16) << 8,
+            0xC => {
+                self.long_branch(memory, n);
+                return 3;
+            }
+            // SEP, SEX
+            0xD => self.p = n as u8,
+            0xE => self.x = n as u8,
+            _ => self.op_f(memory, n),
+        }
+        2
+    }
+
+    /// The 3N short branches, to an address in the same page as the branch.
+    fn short_branch(&mut self, memory: &[u8], n: usize) {
+        let p = self.p as usize;
+        let condition = match n & 7 {
+            0 => true,
+            1 => self.q,
+            2 => self.d == 0,
+            3 => self.df,
+            // The EF inputs are never set
+            _ => false,
+        };
+        // The top half inverts the condition, with 38 (SKP) as "never"
+        if condition != (n >= 8) {
+            let target = memory[self.r[p] as usize % memory.len()];
+            self.r[p] = (self.r[p] & 0xFF00) | target as u16;
+        } else {
+            self.r[p] = self.r[p].wrapping_add(1);
+        }
+    }
+
+    /// The CN long branches and skips.
+    fn long_branch(&mut self, memory: &[u8], n: usize) {
+        let p = self.p as usize;
+        let condition = match n & 3 {
+            // LBR and LSKP are unconditional, and LSIE checks interrupts
+            0 if n == 0xC => self.ie,
+            0 => true,
+            1 => self.q,
+            2 => self.d == 0,
+            _ => self.df,
+        };
+        match n {
+            // NOP
+            0x4 => {}
+            // LBR, LBQ, LBZ, LBDF, and the inverted LBNQ, LBNZ, LBNF
+            0x0..=0x3 | 0x9..=0xB => {
+                if condition != (n >= 8) {
+                    let high = memory[self.r[p] as usize % memory.len()];
+                    let low = memory[self.r[p].wrapping_add(1) as usize % memory.len()];
+                    self.r[p] = u16::from_be_bytes([high, low]);
+                } else {
+                    self.r[p] = self.r[p].wrapping_add(2);
+                }
+            }
+            // LSKP is 0x8, the inverted skips LSNQ, LSNZ, LSNF are 0x5 to 0x7, and
+            // LSIE, LSQ, LSZ, LSDF are 0xC to 0xF
+            _ => {
+                let skip = match n {
+                    0x8 => true,
+                    0x5..=0x7 => !condition,
+                    _ => condition,
+                };
+                if skip {
+                    self.r[p] = self.r[p].wrapping_add(2);
+                }
+            }
+        }
+    }
+
+    fn op_7(&mut self, memory: &mut [u8], n: usize) {
+        let size = memory.len();
+        let rx = self.x as usize;
+        let p = self.p as usize;
+        let mx = memory[self.r[rx] as usize % size];
+        let immediate = memory[self.r[p] as usize % size];
+        match n {
+            // RET, DIS
+            0x0 | 0x1 => {
+                self.x = mx >> 4;
+                self.p = mx & 0xF;
+                self.r[rx] = self.r[rx].wrapping_add(1);
+                self.ie = n == 0;
+            }
+            // LDXA
+            0x2 => {
+                self.d = mx;
+                self.r[rx] = self.r[rx].wrapping_add(1);
+            }
+            // STXD
+            0x3 => {
+                memory[self.r[rx] as usize % size] = self.d;
+                self.r[rx] = self.r[rx].wrapping_sub(1);
+            }
+            // ADC, SDB, SMB, and their immediate forms
+            0x4 | 0x5 | 0x7 | 0xC | 0xD | 0xF => {
+                let operand = if n >= 0xC {
+                    self.r[p] = self.r[p].wrapping_add(1);
+                    immediate
+                } else {
+                    mx
+                };
+                match n & 3 {
+                    0 => self.add(operand, self.df),
+                    1 => self.subtract(operand, self.d, self.df),
+                    _ => self.subtract(self.d, operand, self.df),
+                }
+            }
+            // SHRC
+            0x6 => {
+                let carry = self.d & 1 != 0;
+                self.d = self.d >> 1 | (self.df as u8) << 7;
+                self.df = carry;
+            }
+            // SAV
+            0x8 => memory[self.r[rx] as usize % size] = self.t,
+            // MARK
+            0x9 => {
+                self.t = self.x << 4 | self.p;
+                memory[self.r[2] as usize % size] = self.t;
+                self.x = self.p;
+                self.r[2] = self.r[2].wrapping_sub(1);
+            }
+            // REQ, SEQ
+            0xA => self.q = false,
+            0xB => self.q = true,
+            // SHLC
+            _ => {
+                let carry = self.d & 0x80 != 0;
+                self.d = self.d << 1 | self.df as u8;
+                self.df = carry;
+            }
+        }
+    }
+
+    fn op_f(&mut self, memory: &[u8], n: usize) {
+        let size = memory.len();
+        let p = self.p as usize;
+        // F8 to FF take an immediate byte instead of M(R(X)), except for the shifts
+        let operand = if n >= 8 && n != 0xE {
+            let immediate = memory[self.r[p] as usize % size];
+            self.r[p] = self.r[p].wrapping_add(1);
+            immediate
+        } else {
+            memory[self.r[self.x as usize] as usize % size]
+        };
+        match n & 7 {
+            // LDX, LDI
+            0 => self.d = operand,
+            1 => self.d |= operand,
+            2 => self.d &= operand,
+            3 => self.d ^= operand,
+            4 => self.add(operand, false),
+            // SD, SDI: the operand minus D
+            5 => self.subtract(operand, self.d, true),
+            // SHR, SHL
+            6 if n == 6 => {
+                self.df = self.d & 1 != 0;
+                self.d >>= 1;
+            }
+            6 => {
+                self.df = self.d & 0x80 != 0;
+                self.d <<= 1;
+            }
+            // SM, SMI: D minus the operand
+            _ => self.subtract(self.d, operand, true),
+        }
+    }
+
+    fn add(&mut self, operand: u8, carry: bool) {
+        let sum = self.d as u16 + operand as u16 + carry as u16;
+        self.d = sum as u8;
+        self.df = sum > 0xFF;
+    }
+
+    /// Sets D to `a - b`, where `no_borrow` is DF going in. DF comes out set if
+    /// there was no borrow, like CHIP-8's VF.
+    fn subtract(&mut self, a: u8, b: u8, no_borrow: bool) {
+        let difference = a as i16 - b as i16 - !no_borrow as i16;
+        self.d = difference as u8;
+        self.df = difference >= 0;
+    }
+}
@@ -0,0 +1,113 @@
+//! CHIP-8X, the extended interpreter for the COSMAC VIP with the VP-590 color
+//! board and a second keypad.
+
+use core::ops::Range;
+
+use crate::{Instruction, Palette, Rgb, DISPLAY_HEIGHT, DISPLAY_WIDTH, PROGRAM_START};
+
+/// Where CHIP-8X programs are loaded, after the larger interpreter.
+pub const CHIP8X_PROGRAM_START: u16 = 0x300;
+
+/// The color board gives each byte of a display row its own foreground color.
+pub const COLOR_COLUMNS: usize = DISPLAY_WIDTH / 8;
+/// The height of the zones that BXY0 colors.
+pub const COLOR_ZONE_HEIGHT: usize = 4;
+
+/// The background colors that 02A0 cycles through, starting with dark blue.
+pub const BACKGROUND_COLORS: [Rgb; 4] = [
+    Rgb::new(0x00, 0x00, 0x80),
+    Rgb::new(0x00, 0x00, 0x00),
+    Rgb::new(0x00, 0x80, 0x00),
+    Rgb::new(0x80, 0x00, 0x00),
+];
+
+/// The foreground colors that BXY0 and BXYN set, by the low 3 bits of VY.
+pub const FOREGROUND_COLORS: [Rgb; 8] = [
+    Rgb::new(0x00, 0x00, 0x00), // Black
+    Rgb::new(0xFF, 0x00, 0x00), // Red
+    Rgb::new(0x00, 0x00, 0xFF), // Blue
+    Rgb::new(0xFF, 0x00, 0xFF), // Violet
+    Rgb::new(0x00, 0xFF, 0x00), // Green
+    Rgb::new(0xFF, 0xFF, 0x00), // Yellow
+    Rgb::new(0x00, 0xFF, 0xFF), // Aqua
+    Rgb::new(0xFF, 0xFF, 0xFF), // White
+];
+
+/// The interpreter starts every zone red.
+const DEFAULT_FOREGROUND: u8 = 1;
+
+/// The system a ROM was written for, which decides how some opcodes decode.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Platform {
+    #[default]
+    Chip8,
+    /// CHIP-8X, which replaces BNNN with the color instructions and adds 02A0,
+    /// EXF2, EXF5, FXF8 and FXFB.
+    Chip8X,
+}
+
+impl Platform {
+    /// Where the platform's interpreter loads programs.
+    pub const fn load_address(self) -> u16 {
+        match self {
+            Self::Chip8 => PROGRAM_START,
+            Self::Chip8X => CHIP8X_PROGRAM_START,
+        }
+    }
+
+    /// Decodes a two byte opcode for this platform.
+    pub fn decode(self, opcode: u16) -> Instruction {
+        match self {
+            Self::Chip8 => Instruction::decode(opcode),
+            Self::Chip8X => Instruction::decode_chip8x(opcode),
+        }
+    }
+}
+
+/// The color attributes that the VP-590 color board lays over the monochrome
+/// display: a background for the whole screen, and a foreground color for the
+/// lit pixels in each 8x1 pixel cell.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ColorMap {
+    /// An index into [`BACKGROUND_COLORS`].
+    pub background: u8,
+    /// Indices into [`FOREGROUND_COLORS`], for each byte of each display row.
+    pub foreground: [[u8; COLOR_COLUMNS]; DISPLAY_HEIGHT],
+}
+
+impl ColorMap {
+    pub fn new() -> Self {
+        Self {
+            background: 0,
+            foreground: [[DEFAULT_FOREGROUND; COLOR_COLUMNS]; DISPLAY_HEIGHT],
+        }
+    }
+
+    /// The colors of the pixel at (`x`, `y`) when it's unlit and lit.
+    pub fn palette_at(&self, x: usize, y: usize) -> Palette {
+        let foreground = self.foreground[y % DISPLAY_HEIGHT][x % DISPLAY_WIDTH / 8];
+        Palette::new(
+            BACKGROUND_COLORS[self.background as usize % BACKGROUND_COLORS.len()],
+            FOREGROUND_COLORS[foreground as usize % FOREGROUND_COLORS.len()],
+        )
+    }
+
+    /// Moves on to the next background color, for 02A0.
+    pub(crate) fn cycle_background(&mut self) {
+        self.background = (self.background + 1) % BACKGROUND_COLORS.len() as u8;
+    }
+
+    /// Sets the foreground color of the cells in `columns` and `rows`.
+    pub(crate) fn fill(&mut self, columns: Range<usize>, rows: Range<usize>, color: u8) {
+        for row in &mut self.foreground[rows] {
+            row[columns.clone()].fill(color & 7);
+        }
+    }
+}
+
+impl Default for ColorMap {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,109 @@
+use core::ops::Range;
+
+use crate::Display;
+
+/// The parts of the display that have changed since the last frame was taken.
+///
+/// Damage is tracked per row, along with the range of columns touched across all
+/// of those rows, so it may cover a few more pixels than actually changed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Damage {
+    /// Bit `y` is set if row `y` has changed.
+    rows: u64,
+    columns: Option<(usize, usize)>,
+}
+
+/// A rectangle of the display, in pixels.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DamageRect {
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+impl Damage {
+    /// Damage covering the whole display.
+    pub fn full(width: usize, height: usize) -> Self {
+        let mut damage = Self::default();
+        damage.add(0..width, 0..height);
+        damage
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rows == 0
+    }
+
+    /// Marks the pixels in `columns` of each row in `rows` as changed.
+    pub fn add(&mut self, columns: Range<usize>, rows: Range<usize>) {
+        if columns.is_empty() || rows.is_empty() {
+            return;
+        }
+
+        for y in rows {
+            self.rows |= 1 << y;
+        }
+        self.columns = Some(match self.columns {
+            Some((start, end)) => (start.min(columns.start), end.max(columns.end)),
+            None => (columns.start, columns.end),
+        });
+    }
+
+    pub fn contains_row(&self, y: usize) -> bool {
+        self.rows & (1 << y) != 0
+    }
+
+    /// Iterates over the indices of the changed rows, from top to bottom.
+    pub fn rows(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..u64::BITS as usize).filter(|y| self.contains_row(*y))
+    }
+
+    /// The range of columns that changed, across all rows.
+    pub fn columns(&self) -> Option<Range<usize>> {
+        self.columns.map(|(start, end)| start..end)
+    }
+
+    /// Splits the damage into one rectangle for each run of adjacent changed rows.
+    pub fn rects(&self) -> impl Iterator<Item = DamageRect> + '_ {
+        let columns = self.columns().unwrap_or_default();
+        let mut y = 0;
+
+        core::iter::from_fn(move || {
+            while y < u64::BITS as usize && !self.contains_row(y) {
+                y += 1;
+            }
+            let start = y;
+            while y < u64::BITS as usize && self.contains_row(y) {
+                y += 1;
+            }
+
+            (y > start).then(|| DamageRect {
+                x: columns.start,
+                y: start,
+                width: columns.len(),
+                height: y - start,
+            })
+        })
+    }
+
+    /// The smallest rectangle covering all of the damage.
+    pub fn bounding_rect(&self) -> Option<DamageRect> {
+        let columns = self.columns()?;
+        let top = self.rows.trailing_zeros() as usize;
+        let bottom = u64::BITS as usize - self.rows.leading_zeros() as usize;
+
+        Some(DamageRect {
+            x: columns.start,
+            y: top,
+            width: columns.len(),
+            height: bottom - top,
+        })
+    }
+}
+
+/// A finished frame, returned by [`Machine::take_frame`](crate::Machine::take_frame).
+pub struct Frame<'a> {
+    pub display: &'a Display,
+    /// What has changed since the previous frame was taken.
+    pub damage: Damage,
+}
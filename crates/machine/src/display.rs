@@ -0,0 +1,168 @@
+use core::ops::{BitAnd, BitOrAssign, BitXorAssign, Not};
+
+use alloc::vec::Vec;
+
+use crate::{Palette, DISPLAY_HEIGHT};
+
+/// One row of a [`Framebuffer`], stored as a bitmask with the leftmost pixel in the
+/// most significant bit.
+pub trait Row:
+    Copy
+    + Default
+    + Eq
+    + BitAnd<Output = Self>
+    + BitOrAssign
+    + BitXorAssign
+    + Not<Output = Self>
+    + core::fmt::Debug
+{
+    /// The number of pixels in the row.
+    const WIDTH: usize;
+
+    /// A row with only the pixel at column `x` lit.
+    fn pixel(x: usize) -> Self;
+
+    /// A row with the 8 pixels of `sprite` starting at column `x`. Pixels past the
+    /// right edge are clipped.
+    fn sprite(sprite: u8, x: usize) -> Self;
+
+    fn is_empty(self) -> bool {
+        self == Self::default()
+    }
+}
+
+macro_rules! impl_row {
+    ($($ty:ty),*) => {$(
+        impl Row for $ty {
+            const WIDTH: usize = <$ty>::BITS as usize;
+
+            fn pixel(x: usize) -> Self {
+                1 << (Self::WIDTH - 1 - x)
+            }
+
+            fn sprite(sprite: u8, x: usize) -> Self {
+                let sprite = sprite as Self;
+                if x + 8 <= Self::WIDTH {
+                    sprite << (Self::WIDTH - 8 - x)
+                } else {
+                    sprite >> (x + 8 - Self::WIDTH)
+                }
+            }
+        }
+    )*};
+}
+
+impl_row!(u64, u128);
+
+/// A monochrome bitmap display, stored as one [`Row`] bitmask per line so that
+/// sprites can be drawn a whole row at a time.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Framebuffer<R: Row, const HEIGHT: usize> {
+    rows: [R; HEIGHT],
+}
+
+/// The standard 64x32 CHIP-8 display.
+pub type Display = Framebuffer<u64, DISPLAY_HEIGHT>;
+
+/// The 128x64 high resolution display used by SUPER-CHIP.
+pub type HiresDisplay = Framebuffer<u128, 64>;
+
+impl<R: Row, const HEIGHT: usize> Framebuffer<R, HEIGHT> {
+    pub const WIDTH: usize = R::WIDTH;
+    pub const HEIGHT: usize = HEIGHT;
+
+    pub fn new() -> Self {
+        Self {
+            rows: [R::default(); HEIGHT],
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        Self::WIDTH
+    }
+
+    pub fn height(&self) -> usize {
+        HEIGHT
+    }
+
+    pub fn clear(&mut self) {
+        self.rows = [R::default(); HEIGHT];
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> bool {
+        !(self.rows[y] & R::pixel(x)).is_empty()
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, on: bool) {
+        if on {
+            self.rows[y] |= R::pixel(x);
+        } else {
+            self.rows[y] = self.rows[y] & !R::pixel(x);
+        }
+    }
+
+    pub fn row(&self, y: usize) -> R {
+        self.rows[y]
+    }
+
+    pub fn rows(&self) -> &[R; HEIGHT] {
+        &self.rows
+    }
+
+    pub fn set_row(&mut self, y: usize, row: R) {
+        self.rows[y] = row;
+    }
+
+    /// XORs a sprite onto the display with its top left corner at (`x`, `y`),
+    /// returning whether any lit pixels were turned off. Rows and columns past the
+    /// edges of the display are clipped.
+    pub fn draw_sprite(&mut self, x: usize, y: usize, sprite: &[u8]) -> bool {
+        let mut collision = false;
+
+        for (row, sprite_row) in self.rows.iter_mut().skip(y).zip(sprite) {
+            let sprite_row = R::sprite(*sprite_row, x);
+            collision |= !(*row & sprite_row).is_empty();
+            *row ^= sprite_row;
+        }
+
+        collision
+    }
+
+    /// Iterates over every pixel, row by row, with its position.
+    pub fn iter(&self) -> impl Iterator<Item = ((usize, usize), bool)> + '_ {
+        (0..HEIGHT).flat_map(move |y| (0..R::WIDTH).map(move |x| ((x, y), self.get(x, y))))
+    }
+
+    /// Iterates over the positions of the lit pixels, row by row.
+    pub fn lit_pixels(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.rows
+            .iter()
+            .enumerate()
+            .filter(|(_, row)| !row.is_empty())
+            .flat_map(|(y, row)| {
+                (0..R::WIDTH)
+                    .filter(move |x| !(*row & R::pixel(*x)).is_empty())
+                    .map(move |x| (x, y))
+            })
+    }
+
+    /// Converts the display to row-major RGBA pixels, 4 bytes per pixel.
+    pub fn to_rgba(&self, palette: &Palette) -> Vec<u8> {
+        self.iter()
+            .flat_map(|(_, on)| {
+                let color = if on {
+                    palette.foreground
+                } else {
+                    palette.background
+                };
+                [color.r, color.g, color.b, 0xFF]
+            })
+            .collect()
+    }
+}
+
+impl<R: Row, const HEIGHT: usize> Default for Framebuffer<R, HEIGHT> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
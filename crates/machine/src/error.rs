@@ -0,0 +1,114 @@
+use core::fmt;
+
+/// An error raised while executing an instruction.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Error {
+    /// The opcode at `address` isn't a known instruction.
+    UnknownInstruction { opcode: u16, address: u16 },
+    /// The `00EE` at `address` tried to return with an empty call stack.
+    StackUnderflow { address: u16 },
+    /// The `2NNN` at `address` tried to call a subroutine with a full call stack.
+    StackOverflow { address: u16 },
+    /// The `0NNN` at `address` called the machine code subroutine at `routine`, but
+    /// the 1802 core isn't enabled.
+    MachineCode { routine: u16, address: u16 },
+    /// The machine code subroutine at `routine` didn't return to the interpreter.
+    MachineCodeTimeout { routine: u16 },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnknownInstruction { opcode, address } => {
+                write!(f, "Unknown instruction {opcode:04X} at {address:03X}")
+            }
+            Self::StackUnderflow { address } => write!(
+                f,
+                "Attempted to return from a subroutine with an empty stack at {address:03X}"
+            ),
+            Self::StackOverflow { address } => write!(
+                f,
+                "Attempted to call a subroutine with a full stack at {address:03X}"
+            ),
+            Self::MachineCode { routine, address } => write!(
+                f,
+                "Machine code subroutine {routine:03X} called at {address:03X}, but the 1802 core is disabled"
+            ),
+            Self::MachineCodeTimeout { routine } => write!(
+                f,
+                "Machine code subroutine {routine:03X} didn't return to the interpreter"
+            ),
+        }
+    }
+}
+
+impl core::error::Error for Error {}
+
+/// An error raised while loading a ROM.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LoadError {
+    /// The ROM doesn't fit in memory after the load address.
+    RomTooLarge { size: usize, max: usize },
+    /// The memory size is zero or more than the `max` bytes that `memory` holds.
+    InvalidMemorySize { size: usize, max: usize },
+    /// The load address is past the end of memory.
+    LoadAddressOutOfRange { address: u16, memory_size: usize },
+    /// A font runs past the end of memory.
+    FontOutOfRange { address: u16, memory_size: usize },
+    /// A font and the ROM would be loaded over each other.
+    FontOverlapsRom {
+        font_address: u16,
+        load_address: u16,
+    },
+    /// The small and large fonts would be loaded over each other.
+    FontsOverlap {
+        font_address: u16,
+        large_font_address: u16,
+    },
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::RomTooLarge { size, max } => write!(
+                f,
+                "ROM is {size} bytes, but at most {max} bytes fit in memory"
+            ),
+            Self::InvalidMemorySize { size, max } => write!(
+                f,
+                "Memory size must be between 1 and {max} bytes, not {size}"
+            ),
+            Self::LoadAddressOutOfRange {
+                address,
+                memory_size,
+            } => write!(
+                f,
+                "Load address {address:03X} is past the end of the {memory_size} bytes of memory"
+            ),
+            Self::FontOutOfRange {
+                address,
+                memory_size,
+            } => write!(
+                f,
+                "Font at {address:03X} runs past the end of the {memory_size} bytes of memory"
+            ),
+            Self::FontOverlapsRom {
+                font_address,
+                load_address,
+            } => write!(
+                f,
+                "Font at {font_address:03X} overlaps the ROM loaded at {load_address:03X}"
+            ),
+            Self::FontsOverlap {
+                font_address,
+                large_font_address,
+            } => write!(
+                f,
+                "Font at {font_address:03X} overlaps the large font at {large_font_address:03X}"
+            ),
+        }
+    }
+}
+
+impl core::error::Error for LoadError {}
@@ -0,0 +1,219 @@
+use core::ops::Range;
+
+use crate::MEMORY_SIZE;
+
+/// A decoded instruction. Register operands are indices from 0x0 to 0xF.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Instruction {
+    /// 00E0
+    ClearScreen,
+    /// 00EE
+    Return,
+    /// 0NNN, which runs the 1802 machine code subroutine at NNN.
+    MachineCall { address: u16 },
+    /// 1NNN
+    Jump { address: u16 },
+    /// 2NNN
+    Call { address: u16 },
+    /// 3XNN
+    SkipIfEqual { x: u8, value: u8 },
+    /// 4XNN
+    SkipIfNotEqual { x: u8, value: u8 },
+    /// 5XY0
+    SkipIfRegistersEqual { x: u8, y: u8 },
+    /// 9XY0
+    SkipIfRegistersNotEqual { x: u8, y: u8 },
+    /// 6XNN
+    Set { x: u8, value: u8 },
+    /// 7XNN
+    Add { x: u8, value: u8 },
+    /// ANNN
+    SetIndex { address: u16 },
+    /// 8XY0
+    Copy { x: u8, y: u8 },
+    /// 8XY1
+    Or { x: u8, y: u8 },
+    /// 8XY2
+    And { x: u8, y: u8 },
+    /// 8XY3
+    Xor { x: u8, y: u8 },
+    /// 8XY4
+    AddRegisters { x: u8, y: u8 },
+    /// 8XY5
+    Subtract { x: u8, y: u8 },
+    /// 8XY7
+    SubtractReversed { x: u8, y: u8 },
+    /// 8XY6
+    ShiftRight { x: u8, y: u8 },
+    /// 8XYE
+    ShiftLeft { x: u8, y: u8 },
+    /// DXYN
+    Draw { x: u8, y: u8, height: u8 },
+    /// FX55
+    Store { x: u8 },
+    /// FX65
+    Load { x: u8 },
+    /// FX1E
+    AddToIndex { x: u8 },
+    /// BNNN, or BXNN with the `bxnn` quirk. `x` is the top nibble of `address`.
+    JumpWithOffset { address: u16, x: u8 },
+    /// FX33
+    StoreBcd { x: u8 },
+    /// FX0A
+    WaitForKey { x: u8 },
+    /// EX9E
+    SkipIfKey { x: u8 },
+    /// EXA1
+    SkipIfNotKey { x: u8 },
+    /// FX07
+    GetDelayTimer { x: u8 },
+    /// FX15
+    SetDelayTimer { x: u8 },
+    /// FX18
+    SetSoundTimer { x: u8 },
+    /// FX29
+    FontCharacter { x: u8 },
+    /// FX30
+    LargeFontCharacter { x: u8 },
+    /// CXNN
+    Random { x: u8, mask: u8 },
+    /// 02A0 on CHIP-8X
+    CycleBackground,
+    /// BXY0 on CHIP-8X, which colors 8x4 pixel zones from VX and VX+1
+    SetColorZones { x: u8, y: u8 },
+    /// BXYN on CHIP-8X, which colors N rows of 8 pixels at (VX, VX+1)
+    SetColorRows { x: u8, y: u8, height: u8 },
+    /// EXF2 on CHIP-8X, which checks the second keypad
+    SkipIfKey2 { x: u8 },
+    /// EXF5 on CHIP-8X
+    SkipIfNotKey2 { x: u8 },
+    /// FXF8 on CHIP-8X
+    Output { x: u8 },
+    /// FXFB on CHIP-8X
+    Input { x: u8 },
+    /// An opcode that isn't a known instruction.
+    Unknown { opcode: u16 },
+}
+
+impl Instruction {
+    /// Decodes a two byte opcode.
+    pub fn decode(opcode: u16) -> Self {
+        let first_nibble = (opcode >> 12) & 0xF;
+        let second_nibble = (opcode >> 8) & 0xF;
+        let third_nibble = (opcode >> 4) & 0xF;
+        let fourth_nibble = opcode & 0xF;
+
+        let x = second_nibble as u8;
+        let y = third_nibble as u8;
+        let nn = (opcode & 0xFF) as u8;
+        let nnn = opcode & 0xFFF;
+
+        match (first_nibble, second_nibble, third_nibble, fourth_nibble) {
+            (0x00, 0x00, 0x0E, 0x00) => Self::ClearScreen,
+            (0x00, 0x00, 0x0E, 0x0E) => Self::Return,
+            (0x00, _, _, _) => Self::MachineCall { address: nnn },
+            (0x01, _, _, _) => Self::Jump { address: nnn },
+            (0x02, _, _, _) => Self::Call { address: nnn },
+
+            (0x03, _, _, _) => Self::SkipIfEqual { x, value: nn },
+            (0x04, _, _, _) => Self::SkipIfNotEqual { x, value: nn },
+            (0x05, _, _, _) => Self::SkipIfRegistersEqual { x, y },
+            (0x09, _, _, _) => Self::SkipIfRegistersNotEqual { x, y },
+
+            (0x06, _, _, _) => Self::Set { x, value: nn },
+            (0x07, _, _, _) => Self::Add { x, value: nn },
+            (0x0A, _, _, _) => Self::SetIndex { address: nnn },
+
+            (0x08, _, _, 0x00) => Self::Copy { x, y },
+            (0x08, _, _, 0x01) => Self::Or { x, y },
+            (0x08, _, _, 0x02) => Self::And { x, y },
+            (0x08, _, _, 0x03) => Self::Xor { x, y },
+            (0x08, _, _, 0x04) => Self::AddRegisters { x, y },
+            (0x08, _, _, 0x05) => Self::Subtract { x, y },
+            (0x08, _, _, 0x07) => Self::SubtractReversed { x, y },
+            (0x08, _, _, 0x06) => Self::ShiftRight { x, y },
+            (0x08, _, _, 0x0E) => Self::ShiftLeft { x, y },
+
+            (0x0D, _, _, _) => Self::Draw {
+                x,
+                y,
+                height: fourth_nibble as u8,
+            },
+
+            (0x0F, _, 0x05, _) => Self::Store { x },
+            (0x0F, _, 0x06, _) => Self::Load { x },
+            (0x0F, _, 0x01, 0x0E) => Self::AddToIndex { x },
+            (0x0B, _, _, _) => Self::JumpWithOffset { address: nnn, x },
+            (0x0F, _, 0x03, 0x03) => Self::StoreBcd { x },
+
+            (0x0F, _, 0x00, 0x0A) => Self::WaitForKey { x },
+            (0x0E, _, 0x09, 0x0E) => Self::SkipIfKey { x },
+            (0x0E, _, 0x0A, 0x01) => Self::SkipIfNotKey { x },
+
+            (0x0F, _, 0x00, 0x07) => Self::GetDelayTimer { x },
+            (0x0F, _, 0x01, 0x05) => Self::SetDelayTimer { x },
+            (0x0F, _, 0x01, 0x08) => Self::SetSoundTimer { x },
+
+            (0x0F, _, 0x02, 0x09) => Self::FontCharacter { x },
+            (0x0F, _, 0x03, 0x00) => Self::LargeFontCharacter { x },
+            (0x0C, _, _, _) => Self::Random { x, mask: nn },
+
+            _ => Self::Unknown { opcode },
+        }
+    }
+
+    /// Decodes a two byte opcode for CHIP-8X, which replaces BNNN with the color
+    /// instructions and adds a few of its own.
+    pub fn decode_chip8x(opcode: u16) -> Self {
+        let x = ((opcode >> 8) & 0xF) as u8;
+        let y = ((opcode >> 4) & 0xF) as u8;
+        let n = (opcode & 0xF) as u8;
+
+        match (opcode >> 12, x, y, n) {
+            (0x00, 0x02, 0x0A, 0x00) => Self::CycleBackground,
+            (0x0B, _, _, 0x00) => Self::SetColorZones { x, y },
+            (0x0B, _, _, _) => Self::SetColorRows { x, y, height: n },
+            (0x0E, _, 0x0F, 0x02) => Self::SkipIfKey2 { x },
+            (0x0E, _, 0x0F, 0x05) => Self::SkipIfNotKey2 { x },
+            (0x0F, _, 0x0F, 0x08) => Self::Output { x },
+            (0x0F, _, 0x0F, 0x0B) => Self::Input { x },
+            _ => Self::decode(opcode),
+        }
+    }
+
+    /// Whether the instruction can go somewhere other than the next instruction.
+    pub fn ends_block(&self) -> bool {
+        matches!(
+            self,
+            Self::Return
+                | Self::MachineCall { .. }
+                | Self::Jump { .. }
+                | Self::Call { .. }
+                | Self::SkipIfEqual { .. }
+                | Self::SkipIfNotEqual { .. }
+                | Self::SkipIfRegistersEqual { .. }
+                | Self::SkipIfRegistersNotEqual { .. }
+                | Self::JumpWithOffset { .. }
+                | Self::WaitForKey { .. }
+                | Self::SkipIfKey { .. }
+                | Self::SkipIfNotKey { .. }
+                | Self::SkipIfKey2 { .. }
+                | Self::SkipIfNotKey2 { .. }
+                | Self::Input { .. }
+                | Self::Unknown { .. }
+        )
+    }
+
+    /// The addresses the instruction writes to in memory, given the current index.
+    pub fn memory_written(&self, index: u16) -> Option<Range<usize>> {
+        let index = index as usize;
+        match *self {
+            Self::Store { x } => Some(index..index + x as usize + 1),
+            Self::StoreBcd { .. } => Some(index..index + 3),
+            // Machine code can write anywhere
+            Self::MachineCall { .. } => Some(0..MEMORY_SIZE),
+            _ => None,
+        }
+    }
+}
@@ -1,34 +1,72 @@
-use log::{error, warn};
-use ndarray::Array2;
-
-pub const FONT: [u8; 80] = [
-    0xF0, 0x90, 0x90, 0x90, 0xF0, // 0
-    0x20, 0x60, 0x20, 0x20, 0x70, // 1
-    0xF0, 0x10, 0xF0, 0x80, 0xF0, // 2
-    0xF0, 0x10, 0xF0, 0x10, 0xF0, // 3
-    0x90, 0x90, 0xF0, 0x10, 0x10, // 4
-    0xF0, 0x80, 0xF0, 0x10, 0xF0, // 5
-    0xF0, 0x80, 0xF0, 0x90, 0xF0, // 6
-    0xF0, 0x10, 0x20, 0x40, 0x40, // 7
-    0xF0, 0x90, 0xF0, 0x90, 0xF0, // 8
-    0xF0, 0x90, 0xF0, 0x10, 0xF0, // 9
-    0xF0, 0x90, 0xF0, 0x90, 0x90, // A
-    0xE0, 0x90, 0xE0, 0x90, 0xE0, // B
-    0xF0, 0x80, 0x80, 0x80, 0xF0, // C
-    0xE0, 0x90, 0x90, 0x90, 0xE0, // D
-    0xF0, 0x80, 0xF0, 0x80, 0xF0, // E
-    0xF0, 0x80, 0xF0, 0x80, 0x80, // F
-];
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+#[macro_use]
+mod macros;
+
+mod beep;
+mod block_cache;
+mod builder;
+mod cdp1802;
+mod chip8x;
+mod damage;
+mod display;
+mod error;
+pub mod fonts;
+mod instruction;
+mod palette;
+mod phosphor;
+#[cfg(feature = "serde")]
+mod serialize;
+mod snapshot;
+mod timing;
+mod vip;
+
+use alloc::{boxed::Box, vec::Vec};
+
+use block_cache::BlockCache;
+use cdp1802::Cdp1802;
+use fonts::LARGE_FONT_CHAR_SIZE;
+use timing::Outcome;
+
+pub use beep::{SquareWave, BEEP_FREQUENCY};
+pub use builder::MachineBuilder;
+pub use chip8x::{
+    ColorMap, Platform, BACKGROUND_COLORS, CHIP8X_PROGRAM_START, COLOR_COLUMNS, COLOR_ZONE_HEIGHT,
+    FOREGROUND_COLORS,
+};
+pub use damage::{Damage, DamageRect, Frame};
+pub use display::{Display, Framebuffer, HiresDisplay, Row};
+pub use error::{Error, LoadError};
+pub use instruction::Instruction;
+pub use palette::{Palette, Rgb};
+pub use phosphor::Phosphor;
+pub use snapshot::{state_size, StateError};
+pub use timing::{
+    VIP_AVAILABLE_CYCLES, VIP_CYCLES_PER_FRAME, VIP_DMA_CYCLES, VIP_INTERRUPT_CYCLES,
+};
+pub use vip::{VIP_DISPLAY, VIP_REGISTERS, VIP_STACK, VIP_STACK_DEPTH};
+
+/// The small font loaded by default.
+pub const FONT: [u8; fonts::SMALL_FONT_SIZE] = fonts::SCHIP;
 pub const FONT_CHAR_SIZE: usize = 5;
 pub const FONT_START: u16 = 0x050;
+/// Where the large font for FX30 goes by default, straight after the small one.
+pub const LARGE_FONT_START: u16 = FONT_START + FONT.len() as u16;
 
 pub const DISPLAY_WIDTH: usize = 64;
 pub const DISPLAY_HEIGHT: usize = 32;
 
+pub const MEMORY_SIZE: usize = 4096;
 pub const PROGRAM_START: u16 = 0x200;
+/// The deepest the call stack can go before 2NNN fails.
+pub const STACK_DEPTH: usize = 16;
 
 pub const CYCLES_PER_SECOND: usize = 700;
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Quirks {
     pub set_vx_to_vy: bool,
     pub fx_incr_index: bool,
@@ -45,6 +83,24 @@ impl Quirks {
             bxnn: false,
         }
     }
+
+    pub const fn cosmac_vip() -> Self {
+        Self {
+            set_vx_to_vy: true,
+            fx_incr_index: true,
+            set_vf_on_fx1e_overflow: false,
+            bxnn: false,
+        }
+    }
+
+    pub const fn superchip() -> Self {
+        Self {
+            set_vx_to_vy: false,
+            fx_incr_index: false,
+            set_vf_on_fx1e_overflow: false,
+            bxnn: true,
+        }
+    }
 }
 
 pub struct AudioDriver {
@@ -52,18 +108,13 @@ pub struct AudioDriver {
     pub stop_beep: fn(),
 }
 
-pub struct InputDriver {
-    pub get_key_pressed: fn() -> Option<u8>,
-}
-
 pub struct Drivers {
     pub audio: AudioDriver,
-    pub input: InputDriver,
 }
 
 impl Drivers {
-    pub fn new(audio: AudioDriver, input: InputDriver) -> Self {
-        Self { audio, input }
+    pub fn new(audio: AudioDriver) -> Self {
+        Self { audio }
     }
 
     pub fn noop() -> Self {
@@ -72,17 +123,14 @@ impl Drivers {
                 start_beep: || {},
                 stop_beep: || {},
             },
-            input: InputDriver {
-                get_key_pressed: || None,
-            },
         }
     }
 }
 
 #[must_use]
 pub struct Machine {
-    pub memory: [u8; 4096],
-    pub display: Array2<bool>,
+    pub memory: [u8; MEMORY_SIZE],
+    pub display: Display,
     pub pc: u16,
     pub index: u16,
     pub stack: Vec<u16>,
@@ -90,34 +138,234 @@ pub struct Machine {
     pub dt: u8,
     pub st: u8,
     pub registers: [u8; 16],
-
-    pub is_dirty: bool,
+    /// Bitmask of the keys held down, with bit `n` set if key `n` is pressed.
+    /// Frontends update this with [`Machine::set_key`] or by setting it directly.
+    pub keypad: u16,
+
+    /// The parts of the display changed since the last call to [`Machine::take_frame`].
+    pub damage: Damage,
+    /// Bitmask of the keys that the ROM has checked (with EX9E, EXA1 or FX0A)
+    /// since the frontend last cleared it, so that frontends can show them.
+    pub polled_keys: u16,
+
+    /// Bitmask of the keys held down on CHIP-8X's second keypad, like `keypad`.
+    pub keypad2: u16,
+    /// The colors that CHIP-8X lays over the display.
+    pub colors: ColorMap,
+    /// The last byte that FXF8 wrote to CHIP-8X's I/O port, which sets the pitch of
+    /// the VP-595 sound board.
+    pub output_port: u8,
+    /// A byte waiting on CHIP-8X's I/O port, which FXFB waits for and takes.
+    pub input_port: Option<u8>,
 
     pub quirks: Quirks,
     pub drivers: Drivers,
+    /// The random number generator used by CXNN.
+    pub rng: fastrand::Rng,
+    /// How much of `memory` is addressable; addresses wrap around past it.
+    memory_size: usize,
+    font_address: u16,
+    large_font_address: u16,
+    /// The machine cycles [`Machine::run_vip_frame`] has left, which goes negative
+    /// when an instruction runs past the end of a frame.
+    vip_cycles: i32,
+    /// Decoded instructions for [`Machine::run_frame`], if enabled.
+    block_cache: Option<Box<BlockCache>>,
+    /// The CPU that runs 0NNN machine code subroutines, if enabled.
+    cdp1802: Option<Cdp1802>,
+    /// Decides how opcodes are decoded.
+    platform: Platform,
+    /// Whether the registers, call stack and display are kept in `memory` where
+    /// the VIP's interpreter kept them.
+    vip_memory_map: bool,
 }
 
 impl Machine {
-    pub fn from_rom(rom: &[u8], quirks: Quirks, drivers: Drivers) -> Self {
-        let mut memory = [0; 4096];
-        memory[FONT_START as usize..FONT_START as usize + FONT.len()].copy_from_slice(&FONT);
-        memory[PROGRAM_START as usize..PROGRAM_START as usize + rom.len()].copy_from_slice(rom);
+    /// Loads `rom` at [`PROGRAM_START`], failing if it doesn't fit in memory.
+    /// Use [`Machine::builder`] to change the memory layout.
+    pub fn from_rom(rom: &[u8], quirks: Quirks, drivers: Drivers) -> Result<Self, LoadError> {
+        Self::builder(rom).quirks(quirks).drivers(drivers).build()
+    }
 
-        Self {
-            memory,
-            display: Array2::from_elem([DISPLAY_WIDTH, DISPLAY_HEIGHT], false),
-            pc: PROGRAM_START,
-            index: 0,
-            stack: Vec::new(),
+    pub fn builder(rom: &[u8]) -> MachineBuilder<'_> {
+        MachineBuilder::new(rom)
+    }
+
+    /// The number of addressable bytes at the start of `memory`.
+    pub fn memory_size(&self) -> usize {
+        self.memory_size
+    }
+
+    /// The address of the font that FX29 points I into.
+    pub fn font_address(&self) -> u16 {
+        self.font_address
+    }
+
+    /// The address of the large font that FX30 points I into.
+    pub fn large_font_address(&self) -> u16 {
+        self.large_font_address
+    }
+
+    /// The system the ROM was written for, set with [`MachineBuilder::platform`].
+    pub fn platform(&self) -> Platform {
+        self.platform
+    }
+
+    /// Whether the machine keeps its registers, call stack and display in the
+    /// VIP's work area, set with [`MachineBuilder::vip_memory_map`].
+    pub fn vip_memory_map(&self) -> bool {
+        self.vip_memory_map
+    }
+
+    /// The deepest the call stack can go before 2NNN fails.
+    fn stack_depth(&self) -> usize {
+        if self.vip_memory_map {
+            VIP_STACK_DEPTH
+        } else {
+            STACK_DEPTH
+        }
+    }
+
+    pub fn set_key(&mut self, key: u8, pressed: bool) {
+        let bit = 1 << (key & 0xF);
+        if pressed {
+            self.keypad |= bit;
+        } else {
+            self.keypad &= !bit;
+        }
+    }
+
+    pub fn is_key_pressed(&self, key: u8) -> bool {
+        self.keypad & (1 << (key & 0xF)) != 0
+    }
+
+    /// Runs one 60 Hz frame: decrements the timers, then executes `cycles`
+    /// instructions, stopping at the first error.
+    pub fn run_frame(&mut self, cycles: usize) -> Result<(), Error> {
+        self.decr_timers();
+
+        if let Some(mut cache) = self.block_cache.take() {
+            let result = self.run_cached(&mut cache, cycles);
+            self.block_cache = Some(cache);
+            return result;
+        }
+
+        for _ in 0..cycles {
+            self.cycle()?;
+        }
+
+        Ok(())
+    }
+
+    /// Runs one 60 Hz frame with the timing of the COSMAC VIP: decrements the
+    /// timers, then executes instructions until the machine cycles left over
+    /// after the display and the vertical blank interrupt are used up, stopping at
+    /// the first error.
+    ///
+    /// Each instruction costs what it did on the VIP, so ROMs run at their original
+    /// speed. Like the original interpreter, DXYN waits for the next vertical blank
+    /// before drawing, so at most one sprite is drawn each frame.
+    pub fn run_vip_frame(&mut self) -> Result<(), Error> {
+        self.decr_timers();
+        // Cycles an instruction took past the end of the last frame come out of this one
+        self.vip_cycles =
+            (self.vip_cycles + VIP_AVAILABLE_CYCLES as i32).min(VIP_AVAILABLE_CYCLES as i32);
+
+        let mut start_of_frame = true;
+        while self.vip_cycles > 0 {
+            let instruction = self.fetch();
+            let mut outcome = Outcome::default();
+            if let Instruction::Draw { x, y, height } = instruction {
+                if !start_of_frame {
+                    // The rest of the frame is spent waiting for the interrupt
+                    self.vip_cycles = 0;
+                    break;
+                }
+                let x = self.registers[x as usize] as usize % DISPLAY_WIDTH;
+                let y = self.registers[y as usize] as usize % DISPLAY_HEIGHT;
+                outcome.rows = (height as usize).min(DISPLAY_HEIGHT - y);
+                outcome.aligned = x.is_multiple_of(8);
+            }
+            start_of_frame = false;
+
+            let pc = self.pc as usize;
+            if let Instruction::MachineCall { address } = instruction {
+                self.pc = ((pc + 2) % self.memory_size) as u16;
+                outcome.machine_code_cycles = self.run_machine_code(address)?;
+            } else {
+                self.cycle()?;
+            }
+            outcome.skipped = self.pc as usize == (pc + 4) % self.memory_size;
+            self.vip_cycles -= timing::instruction_cycles(instruction, outcome) as i32;
+        }
+
+        Ok(())
+    }
+
+    /// Enables or disables caching decoded basic blocks in [`Machine::run_frame`],
+    /// which saves decoding the same instructions every time a loop comes around.
+    ///
+    /// The cache notices when the ROM writes over its own code with FX55 or FX33,
+    /// but not when memory is changed from outside, so frontends that poke at
+    /// `memory` directly need to call [`Machine::invalidate_block_cache`] afterwards.
+    pub fn set_block_cache(&mut self, enabled: bool) {
+        self.block_cache =
+            enabled.then(|| Box::new(BlockCache::new(self.memory_size, self.platform)));
+    }
+
+    /// Throws away any cached blocks, so that they're decoded from memory again.
+    pub fn invalidate_block_cache(&mut self) {
+        if let Some(cache) = &mut self.block_cache {
+            cache.clear();
+        }
+    }
 
-            dt: 0,
-            st: 0,
-            registers: [0; 16],
+    fn run_cached(&mut self, cache: &mut BlockCache, cycles: usize) -> Result<(), Error> {
+        let mut remaining = cycles;
+        while remaining > 0 {
+            let id = cache.block_at(&self.memory[..self.memory_size], self.pc);
+            let block = cache.block(id);
+            if block.is_empty() {
+                // There's no room for a whole instruction, so leave it to the interpreter
+                self.cycle()?;
+                remaining -= 1;
+                continue;
+            }
+
+            let mut stale = false;
+            for &instruction in block.iter().take(remaining) {
+                let written = instruction.memory_written(self.index);
+                remaining -= 1;
+
+                self.pc = ((self.pc as usize + 2) % self.memory_size) as u16;
+                self.execute(instruction)?;
+
+                // Self-modifying code, so the rest of this block may be out of date
+                if written.is_some_and(|range| cache.is_code(range)) {
+                    stale = true;
+                    break;
+                }
+            }
+
+            if stale {
+                cache.clear();
+            }
+        }
+
+        Ok(())
+    }
 
-            is_dirty: false,
+    /// Returns whether the display has changed since the last frame was taken.
+    pub fn is_dirty(&self) -> bool {
+        !self.damage.is_empty()
+    }
 
-            quirks,
-            drivers,
+    /// Returns the display along with what has changed since the last call, so that
+    /// frontends only need to redraw the damaged parts.
+    pub fn take_frame(&mut self) -> Frame<'_> {
+        Frame {
+            display: &self.display,
+            damage: core::mem::take(&mut self.damage),
         }
     }
 
@@ -134,111 +382,150 @@ impl Machine {
         }
     }
 
-    pub fn cycle(&mut self) {
-        let instr = ((self.memory[self.pc as usize] as u16) << 8)
-            | self.memory[self.pc as usize + 1] as u16;
-        self.pc += 2;
+    /// Executes a single instruction. If the instruction can't be executed, it's
+    /// skipped and the error is returned.
+    pub fn cycle(&mut self) -> Result<(), Error> {
+        let instruction = self.fetch();
+        self.pc = ((self.pc as usize + 2) % self.memory_size) as u16;
 
         if self.st > 0 {
             warn!("Start a beep")
         }
 
-        let first_nibble = (instr >> 12) & 0xF;
-        let second_nibble = (instr >> 8) & 0xF;
-        let third_nibble = (instr >> 4) & 0xF;
-        let fourth_nibble = instr & 0xF;
+        self.execute(instruction)
+    }
+
+    /// Decodes the instruction at the PC.
+    fn fetch(&self) -> Instruction {
+        let pc = self.pc as usize;
+        let opcode = ((self.read(pc) as u16) << 8) | self.read(pc + 1) as u16;
+        self.platform.decode(opcode)
+    }
 
-        let x = second_nibble as usize;
-        let y = third_nibble as usize;
-        let n = fourth_nibble;
-        let nn = (third_nibble << 4) | fourth_nibble;
-        let nnn = (second_nibble << 8) | (third_nibble << 4) | fourth_nibble;
+    /// Reads the byte at `address`, wrapping around the end of memory like the
+    /// 12-bit addresses of the original interpreter.
+    fn read(&self, address: usize) -> u8 {
+        self.memory[address % self.memory_size]
+    }
+
+    fn write(&mut self, address: usize, value: u8) {
+        self.memory[address % self.memory_size] = value;
+    }
 
-        match (first_nibble, second_nibble, third_nibble, fourth_nibble) {
-            (0x00, _, _, 0x00) => {
+    /// The address of the instruction being executed, for errors.
+    fn instruction_address(&self) -> u16 {
+        ((self.pc as usize + 2 * self.memory_size - 2) % self.memory_size) as u16
+    }
+
+    /// Executes a decoded instruction, with the PC already pointing past it, so
+    /// that code which decodes ahead of time (like the recompiler) behaves the same
+    /// as [`Machine::cycle`].
+    pub fn execute(&mut self, instruction: Instruction) -> Result<(), Error> {
+        let written = if self.vip_memory_map {
+            instruction.memory_written(self.index)
+        } else {
+            None
+        };
+
+        match instruction {
+            Instruction::ClearScreen => {
                 // Clear the display
-                self.display.fill(false);
-                self.is_dirty = true;
+                self.display.clear();
+                self.damage.add(0..DISPLAY_WIDTH, 0..DISPLAY_HEIGHT);
             }
 
-            (0x01, _, _, _) => {
+            Instruction::Jump { address } => {
                 // Jump to address nnn
-                self.pc = nnn;
+                self.pc = address;
             }
 
-            (0x02, _, _, _) => {
+            Instruction::Call { address } => {
                 // Call subroutine at nnn
+                if self.stack.len() >= self.stack_depth() {
+                    return Err(Error::StackOverflow {
+                        address: self.instruction_address(),
+                    });
+                }
                 self.stack.push(self.pc);
-                self.pc = nnn;
+                self.pc = address;
+            }
+            Instruction::MachineCall { address } => {
+                // Run the machine code subroutine at nnn on the 1802
+                self.run_machine_code(address)?;
             }
-            (0x00, _, _, 0x0E) => {
+            Instruction::Return => {
                 // Returning from a subroutine
                 match self.stack.pop() {
                     Some(addr) => self.pc = addr,
-                    None => warn!("Attempted to return from a subroutine with an empty stack"),
+                    None => {
+                        return Err(Error::StackUnderflow {
+                            address: self.instruction_address(),
+                        })
+                    }
                 }
             }
 
-            (0x03, _, _, _) => {
+            Instruction::SkipIfEqual { x, value } => {
                 // Skip next instruction if register `x` equals `nn`
-                if self.registers[x] == nn as u8 {
+                if self.registers[x as usize] == value {
                     self.pc += 2;
                 }
             }
-            (0x04, _, _, _) => {
+            Instruction::SkipIfNotEqual { x, value } => {
                 // Skip next instruction if register `x` doesn't equal `nn`
-                if self.registers[x] != nn as u8 {
+                if self.registers[x as usize] != value {
                     self.pc += 2;
                 }
             }
-            (0x05, _, _, _) => {
+            Instruction::SkipIfRegistersEqual { x, y } => {
                 // Skip next instruction if register `x` equals register `y`
-                if self.registers[x] == self.registers[y] {
+                if self.registers[x as usize] == self.registers[y as usize] {
                     self.pc += 2;
                 }
             }
-            (0x09, _, _, _) => {
+            Instruction::SkipIfRegistersNotEqual { x, y } => {
                 // Skip next instruction if register `x` doesn't equal register `y`
-                if self.registers[x] != self.registers[y] {
+                if self.registers[x as usize] != self.registers[y as usize] {
                     self.pc += 2;
                 }
             }
 
-            (0x06, _, _, _) => {
+            Instruction::Set { x, value } => {
                 // Set register `x` to `nn`
-                self.registers[x] = nn as u8;
+                self.registers[x as usize] = value;
             }
-            (0x07, _, _, _) => {
+            Instruction::Add { x, value } => {
                 // Add `nn` to register `x`
-                self.registers[x] = self.registers[x].wrapping_add(nn as u8);
+                self.registers[x as usize] = self.registers[x as usize].wrapping_add(value);
             }
-            (0x0A, _, _, _) => {
+            Instruction::SetIndex { address } => {
                 // Set index register to `nnn`
-                self.index = nnn;
+                self.index = address;
             }
 
-            (0x08, _, _, 0x00) => {
+            Instruction::Copy { x, y } => {
                 // Set register `x` to the value of register `y`
-                self.registers[x] = self.registers[y];
+                self.registers[x as usize] = self.registers[y as usize];
             }
-            (0x08, _, _, 0x01) => {
+            Instruction::Or { x, y } => {
                 // Set register `x` to `x` OR `y`
-                self.registers[x] |= self.registers[y];
+                self.registers[x as usize] |= self.registers[y as usize];
             }
-            (0x08, _, _, 0x02) => {
+            Instruction::And { x, y } => {
                 // Set register `x` to `x` AND `y`
-                self.registers[x] &= self.registers[y];
+                self.registers[x as usize] &= self.registers[y as usize];
             }
-            (0x08, _, _, 0x03) => {
+            Instruction::Xor { x, y } => {
                 // Set register `x` to `x` XOR `y`
-                self.registers[x] ^= self.registers[y];
+                self.registers[x as usize] ^= self.registers[y as usize];
             }
-            (0x08, _, _, 0x04) => {
+            Instruction::AddRegisters { x, y } => {
                 // Add register `y` to register `x`
                 // Set register `F` to 1 if there's an overflow, 0 otherwise
-                let (result, did_overflow) = self.registers[x].overflowing_add(self.registers[y]);
+                let (result, did_overflow) =
+                    self.registers[x as usize].overflowing_add(self.registers[y as usize]);
 
-                self.registers[x] = result;
+                self.registers[x as usize] = result;
 
                 if did_overflow {
                     self.registers[0xF] = 1;
@@ -246,202 +533,268 @@ impl Machine {
                     self.registers[0xF] = 0;
                 }
             }
-            (0x08, _, _, 0x05) => {
+            Instruction::Subtract { x, y } => {
                 // Set register `x` to `x` - `y`
-                let original_x = self.registers[x];
-                self.registers[x] = self.registers[x].wrapping_sub(self.registers[y]);
+                let original_x = self.registers[x as usize];
+                self.registers[x as usize] =
+                    self.registers[x as usize].wrapping_sub(self.registers[y as usize]);
 
-                if original_x >= self.registers[y] {
+                if original_x >= self.registers[y as usize] {
                     self.registers[0xF] = 1;
                 } else {
                     self.registers[0xF] = 0;
                 }
             }
-            (0x08, _, _, 0x07) => {
+            Instruction::SubtractReversed { x, y } => {
                 // Set register `x` to `y` - `x`
-                let original_y = self.registers[y];
-                self.registers[x] = self.registers[y].wrapping_sub(self.registers[x]);
+                let original_x = self.registers[x as usize];
+                let original_y = self.registers[y as usize];
+                self.registers[x as usize] = original_y.wrapping_sub(original_x);
 
-                if original_y >= self.registers[x] {
+                if original_y >= original_x {
                     self.registers[0xF] = 1;
                 } else {
                     self.registers[0xF] = 0;
                 }
             }
-            (0x08, _, _, 0x06) => {
+            Instruction::ShiftRight { x, y } => {
                 // Shift the value of `x` one bit to the right (8XY6)
                 if self.quirks.set_vx_to_vy {
-                    self.registers[x] = self.registers[y];
+                    self.registers[x as usize] = self.registers[y as usize];
                 }
 
-                let original_x = self.registers[x];
-                self.registers[x] >>= 1;
+                let original_x = self.registers[x as usize];
+                self.registers[x as usize] >>= 1;
                 self.registers[0xF] = original_x & 0x1;
             }
-            (0x08, _, _, 0x0E) => {
+            Instruction::ShiftLeft { x, y } => {
                 // Shift the value of `x` one bit to the left (8XY6)
                 if self.quirks.set_vx_to_vy {
-                    self.registers[x] = self.registers[y];
+                    self.registers[x as usize] = self.registers[y as usize];
                 }
 
-                let original_x = self.registers[x];
-                self.registers[x] <<= 1;
+                let original_x = self.registers[x as usize];
+                self.registers[x as usize] <<= 1;
                 self.registers[0xF] = (original_x & 0x80) >> 7;
             }
 
-            (0x0D, _, _, _) => {
+            Instruction::Draw { x, y, height } => {
                 // Draw sprite at `x`, `y` with height `n` (DXYN)
-                let mut x_coord = self.registers[x] as usize % DISPLAY_WIDTH;
-                let mut y_coord = self.registers[y] as usize % DISPLAY_HEIGHT;
-
-                let initial_x = x_coord;
-
-                self.registers[0xF] = 0;
-
-                self.is_dirty = true;
-
-                for yline in 0..n {
-                    let sprite_data = self.memory[self.index as usize + yline as usize];
-
-                    for bit in get_bits(sprite_data) {
-                        if bit && self.display[(x_coord, y_coord)] {
-                            self.display[(x_coord, y_coord)] = false;
-                            self.registers[0xF] = 1;
-                        } else if bit {
-                            self.display[(x_coord, y_coord)] = true;
-                        }
-
-                        // If you reach the right edge of the display, stop drawing this row
-                        x_coord += 1;
-                        if x_coord == DISPLAY_WIDTH {
-                            break;
-                        }
-                    }
-
-                    x_coord = initial_x;
-
-                    y_coord += 1;
-                    if y_coord == DISPLAY_HEIGHT {
-                        break;
-                    }
+                // The starting position wraps, but the sprite itself is clipped at the edges
+                let x_coord = self.registers[x as usize] as usize % DISPLAY_WIDTH;
+                let y_coord = self.registers[y as usize] as usize % DISPLAY_HEIGHT;
+
+                let height = (height as usize).min(DISPLAY_HEIGHT - y_coord);
+                let mut sprite = [0; 15];
+                for (i, byte) in sprite[..height].iter_mut().enumerate() {
+                    *byte = self.read(self.index as usize + i);
                 }
+                let collision = self
+                    .display
+                    .draw_sprite(x_coord, y_coord, &sprite[..height]);
+
+                self.registers[0xF] = collision as u8;
+                self.damage.add(
+                    x_coord..(x_coord + 8).min(DISPLAY_WIDTH),
+                    y_coord..y_coord + height,
+                );
             }
 
-            (0x0F, _, 0x05, _) => {
+            Instruction::Store { x } => {
                 // For FX55, the value of each variable register from V0 to VX inclusive
                 // (if X is 0, then only V0) will be stored in successive memory addresses,
                 // starting with the one that’s stored in I. V0 will be stored at the address
                 // in I, V1 will be stored in I + 1, and so on, until VX is stored in I + X.
-                for x in 0..=x {
-                    self.memory[self.index as usize + x] = self.registers[x];
-                    if self.quirks.fx_incr_index {
-                        self.index += 1
-                    }
+                for i in 0..=x as usize {
+                    self.write(self.index as usize + i, self.registers[i]);
+                }
+                if self.quirks.fx_incr_index {
+                    self.index = self.index.wrapping_add(x as u16 + 1);
                 }
             }
-            (0x0F, _, 0x06, _) => {
+            Instruction::Load { x } => {
                 // FX65 does the opposite; it takes the value stored at the
                 // memory addresses and loads them into the variable registers instead.
-                for x in 0..=x {
-                    self.registers[x] = self.memory[self.index as usize + x];
-                    if self.quirks.fx_incr_index {
-                        self.index += 1
-                    }
+                for i in 0..=x as usize {
+                    self.registers[i] = self.read(self.index as usize + i);
+                }
+                if self.quirks.fx_incr_index {
+                    self.index = self.index.wrapping_add(x as u16 + 1);
                 }
             }
 
-            (0x0F, _, 0x01, 0x0E) => {
+            Instruction::AddToIndex { x } => {
                 // The index register I will get the value in VX added to it.
-                let result = self.index.wrapping_add(self.registers[x] as u16);
+                let result = self.index.wrapping_add(self.registers[x as usize] as u16);
                 self.index = result;
-                if (result <= 0x0FFF || result >= 0x1000) && self.quirks.set_vf_on_fx1e_overflow {
-                    self.registers[0xF] = 1;
+                // VF is set if I goes past the end of memory, as on the Amiga interpreter
+                if self.quirks.set_vf_on_fx1e_overflow {
+                    self.registers[0xF] = (result > 0x0FFF) as u8;
                 }
             }
 
-            (0x0B, _, _, _) => {
+            Instruction::JumpWithOffset { address, x } => {
                 if self.quirks.bxnn {
                     // Jump to the address XNN, plus the value in the register VX.
-                    self.pc = nnn + self.registers[x] as u16;
+                    self.pc = address + self.registers[x as usize] as u16;
                 } else {
-                    self.pc = nnn + self.registers[0] as u16;
+                    self.pc = address + self.registers[0] as u16;
                 }
             }
 
-            (0x0F, _, 0x03, 0x03) => {
+            Instruction::StoreBcd { x } => {
                 // Takes the number in VX (which is one byte, so it can be any number from 0 to 255) and
                 // converts it to three decimal digits, storing these digits in memory at
                 // the address in the index register I. For example, if VX contains 156 (or 9C in hexadecimal),
                 // it would put the number 1 at the address in I, 5 in address I + 1, and 6 in address I + 2.
-                let value = self.registers[x];
-                self.memory[self.index as usize] = value / 100;
-                self.memory[self.index as usize + 1] = (value / 10) % 10;
-                self.memory[self.index as usize + 2] = value % 10;
+                let value = self.registers[x as usize];
+                let index = self.index as usize;
+                self.write(index, value / 100);
+                self.write(index + 1, (value / 10) % 10);
+                self.write(index + 2, value % 10);
             }
 
             // Input
-            (0x0F, _, 0x00, 0x0A) => match (self.drivers.input.get_key_pressed)() {
+            Instruction::WaitForKey { x } => {
                 // This instruction “blocks”; it stops executing instructions and waits for
                 // key input (or loops forever, unless a key is pressed).
                 // To loop while still decrementing the times, we just decrement the program counter.
                 // This means that the program will go to this instruction again and again, until
                 // a key is pressed.
-                Some(key) => {
-                    log::debug!("Key pressed: {:X}", key);
-                    self.registers[x] = key;
+                self.polled_keys = u16::MAX;
+                if self.keypad == 0 {
+                    self.pc = self.instruction_address();
+                } else {
+                    let key = self.keypad.trailing_zeros() as u8;
+                    debug!("Key pressed: {:X}", key);
+                    self.registers[x as usize] = key;
                 }
-                None => self.pc -= 2,
-            },
-            (0x0E, _, 0x09, 0x0E) => {
+            }
+            Instruction::SkipIfKey { x } => {
                 // Skip next instruction if key with the value of VX is pressed
-                if (self.drivers.input.get_key_pressed)() == Some(self.registers[x]) {
+                self.polled_keys |= 1 << (self.registers[x as usize] & 0xF);
+                if self.is_key_pressed(self.registers[x as usize]) {
                     self.pc += 2;
                 }
             }
-            (0x0E, _, 0x0A, 0x01) => {
+            Instruction::SkipIfNotKey { x } => {
                 // Skip next instruction if key with the value of VX is not pressed
-                if (self.drivers.input.get_key_pressed)() != Some(self.registers[x]) {
+                self.polled_keys |= 1 << (self.registers[x as usize] & 0xF);
+                if !self.is_key_pressed(self.registers[x as usize]) {
                     self.pc += 2;
                 }
             }
 
             // Timers
-            (0x0F, _, 0x00, 0x07) => {
+            Instruction::GetDelayTimer { x } => {
                 // Set VX to the value of the delay timer
-                self.registers[x] = self.dt;
+                self.registers[x as usize] = self.dt;
             }
-            (0x0F, _, 0x01, 0x05) => {
+            Instruction::SetDelayTimer { x } => {
                 // Sets the delay timer to the value in VX
-                self.dt = self.registers[x];
+                self.dt = self.registers[x as usize];
             }
-            (0x0F, _, 0x01, 0x08) => {
+            Instruction::SetSoundTimer { x } => {
                 // Sets the sound timer to the value in VX
-                self.st = self.registers[x];
+                self.st = self.registers[x as usize];
             }
 
-            (0x0F, _, 0x02, 0x09) => {
+            Instruction::FontCharacter { x } => {
                 // Set I to the address of the hexadecimal character in VX.
-                self.index = FONT_START + (self.registers[x] & 0xF) as u16 * FONT_CHAR_SIZE as u16;
+                self.index = self.font_address
+                    + (self.registers[x as usize] & 0xF) as u16 * FONT_CHAR_SIZE as u16;
+            }
+            Instruction::LargeFontCharacter { x } => {
+                // Set I to the address of the 8x10 hexadecimal character in VX (SUPER-CHIP).
+                self.index = self.large_font_address
+                    + (self.registers[x as usize] & 0xF) as u16 * LARGE_FONT_CHAR_SIZE as u16;
             }
 
-            (0x0C, _, _, _) => {
+            Instruction::Random { x, mask } => {
                 // CXNN generates a random number, binary ANDs it with the value NN, and puts the result in VX.
-                self.registers[x] = fastrand::u8(0..u8::MAX) & nn as u8;
+                self.registers[x as usize] = self.rng.u8(..) & mask;
+            }
+
+            // CHIP-8X
+            Instruction::CycleBackground => {
+                self.colors.cycle_background();
+                self.damage.add(0..DISPLAY_WIDTH, 0..DISPLAY_HEIGHT);
+            }
+            Instruction::SetColorZones { x, y } => {
+                // The low nibbles of VX and VX+1 are the first zone across and down,
+                // and the high nibbles are how many more zones to color
+                let across = self.registers[x as usize];
+                let down = self.registers[(x as usize + 1) & 0xF];
+                let start = (across & 0x7) as usize;
+                let columns = start..(start + (across >> 4) as usize + 1).min(COLOR_COLUMNS);
+                let zone_rows = DISPLAY_HEIGHT / COLOR_ZONE_HEIGHT;
+                let start = (down & 0x7) as usize;
+                let end = (start + (down >> 4) as usize + 1).min(zone_rows);
+                let rows = start * COLOR_ZONE_HEIGHT..end * COLOR_ZONE_HEIGHT;
+
+                self.damage
+                    .add(columns.start * 8..columns.end * 8, rows.clone());
+                self.colors.fill(columns, rows, self.registers[y as usize]);
+            }
+            Instruction::SetColorRows { x, y, height } => {
+                // Colors the 8 pixels across and `height` rows down from (VX, VX+1)
+                let column = self.registers[x as usize] as usize % DISPLAY_WIDTH / 8;
+                let top = self.registers[(x as usize + 1) & 0xF] as usize % DISPLAY_HEIGHT;
+                let rows = top..(top + height as usize).min(DISPLAY_HEIGHT);
+
+                self.damage.add(column * 8..column * 8 + 8, rows.clone());
+                self.colors
+                    .fill(column..column + 1, rows, self.registers[y as usize]);
+            }
+            Instruction::SkipIfKey2 { x } => {
+                // Skip next instruction if key VX is pressed on the second keypad
+                if self.keypad2 & (1 << (self.registers[x as usize] & 0xF)) != 0 {
+                    self.pc += 2;
+                }
+            }
+            Instruction::SkipIfNotKey2 { x } => {
+                if self.keypad2 & (1 << (self.registers[x as usize] & 0xF)) == 0 {
+                    self.pc += 2;
+                }
+            }
+            Instruction::Output { x } => {
+                self.output_port = self.registers[x as usize];
+            }
+            Instruction::Input { x } => {
+                // Blocks until there's a byte on the port, like FX0A
+                match self.input_port.take() {
+                    Some(byte) => self.registers[x as usize] = byte,
+                    None => self.pc = self.instruction_address(),
+                }
             }
 
-            _ => {
-                error!("Unknown instruction: {:04X}", instr);
+            Instruction::Unknown { opcode } => {
+                return Err(Error::UnknownInstruction {
+                    opcode,
+                    address: self.instruction_address(),
+                });
             }
         }
+
+        // Jumps and skips can go past the end of memory, which wraps around
+        self.pc %= self.memory_size as u16;
+
+        if self.vip_memory_map {
+            self.sync_vip_memory(instruction, written);
+        }
+
+        Ok(())
     }
 }
 
-/// Get the bits of a byte in **big-endian** order.
-fn get_bits(value: u8) -> [bool; 8] {
-    let mut bits = [false; 8];
-    for i in 0..8 {
-        bits[7 - i] = (value & (1 << i)) != 0;
-    }
+/// Creates a randomly seeded RNG. Without `std` there's no source of entropy, so
+/// the RNG starts from a fixed seed; reseed [`Machine::rng`] to vary it.
+#[cfg(feature = "std")]
+fn new_rng() -> fastrand::Rng {
+    fastrand::Rng::new()
+}
 
-    bits
+#[cfg(not(feature = "std"))]
+fn new_rng() -> fastrand::Rng {
+    fastrand::Rng::with_seed(0x5EED_C8C8)
 }
@@ -0,0 +1,26 @@
+//! Logging macros that forward to the `log` crate when the `log` feature is
+//! enabled, and compile to nothing otherwise.
+
+#[cfg(feature = "log")]
+macro_rules! debug {
+    ($($arg:tt)*) => { log::debug!($($arg)*) };
+}
+
+#[cfg(not(feature = "log"))]
+macro_rules! debug {
+    ($($arg:tt)*) => {
+        ()
+    };
+}
+
+#[cfg(feature = "log")]
+macro_rules! warn {
+    ($($arg:tt)*) => { log::warn!($($arg)*) };
+}
+
+#[cfg(not(feature = "log"))]
+macro_rules! warn {
+    ($($arg:tt)*) => {
+        ()
+    };
+}
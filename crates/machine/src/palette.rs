@@ -0,0 +1,81 @@
+/// A color, with 8 bits per channel.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Rgb {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
+
+impl Rgb {
+    pub const fn new(r: u8, g: u8, b: u8) -> Self {
+        Self { r, g, b }
+    }
+
+    /// Parses a color in `#RRGGBB` form (the `#` is optional).
+    pub fn from_hex(hex: &str) -> Option<Self> {
+        let digits = hex.trim().strip_prefix('#').unwrap_or(hex.trim());
+        if digits.len() != 6 || !digits.bytes().all(|c| c.is_ascii_hexdigit()) {
+            return None;
+        }
+
+        let value = u32::from_str_radix(digits, 16).ok()?;
+        Some(Self::new(
+            (value >> 16) as u8,
+            (value >> 8) as u8,
+            value as u8,
+        ))
+    }
+
+    /// Linearly interpolates from `self` (at 0.0) to `other` (at 1.0).
+    pub fn lerp(self, other: Self, t: f32) -> Self {
+        let t = t.clamp(0.0, 1.0);
+        // Adding 0.5 before truncating rounds to the nearest value, without needing
+        // `f32::round` from std
+        let channel = |from: u8, to: u8| (from as f32 + (to as f32 - from as f32) * t + 0.5) as u8;
+        Self::new(
+            channel(self.r, other.r),
+            channel(self.g, other.g),
+            channel(self.b, other.b),
+        )
+    }
+}
+
+/// The colors used to draw the display.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Palette {
+    /// The color of unlit pixels.
+    pub background: Rgb,
+    /// The color of lit pixels.
+    pub foreground: Rgb,
+}
+
+impl Palette {
+    /// White on black.
+    pub const CLASSIC: Self = Self::new(Rgb::new(0x00, 0x00, 0x00), Rgb::new(0xFF, 0xFF, 0xFF));
+    /// An amber monochrome monitor.
+    pub const AMBER: Self = Self::new(Rgb::new(0x1A, 0x0F, 0x00), Rgb::new(0xFF, 0xB0, 0x00));
+    /// A green phosphor monochrome monitor.
+    pub const GREEN_PHOSPHOR: Self =
+        Self::new(Rgb::new(0x00, 0x14, 0x05), Rgb::new(0x33, 0xFF, 0x66));
+    /// A green-tinted handheld LCD.
+    pub const LCD: Self = Self::new(Rgb::new(0x9B, 0xBC, 0x0F), Rgb::new(0x0F, 0x38, 0x0F));
+
+    pub const fn new(background: Rgb, foreground: Rgb) -> Self {
+        Self {
+            background,
+            foreground,
+        }
+    }
+
+    /// The color of a pixel that is lit with the given intensity, from 0.0 (the
+    /// background) to 1.0 (the foreground).
+    pub fn color(&self, intensity: f32) -> Rgb {
+        self.background.lerp(self.foreground, intensity)
+    }
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+        Self::CLASSIC
+    }
+}
@@ -0,0 +1,66 @@
+use alloc::{vec, vec::Vec};
+
+use crate::{Framebuffer, Row};
+
+/// Intensities below this are treated as fully faded.
+const CUTOFF: f32 = 1.0 / 256.0;
+
+/// Simulates the afterglow of a phosphor screen by blending the last few frames
+/// of the display.
+///
+/// CHIP-8 games erase sprites by drawing them again with XOR, so a moving sprite
+/// is often missing from every other frame. Keeping some of each pixel's previous
+/// brightness hides most of that flicker. Call [`Phosphor::update`] once per
+/// frame, then draw each pixel with its [`Phosphor::intensity`].
+pub struct Phosphor {
+    /// How much of a pixel's brightness is kept each frame once it's turned off,
+    /// from 0.0 (none, so this does nothing) to just below 1.0 (a long trail).
+    pub decay: f32,
+    width: usize,
+    /// Row-major pixel intensities.
+    intensities: Vec<f32>,
+}
+
+impl Phosphor {
+    pub fn new(decay: f32) -> Self {
+        Self {
+            decay: decay.clamp(0.0, 0.99),
+            width: 0,
+            intensities: Vec::new(),
+        }
+    }
+
+    /// Blends a new frame into the afterglow. If the display's size has changed,
+    /// the afterglow is reset.
+    pub fn update<R: Row, const HEIGHT: usize>(&mut self, display: &Framebuffer<R, HEIGHT>) {
+        if self.width != display.width() || self.intensities.len() != R::WIDTH * HEIGHT {
+            self.width = display.width();
+            self.intensities = vec![0.0; R::WIDTH * HEIGHT];
+        }
+
+        for ((x, y), pixel_on) in display.iter() {
+            let intensity = &mut self.intensities[y * self.width + x];
+            *intensity = if pixel_on {
+                1.0
+            } else if *intensity * self.decay >= CUTOFF {
+                *intensity * self.decay
+            } else {
+                0.0
+            };
+        }
+    }
+
+    /// The brightness of a pixel, from 0.0 (unlit) to 1.0 (lit this frame).
+    pub fn intensity(&self, x: usize, y: usize) -> f32 {
+        self.intensities[y * self.width + x]
+    }
+
+    /// Iterates over every pixel that is at least partly lit, with its intensity.
+    pub fn lit_pixels(&self) -> impl Iterator<Item = ((usize, usize), f32)> + '_ {
+        self.intensities
+            .iter()
+            .enumerate()
+            .filter(|(_, intensity)| **intensity > 0.0)
+            .map(|(i, intensity)| ((i % self.width, i / self.width), *intensity))
+    }
+}
@@ -0,0 +1,307 @@
+//! Serde support for the machine's state, behind the `serde` feature.
+
+use alloc::{string::String, vec::Vec};
+use core::fmt;
+
+use serde::{
+    de::{self, SeqAccess, Visitor},
+    ser::SerializeSeq,
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+
+use crate::{
+    cdp1802::Cdp1802,
+    fonts::{LARGE_FONT_SIZE, SMALL_FONT_SIZE},
+    ColorMap, Damage, Display, Drivers, Framebuffer, Machine, Platform, Quirks, Row,
+    DISPLAY_HEIGHT, FONT_START, LARGE_FONT_START, MEMORY_SIZE,
+};
+
+/// Lit and unlit pixels, when the display is serialized as one string per row.
+const LIT: char = '#';
+const UNLIT: char = '.';
+
+/// Serializes memory as a hex string, rather than as thousands of numbers.
+mod hex {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(
+        memory: &[u8; MEMORY_SIZE],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        const DIGITS: &[u8; 16] = b"0123456789abcdef";
+        let hex: String = memory
+            .iter()
+            .flat_map(|byte| [byte >> 4, byte & 0xF])
+            .map(|digit| DIGITS[digit as usize] as char)
+            .collect();
+        serializer.serialize_str(&hex)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<[u8; MEMORY_SIZE], D::Error> {
+        let hex = String::deserialize(deserializer)?;
+        if hex.len() != MEMORY_SIZE * 2 {
+            return Err(de::Error::invalid_length(hex.len(), &"8192 hex digits"));
+        }
+
+        let mut memory = [0; MEMORY_SIZE];
+        for (byte, digits) in memory.iter_mut().zip(hex.as_bytes().chunks(2)) {
+            let digits = core::str::from_utf8(digits).map_err(de::Error::custom)?;
+            *byte = u8::from_str_radix(digits, 16).map_err(|_| {
+                de::Error::invalid_value(de::Unexpected::Str(digits), &"hex digits")
+            })?;
+        }
+        Ok(memory)
+    }
+}
+
+/// The display is serialized as one string per row, with `#` for lit pixels and
+/// `.` for unlit ones, so that it can be read at a glance.
+impl<R: Row, const HEIGHT: usize> Serialize for Framebuffer<R, HEIGHT> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut rows = serializer.serialize_seq(Some(HEIGHT))?;
+        for y in 0..HEIGHT {
+            let row: String = (0..R::WIDTH)
+                .map(|x| if self.get(x, y) { LIT } else { UNLIT })
+                .collect();
+            rows.serialize_element(&row)?;
+        }
+        rows.end()
+    }
+}
+
+impl<'de, R: Row, const HEIGHT: usize> Deserialize<'de> for Framebuffer<R, HEIGHT> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct RowsVisitor<R: Row, const HEIGHT: usize>(core::marker::PhantomData<R>);
+
+        impl<'de, R: Row, const HEIGHT: usize> Visitor<'de> for RowsVisitor<R, HEIGHT> {
+            type Value = Framebuffer<R, HEIGHT>;
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                write!(f, "{HEIGHT} rows of {} `#` or `.` pixels", R::WIDTH)
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+                let mut display = Framebuffer::new();
+                for y in 0..HEIGHT {
+                    let row: String = seq
+                        .next_element()?
+                        .ok_or_else(|| de::Error::invalid_length(y, &self))?;
+                    if row.chars().count() != R::WIDTH {
+                        return Err(de::Error::invalid_value(de::Unexpected::Str(&row), &self));
+                    }
+
+                    for (x, pixel) in row.chars().enumerate() {
+                        match pixel {
+                            LIT => display.set(x, y, true),
+                            UNLIT => {}
+                            _ => {
+                                return Err(de::Error::invalid_value(
+                                    de::Unexpected::Char(pixel),
+                                    &self,
+                                ))
+                            }
+                        }
+                    }
+                }
+                if seq.next_element::<de::IgnoredAny>()?.is_some() {
+                    return Err(de::Error::invalid_length(HEIGHT + 1, &self));
+                }
+
+                Ok(display)
+            }
+        }
+
+        deserializer.deserialize_seq(RowsVisitor(core::marker::PhantomData))
+    }
+}
+
+/// CHIP-8X's colors are serialized as the background and one string per row,
+/// with a digit for the foreground color of each 8 pixels.
+#[derive(Serialize, Deserialize)]
+struct ColorMapState {
+    background: u8,
+    foreground: Vec<String>,
+}
+
+impl Serialize for ColorMap {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        ColorMapState {
+            background: self.background,
+            foreground: self
+                .foreground
+                .iter()
+                .map(|row| row.iter().map(|color| (b'0' + color) as char).collect())
+                .collect(),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for ColorMap {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        const EXPECTED: &str = "rows of 8 digits from 0 to 7";
+        let state = ColorMapState::deserialize(deserializer)?;
+        if state.foreground.len() != DISPLAY_HEIGHT {
+            return Err(de::Error::invalid_length(state.foreground.len(), &EXPECTED));
+        }
+
+        let mut colors = ColorMap::new();
+        colors.background = state.background;
+        for (row, digits) in colors.foreground.iter_mut().zip(&state.foreground) {
+            if digits.len() != row.len() || !digits.bytes().all(|c| (b'0'..=b'7').contains(&c)) {
+                return Err(de::Error::invalid_value(
+                    de::Unexpected::Str(digits),
+                    &EXPECTED,
+                ));
+            }
+            for (color, digit) in row.iter_mut().zip(digits.bytes()) {
+                *color = digit - b'0';
+            }
+        }
+        Ok(colors)
+    }
+}
+
+/// The parts of a [`Machine`] that are serialized: everything but the drivers and
+/// the bookkeeping for frontends.
+#[derive(Serialize, Deserialize)]
+struct MachineState {
+    #[serde(with = "hex")]
+    memory: [u8; MEMORY_SIZE],
+    display: Display,
+    pc: u16,
+    index: u16,
+    stack: Vec<u16>,
+    dt: u8,
+    st: u8,
+    registers: [u8; 16],
+    keypad: u16,
+    quirks: Quirks,
+    /// The state of the random number generator, so that a deserialized machine
+    /// generates the same numbers.
+    rng_seed: u64,
+    /// Defaults to the standard layout, for states saved before these were configurable.
+    #[serde(default = "default_memory_size")]
+    memory_size: usize,
+    #[serde(default = "default_font_address")]
+    font_address: u16,
+    #[serde(default = "default_large_font_address")]
+    large_font_address: u16,
+    #[serde(default)]
+    vip_memory_map: bool,
+    #[serde(default)]
+    cdp1802: bool,
+    #[serde(default)]
+    platform: Platform,
+    #[serde(default)]
+    keypad2: u16,
+    #[serde(default)]
+    colors: ColorMap,
+    #[serde(default)]
+    output_port: u8,
+    #[serde(default)]
+    input_port: Option<u8>,
+}
+
+fn default_memory_size() -> usize {
+    MEMORY_SIZE
+}
+
+fn default_font_address() -> u16 {
+    FONT_START
+}
+
+fn default_large_font_address() -> u16 {
+    LARGE_FONT_START
+}
+
+impl Serialize for Machine {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        MachineState {
+            memory: self.memory,
+            display: self.display.clone(),
+            pc: self.pc,
+            index: self.index,
+            stack: self.stack.clone(),
+            dt: self.dt,
+            st: self.st,
+            registers: self.registers,
+            keypad: self.keypad,
+            quirks: self.quirks,
+            rng_seed: self.rng.get_seed(),
+            memory_size: self.memory_size,
+            font_address: self.font_address,
+            large_font_address: self.large_font_address,
+            vip_memory_map: self.vip_memory_map,
+            cdp1802: self.cdp1802.is_some(),
+            platform: self.platform,
+            keypad2: self.keypad2,
+            colors: self.colors.clone(),
+            output_port: self.output_port,
+            input_port: self.input_port,
+        }
+        .serialize(serializer)
+    }
+}
+
+/// Checks that a font at `address` fits inside memory, like
+/// [`MachineBuilder::build`](crate::MachineBuilder::build) does.
+fn check_font_address<E: de::Error>(
+    address: u16,
+    size: usize,
+    memory_size: usize,
+) -> Result<(), E> {
+    if address as usize + size > memory_size {
+        return Err(E::invalid_value(
+            de::Unexpected::Unsigned(address as u64),
+            &"a font address inside memory",
+        ));
+    }
+    Ok(())
+}
+
+/// A deserialized machine has no-op drivers, which frontends can replace.
+impl<'de> Deserialize<'de> for Machine {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let state = MachineState::deserialize(deserializer)?;
+        if state.memory_size == 0 || state.memory_size > MEMORY_SIZE {
+            return Err(de::Error::invalid_value(
+                de::Unexpected::Unsigned(state.memory_size as u64),
+                &"a memory size between 1 and 4096",
+            ));
+        }
+        check_font_address(state.font_address, SMALL_FONT_SIZE, state.memory_size)?;
+        check_font_address(state.large_font_address, LARGE_FONT_SIZE, state.memory_size)?;
+
+        Ok(Self {
+            memory: state.memory,
+            display: state.display,
+            pc: state.pc,
+            index: state.index,
+            stack: state.stack,
+            dt: state.dt,
+            st: state.st,
+            registers: state.registers,
+            keypad: state.keypad,
+            damage: Damage::full(Display::WIDTH, Display::HEIGHT),
+            polled_keys: 0,
+            keypad2: state.keypad2,
+            colors: state.colors,
+            output_port: state.output_port,
+            input_port: state.input_port,
+            quirks: state.quirks,
+            drivers: Drivers::noop(),
+            rng: fastrand::Rng::with_seed(state.rng_seed),
+            memory_size: state.memory_size,
+            font_address: state.font_address,
+            large_font_address: state.large_font_address,
+            vip_cycles: 0,
+            block_cache: None,
+            cdp1802: state.cdp1802.then(Cdp1802::default),
+            platform: state.platform,
+            vip_memory_map: state.vip_memory_map,
+        })
+    }
+}
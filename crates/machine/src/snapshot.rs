@@ -0,0 +1,170 @@
+use core::fmt;
+
+use alloc::vec::Vec;
+
+use crate::{
+    ColorMap, Damage, Display, Machine, COLOR_COLUMNS, DISPLAY_HEIGHT, DISPLAY_WIDTH, MEMORY_SIZE,
+};
+
+/// Identifies a save state, and catches attempts to load some other file as one.
+const MAGIC: &[u8; 4] = b"C8ST";
+/// Bumped whenever the layout of a save state changes.
+const VERSION: u8 = 2;
+
+/// The size of CHIP-8X's state: the background, the foreground colors and the
+/// output port.
+const CHIP8X_SIZE: usize = 1 + COLOR_COLUMNS * DISPLAY_HEIGHT + 1;
+
+/// The size of everything in a save state except the stack: the header, memory,
+/// display, PC, index, timers, registers, RNG seed, CHIP-8X's state and stack depth.
+const FIXED_SIZE: usize =
+    MAGIC.len() + 1 + MEMORY_SIZE + DISPLAY_HEIGHT * 8 + 2 + 2 + 1 + 1 + 16 + 8 + CHIP8X_SIZE + 1;
+
+/// The size of a save state whose stack holds `stack_depth` return addresses.
+pub const fn state_size(stack_depth: usize) -> usize {
+    FIXED_SIZE + stack_depth * 2
+}
+
+/// An error raised while loading a save state.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StateError {
+    /// The data doesn't start with the save state header.
+    InvalidHeader,
+    /// The state was saved by a version of the emulator with a different layout.
+    UnsupportedVersion(u8),
+    /// The data ends partway through the state.
+    Truncated,
+    /// The PC or a return address is outside this machine's memory, or the call
+    /// stack is deeper than this machine allows.
+    OutOfRange,
+}
+
+impl fmt::Display for StateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidHeader => write!(f, "Not a save state"),
+            Self::UnsupportedVersion(version) => {
+                write!(f, "Unsupported save state version {version}")
+            }
+            Self::Truncated => write!(f, "Save state is truncated"),
+            Self::OutOfRange => write!(f, "Save state doesn't fit this machine's memory"),
+        }
+    }
+}
+
+impl core::error::Error for StateError {}
+
+/// Reads fields from the front of a save state.
+struct Reader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take<const N: usize>(&mut self) -> Result<[u8; N], StateError> {
+        let (bytes, rest) = self.data.split_first_chunk().ok_or(StateError::Truncated)?;
+        self.data = rest;
+        Ok(*bytes)
+    }
+
+    fn u8(&mut self) -> Result<u8, StateError> {
+        Ok(self.take::<1>()?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, StateError> {
+        Ok(u16::from_le_bytes(self.take()?))
+    }
+}
+
+impl Machine {
+    /// Saves everything needed to resume execution later with [`Machine::load_state`].
+    ///
+    /// The quirks and drivers aren't included, since they're set up by the frontend
+    /// rather than by the ROM, and neither are the keypad and damage.
+    pub fn save_state(&self) -> Vec<u8> {
+        let mut state = Vec::with_capacity(state_size(self.stack.len()));
+        state.extend_from_slice(MAGIC);
+        state.push(VERSION);
+
+        state.extend_from_slice(&self.memory);
+        for row in self.display.rows() {
+            state.extend_from_slice(&row.to_be_bytes());
+        }
+        state.extend_from_slice(&self.pc.to_le_bytes());
+        state.extend_from_slice(&self.index.to_le_bytes());
+        state.push(self.dt);
+        state.push(self.st);
+        state.extend_from_slice(&self.registers);
+        state.extend_from_slice(&self.rng.get_seed().to_le_bytes());
+        state.push(self.colors.background);
+        for row in &self.colors.foreground {
+            state.extend_from_slice(row);
+        }
+        state.push(self.output_port);
+
+        state.push(self.stack.len() as u8);
+        for address in &self.stack {
+            state.extend_from_slice(&address.to_le_bytes());
+        }
+
+        state
+    }
+
+    /// Restores a state saved with [`Machine::save_state`]. Any bytes after the end
+    /// of the state are ignored, so it can be read from a larger fixed-size buffer.
+    ///
+    /// The state has to fit this machine: the PC and return addresses inside
+    /// [`Machine::memory_size`], and the stack no deeper than 2NNN lets it go.
+    /// If this returns an error, the machine is left unchanged.
+    pub fn load_state(&mut self, state: &[u8]) -> Result<(), StateError> {
+        let mut reader = Reader { data: state };
+        if &reader.take()? != MAGIC {
+            return Err(StateError::InvalidHeader);
+        }
+        match reader.u8()? {
+            VERSION => {}
+            version => return Err(StateError::UnsupportedVersion(version)),
+        }
+
+        let memory = reader.take::<MEMORY_SIZE>()?;
+        let mut display = Display::new();
+        for y in 0..DISPLAY_HEIGHT {
+            display.set_row(y, u64::from_be_bytes(reader.take()?));
+        }
+        let pc = reader.u16()?;
+        let index = reader.u16()?;
+        let dt = reader.u8()?;
+        let st = reader.u8()?;
+        let registers = reader.take()?;
+        let seed = u64::from_le_bytes(reader.take()?);
+        let mut colors = ColorMap::new();
+        colors.background = reader.u8()?;
+        for row in &mut colors.foreground {
+            *row = reader.take()?;
+        }
+        let output_port = reader.u8()?;
+        let stack: Vec<u16> = (0..reader.u8()?)
+            .map(|_| reader.u16())
+            .collect::<Result<_, _>>()?;
+
+        let in_memory = |address: &u16| (*address as usize) < self.memory_size;
+        if !in_memory(&pc) || !stack.iter().all(in_memory) || stack.len() > self.stack_depth() {
+            return Err(StateError::OutOfRange);
+        }
+
+        self.memory = memory;
+        self.display = display;
+        self.pc = pc;
+        self.index = index;
+        self.dt = dt;
+        self.st = st;
+        self.registers = registers;
+        self.rng = fastrand::Rng::with_seed(seed);
+        self.colors = colors;
+        self.output_port = output_port;
+        self.stack = stack;
+        self.damage = Damage::full(DISPLAY_WIDTH, DISPLAY_HEIGHT);
+        self.invalidate_block_cache();
+
+        Ok(())
+    }
+}
@@ -0,0 +1,98 @@
+//! How long each instruction took on the COSMAC VIP, for [`Machine::run_vip_frame`].
+//!
+//! Costs are in 1802 machine cycles of 8 clock cycles each, at the VIP's
+//! 1.7609 MHz clock. They follow measurements of the original interpreter, which
+//! spends most of its time in sprite drawing, the arithmetic dispatch for 8XYN,
+//! and the loops behind FX33, FX55 and FX65.
+//!
+//! [`Machine::run_vip_frame`]: crate::Machine::run_vip_frame
+
+use crate::Instruction;
+
+/// The machine cycles in each 60 Hz frame.
+pub const VIP_CYCLES_PER_FRAME: u32 = 3668;
+/// The cycles that the display's DMA takes away from the CPU every frame, one per
+/// byte of the 128 scanlines it reads (each row of pixels is shown 4 times).
+pub const VIP_DMA_CYCLES: u32 = 1024;
+/// The cycles the vertical blank interrupt takes to update the timers.
+pub const VIP_INTERRUPT_CYCLES: u32 = 46;
+/// The cycles left over for running instructions each frame.
+pub const VIP_AVAILABLE_CYCLES: u32 = VIP_CYCLES_PER_FRAME - VIP_DMA_CYCLES - VIP_INTERRUPT_CYCLES;
+
+/// The cycles spent fetching an instruction and jumping to its handler.
+const FETCH_CYCLES: u32 = 9;
+
+/// What happened when an instruction ran, where that changes its cost.
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct Outcome {
+    /// Whether a skip instruction skipped.
+    pub skipped: bool,
+    /// The rows of the sprite that DXYN actually drew, after clipping.
+    pub rows: usize,
+    /// Whether DXYN's sprite lined up with a byte of display memory, which saves
+    /// shifting every row across two bytes.
+    pub aligned: bool,
+    /// The machine cycles that 0NNN's machine code subroutine ran for.
+    pub machine_code_cycles: u32,
+}
+
+/// The machine cycles that `instruction` took, including fetching it.
+pub(crate) fn instruction_cycles(instruction: Instruction, outcome: Outcome) -> u32 {
+    let skip = if outcome.skipped { 4 } else { 0 };
+    let execute = match instruction {
+        Instruction::ClearScreen => 24,
+        Instruction::Return => 23,
+        // The interpreter jumps straight into the subroutine with SEP R3
+        Instruction::MachineCall { .. } => 4 + outcome.machine_code_cycles,
+        Instruction::Jump { .. } | Instruction::Call { .. } => 23,
+        Instruction::JumpWithOffset { .. } => 23,
+
+        Instruction::SkipIfEqual { .. } | Instruction::SkipIfNotEqual { .. } => 10 + skip,
+        Instruction::SkipIfRegistersEqual { .. } | Instruction::SkipIfRegistersNotEqual { .. } => {
+            14 + skip
+        }
+        Instruction::SkipIfKey { .. } | Instruction::SkipIfNotKey { .. } => 14 + skip,
+
+        Instruction::Set { .. } => 6,
+        Instruction::Add { .. } => 10,
+        Instruction::SetIndex { .. } => 12,
+        // The ALU instructions are all run by building an 1802 instruction in RAM
+        Instruction::Copy { .. }
+        | Instruction::Or { .. }
+        | Instruction::And { .. }
+        | Instruction::Xor { .. }
+        | Instruction::AddRegisters { .. }
+        | Instruction::Subtract { .. }
+        | Instruction::SubtractReversed { .. }
+        | Instruction::ShiftRight { .. }
+        | Instruction::ShiftLeft { .. } => 44,
+        Instruction::Random { .. } => 36,
+
+        // Unaligned sprites are shifted into two bytes, a bit at a time
+        Instruction::Draw { .. } => {
+            let per_row = if outcome.aligned { 15 } else { 23 };
+            26 + per_row * outcome.rows as u32
+        }
+
+        Instruction::Store { x } | Instruction::Load { x } => 5 + 8 * (x as u32 + 1),
+        Instruction::StoreBcd { .. } => 204,
+        Instruction::AddToIndex { .. } => 19,
+        Instruction::FontCharacter { .. } | Instruction::LargeFontCharacter { .. } => 20,
+
+        // Each check of the keypad while waiting
+        Instruction::WaitForKey { .. } => 10,
+        Instruction::GetDelayTimer { .. }
+        | Instruction::SetDelayTimer { .. }
+        | Instruction::SetSoundTimer { .. } => 10,
+
+        // CHIP-8X, which ran on the same hardware
+        Instruction::CycleBackground => 20,
+        Instruction::SetColorZones { .. } => 80,
+        Instruction::SetColorRows { height, .. } => 26 + 6 * height as u32,
+        Instruction::SkipIfKey2 { .. } | Instruction::SkipIfNotKey2 { .. } => 14 + skip,
+        Instruction::Output { .. } | Instruction::Input { .. } => 10,
+
+        Instruction::Unknown { .. } => 0,
+    };
+    FETCH_CYCLES + execute
+}
@@ -0,0 +1,191 @@
+//! Where the COSMAC VIP's interpreter kept its state in RAM, for the VIP memory
+//! map and for the machine code subroutines that 0NNN calls.
+
+use core::ops::Range;
+
+use crate::{
+    cdp1802::Cdp1802, Error, Instruction, Machine, DISPLAY_HEIGHT, DISPLAY_WIDTH, MEMORY_SIZE,
+    VIP_CYCLES_PER_FRAME,
+};
+
+/// The start of the interpreter's work area, where the call stack grows down to.
+pub const VIP_STACK: u16 = 0xEA0;
+/// V0 to VF.
+pub const VIP_REGISTERS: u16 = 0xEF0;
+/// The display, 8 bytes to a row with the leftmost pixel in the top bit.
+pub const VIP_DISPLAY: u16 = 0xF00;
+/// The deepest the call stack can go with the VIP memory map.
+pub const VIP_STACK_DEPTH: usize = 12;
+
+/// The byte that the stack pointer (R2) starts at. Each return address goes
+/// below the last one, high byte first.
+const VIP_STACK_TOP: u16 = 0xECF;
+
+/// The longest a machine code subroutine can run before it's given up on, a
+/// second of VIP time.
+const MAX_MACHINE_CODE_CYCLES: u32 = VIP_CYCLES_PER_FRAME * 60;
+
+/// The 1802 register the interpreter's fetch loop runs from, so that `SEP R4`
+/// (D4) returns from machine code.
+const INTERPRETER_P: u8 = 4;
+
+/// The first address of the interpreter's work area in `memory_size` bytes of
+/// memory, which a ROM can't be loaded over.
+pub(crate) fn work_area_start(memory_size: usize) -> usize {
+    memory_size.saturating_sub(MEMORY_SIZE - VIP_STACK as usize)
+}
+
+impl Machine {
+    /// Moves an address in the VIP's 4 KB layout to the same distance from the end
+    /// of memory, where the interpreter puts its work area on smaller machines.
+    fn vip_address(&self, address: u16) -> usize {
+        self.wrap_address(address as isize + self.memory_size as isize - MEMORY_SIZE as isize)
+    }
+
+    /// Wraps an address that may have gone below zero or past the end of memory.
+    fn wrap_address(&self, address: isize) -> usize {
+        address.rem_euclid(self.memory_size as isize) as usize
+    }
+
+    /// The address of the byte that stack entry `i` ends at.
+    fn stack_entry(&self, i: usize) -> isize {
+        self.vip_address(VIP_STACK_TOP) as isize - 2 * i as isize
+    }
+
+    /// Brings RAM and the interpreter's state back in line after `instruction`, for
+    /// the VIP memory map. If the instruction wrote to the work area (`written`),
+    /// the registers, display and stack are read back from it, so that FX55 and
+    /// FX33 can change them; otherwise the work area is updated from them.
+    pub(crate) fn sync_vip_memory(
+        &mut self,
+        instruction: Instruction,
+        written: Option<Range<usize>>,
+    ) {
+        let work_area = self.vip_address(VIP_STACK);
+        let wrote_work_area = written
+            .is_some_and(|mut range| range.any(|address| address % self.memory_size >= work_area));
+        if wrote_work_area {
+            self.load_vip_state();
+            return;
+        }
+
+        self.store_registers();
+        self.store_stack();
+        if matches!(
+            instruction,
+            Instruction::ClearScreen | Instruction::Draw { .. }
+        ) {
+            self.store_display();
+        }
+    }
+
+    fn store_registers(&mut self) {
+        let registers = self.vip_address(VIP_REGISTERS);
+        for (i, value) in self.registers.into_iter().enumerate() {
+            self.write(registers + i, value);
+        }
+    }
+
+    fn store_stack(&mut self) {
+        for i in 0..self.stack.len() {
+            let [high, low] = self.stack[i].to_be_bytes();
+            let address = self.stack_entry(i);
+            self.write(self.wrap_address(address - 1), high);
+            self.write(self.wrap_address(address), low);
+        }
+    }
+
+    fn store_display(&mut self) {
+        let display = self.vip_address(VIP_DISPLAY);
+        for y in 0..DISPLAY_HEIGHT {
+            let row = self.display.row(y).to_be_bytes();
+            for (i, byte) in row.into_iter().enumerate() {
+                self.write(display + y * 8 + i, byte);
+            }
+        }
+    }
+
+    /// Reads the registers, the return addresses on the stack and the display
+    /// back from the work area.
+    fn load_vip_state(&mut self) {
+        let registers = self.vip_address(VIP_REGISTERS);
+        for i in 0..16 {
+            self.registers[i] = self.read(registers + i);
+        }
+
+        for i in 0..self.stack.len() {
+            let address = self.stack_entry(i);
+            let high = self.read(self.wrap_address(address - 1));
+            let low = self.read(self.wrap_address(address));
+            self.stack[i] = u16::from_be_bytes([high, low]) % self.memory_size as u16;
+        }
+
+        let display = self.vip_address(VIP_DISPLAY);
+        for y in 0..DISPLAY_HEIGHT {
+            let row = core::array::from_fn(|i| self.read(display + y * 8 + i));
+            let row = u64::from_be_bytes(row);
+            if row != self.display.row(y) {
+                self.display.set_row(y, row);
+                self.damage.add(0..DISPLAY_WIDTH, y..y + 1);
+            }
+        }
+    }
+
+    /// Runs the machine code subroutine at `routine` for 0NNN, returning the
+    /// machine cycles it took.
+    pub(crate) fn run_machine_code(&mut self, routine: u16) -> Result<u32, Error> {
+        let Some(mut cpu) = self.cdp1802.take() else {
+            return Err(Error::MachineCode {
+                routine,
+                address: self.instruction_address(),
+            });
+        };
+        let result = self.call_machine_code(&mut cpu, routine);
+        self.cdp1802 = Some(cpu);
+        result
+    }
+
+    /// Runs the machine code subroutine at `routine` on the 1802 until it returns
+    /// to the interpreter with `SEP R4`.
+    ///
+    /// The interpreter's registers are set up like on the VIP: R5 is the PC, R6 and
+    /// R7 point at VX and VY (from the second and third nibbles of the opcode), RA
+    /// is I, R8 holds the delay and sound timers, and R2 is the stack pointer.
+    /// The V registers, the display and the stack are copied into RAM for the
+    /// call, and copied back afterwards along with whatever the subroutine changed.
+    fn call_machine_code(&mut self, cpu: &mut Cdp1802, routine: u16) -> Result<u32, Error> {
+        self.store_registers();
+        self.store_stack();
+        self.store_display();
+
+        let registers = self.vip_address(VIP_REGISTERS);
+        cpu.r[2] = self.wrap_address(self.stack_entry(self.stack.len())) as u16;
+        cpu.r[3] = routine;
+        cpu.r[5] = self.pc;
+        cpu.r[6] = (registers + (routine as usize >> 8 & 0xF)) as u16;
+        cpu.r[7] = (registers + (routine as usize >> 4 & 0xF)) as u16;
+        cpu.r[8] = u16::from_be_bytes([self.dt, self.st]);
+        cpu.r[0xA] = self.index;
+        cpu.r[0xB] = (self.vip_address(VIP_DISPLAY) as u16) & 0xFF00;
+        cpu.p = 3;
+        cpu.x = 2;
+
+        let memory = &mut self.memory[..self.memory_size];
+        let mut cycles = 0;
+        while cpu.p != INTERPRETER_P {
+            if cycles > MAX_MACHINE_CODE_CYCLES {
+                return Err(Error::MachineCodeTimeout { routine });
+            }
+            cycles += cpu.step(memory);
+        }
+
+        self.load_vip_state();
+        self.pc = (cpu.r[5] as usize % self.memory_size) as u16;
+        [self.dt, self.st] = cpu.r[8].to_be_bytes();
+        self.index = cpu.r[0xA];
+        // The subroutine could have written anywhere, including over cached code
+        self.invalidate_block_cache();
+
+        Ok(cycles)
+    }
+}
@@ -0,0 +1,124 @@
+use machine::{Drivers, Error, Instruction, Machine, Quirks};
+
+const ROMS: &[&[u8]] = &[
+    include_bytes!("../../../roms/ibm-logo.ch8"),
+    include_bytes!("../../../roms/coraxplus.ch8"),
+    include_bytes!("../../../roms/flags.ch8"),
+    include_bytes!("../../../roms/splash.ch8"),
+];
+
+fn machine(rom: &[u8], cached: bool) -> Machine {
+    let mut machine = Machine::from_rom(rom, Quirks::modern_chip8(), Drivers::noop()).unwrap();
+    machine.rng.seed(1);
+    machine.set_block_cache(cached);
+    machine
+}
+
+fn assert_same_state(cached: &Machine, interpreted: &Machine) {
+    assert_eq!(cached.memory, interpreted.memory);
+    assert_eq!(cached.display, interpreted.display);
+    assert_eq!(cached.pc, interpreted.pc);
+    assert_eq!(cached.index, interpreted.index);
+    assert_eq!(cached.stack, interpreted.stack);
+    assert_eq!(cached.registers, interpreted.registers);
+    assert_eq!((cached.dt, cached.st), (interpreted.dt, interpreted.st));
+}
+
+#[test]
+fn matches_interpreter() {
+    for rom in ROMS {
+        let mut cached = machine(rom, true);
+        let mut interpreted = machine(rom, false);
+
+        // An odd number of cycles, so that frames end partway through blocks
+        for _ in 0..100 {
+            cached.run_frame(7).unwrap();
+            interpreted.run_frame(7).unwrap();
+            assert_same_state(&cached, &interpreted);
+        }
+    }
+}
+
+#[test]
+fn invalidates_on_fx55() {
+    let mut machine = machine(
+        &[
+            0xA2, 0x08, // I = 0x208
+            0x60, 0x6B, // V0 = 0x6B
+            0x61, 0x07, // V1 = 0x07
+            0xF1, 0x55, // Store V0 and V1 over the next instruction
+            0x6B, 0x01, // VB = 1, patched to VB = 7
+            0x12, 0x0A, // Loop forever
+        ],
+        true,
+    );
+
+    machine.run_frame(6).unwrap();
+    assert_eq!(machine.registers[0xB], 7);
+}
+
+#[test]
+fn invalidates_on_fx33() {
+    let mut machine = machine(
+        &[
+            0xA2, 0x07, // I = 0x207
+            0x60, 0x78, // V0 = 120
+            0xF0, 0x33, // Store the digits 1, 2, 0 over the next two instructions
+            0x6B, 0xFF, // VB = 0xFF, patched to VB = 1
+            0x7C, 0x05, // VC += 5, patched to 0200 (machine code at 0x200)
+            0x12, 0x0A, // Loop forever
+        ],
+        true,
+    );
+
+    assert_eq!(
+        machine.run_frame(6),
+        Err(Error::MachineCode {
+            routine: 0x200,
+            address: 0x208
+        })
+    );
+    assert_eq!(machine.registers[0xB], 1);
+    assert_eq!(machine.registers[0xC], 0);
+}
+
+#[test]
+fn invalidates_on_loading_state() {
+    let mut machine = machine(&[0x6B, 0x01, 0x12, 0x00], true);
+    let mut other = self::machine(&[0x6B, 0x02, 0x12, 0x00], false);
+    machine.run_frame(2).unwrap();
+    other.run_frame(2).unwrap();
+
+    machine.load_state(&other.save_state()).unwrap();
+    machine.registers[0xB] = 0;
+    machine.run_frame(2).unwrap();
+    assert_eq!(machine.registers[0xB], 2);
+}
+
+#[test]
+fn decodes_instructions() {
+    assert_eq!(Instruction::decode(0x00E0), Instruction::ClearScreen);
+    assert_eq!(
+        Instruction::decode(0xD12F),
+        Instruction::Draw {
+            x: 1,
+            y: 2,
+            height: 0xF
+        }
+    );
+    assert_eq!(
+        Instruction::decode(0xB3A0),
+        Instruction::JumpWithOffset {
+            address: 0x3A0,
+            x: 3
+        }
+    );
+    assert_eq!(
+        Instruction::decode(0x5FFF),
+        Instruction::SkipIfRegistersEqual { x: 0xF, y: 0xF }
+    );
+    assert_eq!(
+        Instruction::decode(0xF0FF),
+        Instruction::Unknown { opcode: 0xF0FF }
+    );
+}
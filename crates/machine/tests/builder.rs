@@ -0,0 +1,136 @@
+use machine::{Drivers, LoadError, Machine, Quirks, FONT, FONT_START, MEMORY_SIZE, PROGRAM_START};
+
+#[test]
+fn defaults_match_from_rom() {
+    let rom = include_bytes!("../../../roms/ibm-logo.ch8");
+    let built = Machine::builder(rom).build().unwrap();
+    let loaded = Machine::from_rom(rom, Quirks::modern_chip8(), Drivers::noop()).unwrap();
+
+    assert_eq!(built.memory, loaded.memory);
+    assert_eq!(built.pc, PROGRAM_START);
+    assert_eq!(built.quirks, loaded.quirks);
+    assert_eq!(built.memory_size(), MEMORY_SIZE);
+    assert_eq!(built.font_address(), FONT_START);
+}
+
+#[test]
+fn loads_at_custom_addresses() {
+    let font = core::array::from_fn(|i| i as u8);
+    let mut machine = Machine::builder(&[
+        0x60, 0x0A, // V0 = 0xA
+        0xF0, 0x29, // I = the font character for V0
+    ])
+    .load_address(0x600)
+    .font_address(0x150)
+    .font(font)
+    .build()
+    .unwrap();
+
+    assert_eq!(machine.pc, 0x600);
+    assert_eq!(&machine.memory[0x600..0x604], &[0x60, 0x0A, 0xF0, 0x29]);
+    assert_eq!(&machine.memory[0x150..0x1A0], &font);
+    assert!(machine.memory[FONT_START as usize..][..FONT.len()]
+        .iter()
+        .all(|byte| *byte == 0));
+
+    machine.cycle().unwrap();
+    machine.cycle().unwrap();
+    assert_eq!(machine.index, 0x150 + 0xA * 5);
+}
+
+#[test]
+fn wraps_at_memory_size() {
+    let mut machine = Machine::builder(&[
+        0xA7, 0xFF, // I = 0x7FF
+        0x60, 0x01, // V0 = 1
+        0x61, 0x02, // V1 = 2
+        0xF1, 0x55, // Store V0 and V1
+        0x17, 0xFE, // Jump to 0x7FE
+    ])
+    .memory_size(0x800)
+    .build()
+    .unwrap();
+    machine.memory[0x7FE..0x800].copy_from_slice(&[0x6B, 0x01]); // VB = 1
+
+    for _ in 0..6 {
+        machine.cycle().unwrap();
+    }
+    assert_eq!(machine.memory[0x7FF], 1);
+    assert_eq!(machine.memory[0], 2);
+    assert_eq!(machine.memory[0x800], 0);
+    assert_eq!(machine.registers[0xB], 1);
+    assert_eq!(machine.pc, 0);
+}
+
+#[test]
+fn seeds_the_rng() {
+    let rom = [0xC0, 0xFF, 0xC1, 0xFF]; // Two random bytes
+    let mut first = Machine::builder(&rom).seed(42).build().unwrap();
+    let mut second = Machine::builder(&rom).seed(42).build().unwrap();
+    first.run_frame(2).unwrap();
+    second.run_frame(2).unwrap();
+    assert_eq!(first.registers, second.registers);
+}
+
+#[test]
+fn rejects_layouts_that_dont_fit() {
+    assert!(Machine::builder(&[0; 0xA00])
+        .load_address(0x600)
+        .build()
+        .is_ok());
+    assert_eq!(
+        Machine::builder(&[0; 0xA01])
+            .load_address(0x600)
+            .build()
+            .err(),
+        Some(LoadError::RomTooLarge {
+            size: 0xA01,
+            max: 0xA00
+        })
+    );
+    assert_eq!(
+        Machine::builder(&[]).memory_size(0x2000).build().err(),
+        Some(LoadError::InvalidMemorySize {
+            size: 0x2000,
+            max: MEMORY_SIZE
+        })
+    );
+    assert_eq!(
+        Machine::builder(&[])
+            .memory_size(0x800)
+            .load_address(0x800)
+            .build()
+            .err(),
+        Some(LoadError::LoadAddressOutOfRange {
+            address: 0x800,
+            memory_size: 0x800
+        })
+    );
+    assert_eq!(
+        Machine::builder(&[]).font_address(0xFC0).build().err(),
+        Some(LoadError::FontOutOfRange {
+            address: 0xFC0,
+            memory_size: MEMORY_SIZE
+        })
+    );
+    assert_eq!(
+        Machine::builder(&[0; 4]).font_address(0x1F0).build().err(),
+        Some(LoadError::FontOverlapsRom {
+            font_address: 0x1F0,
+            load_address: 0x200
+        })
+    );
+}
+
+#[test]
+fn describes_errors() {
+    let err = Machine::builder(&[0; 0x100])
+        .load_address(0xF80)
+        .build()
+        .err()
+        .unwrap();
+    assert_eq!(
+        err.to_string(),
+        "ROM is 256 bytes, but at most 128 bytes fit in memory"
+    );
+}
@@ -0,0 +1,158 @@
+use machine::{Error, Instruction, LoadError, Machine, VIP_DISPLAY, VIP_REGISTERS, VIP_STACK};
+
+/// Loads `rom` with the machine code subroutine `routine` at 0x210.
+fn machine(rom: &[u8], routine: &[u8]) -> Machine {
+    let mut program = rom.to_vec();
+    program.resize(0x10, 0);
+    program.extend_from_slice(routine);
+    Machine::builder(&program).cdp1802(true).build().unwrap()
+}
+
+#[test]
+fn decodes_machine_calls() {
+    assert_eq!(Instruction::decode(0x00E0), Instruction::ClearScreen);
+    assert_eq!(Instruction::decode(0x00EE), Instruction::Return);
+    assert_eq!(
+        Instruction::decode(0x0123),
+        Instruction::MachineCall { address: 0x123 }
+    );
+    assert_eq!(
+        Instruction::decode(0x0000),
+        Instruction::MachineCall { address: 0 }
+    );
+}
+
+#[test]
+fn changes_registers_in_memory() {
+    let mut machine = machine(
+        &[
+            0x62, 0x15, // 200: V2 = 21
+            0x02, 0x10, // 202: Run the machine code at 0x210, with R6 pointing at V2
+        ],
+        &[
+            0x06, // LDN R6: D = V2
+            0xE6, // SEX R6
+            0xF4, // ADD: D += V2
+            0x56, // STR R6: V2 = D
+            0xD4, // SEP R4: Back to the interpreter
+        ],
+    );
+    machine.run_frame(2).unwrap();
+
+    assert_eq!(machine.registers[2], 42);
+    assert_eq!(machine.memory[VIP_REGISTERS as usize + 2], 42);
+    assert_eq!(machine.pc, 0x204);
+}
+
+#[test]
+fn draws_into_display_memory() {
+    let mut machine = machine(
+        &[0x02, 0x10],
+        &[
+            0xF8, 0xFF, // LDI 0xFF
+            0x5B, // STR RB: The first byte of the display
+            0xD4,
+        ],
+    );
+    machine.run_frame(1).unwrap();
+
+    assert_eq!(machine.memory[VIP_DISPLAY as usize], 0xFF);
+    assert_eq!(machine.display.row(0), 0xFF << 56);
+    assert!(machine.is_dirty());
+}
+
+#[test]
+fn sets_the_index_and_timers() {
+    let mut machine = machine(
+        &[0x02, 0x10],
+        &[
+            0xF8, 0x03, 0xBA, // RA.1 = 0x03
+            0xF8, 0x45, 0xAA, // RA.0 = 0x45, so I = 0x345
+            0xF8, 0x3C, 0xB8, // R8.1 = 60, the delay timer
+            0x88, // GLO R8: D = the sound timer
+            0xFC, 0x01, // ADI 1
+            0xA8, // PLO R8
+            0xD4,
+        ],
+    );
+    machine.st = 9;
+    machine.cycle().unwrap();
+
+    assert_eq!(machine.index, 0x345);
+    assert_eq!(machine.dt, 60);
+    assert_eq!(machine.st, 10);
+}
+
+#[test]
+fn subtracts_with_borrow() {
+    let mut machine = machine(
+        &[
+            0x62, 0x05, // 200: V2 = 5
+            0x02, 0x10, // 202: Machine code with R6 pointing at V2 and R7 at V1
+        ],
+        &[
+            0x06, // 210: LDN R6: D = V2
+            0xFF, 0x07, // 211: SMI 7: D = 5 - 7, which borrows
+            0x56, // 213: STR R6: V2 = D
+            0x33, 0x19, // 214: BDF 0x219: Skip setting V1 if there was no borrow
+            0xF8, 0x01, // 216: LDI 1
+            0x57, // 218: STR R7: V1 = 1
+            0xD4, // 219
+        ],
+    );
+    machine.run_frame(2).unwrap();
+
+    assert_eq!(machine.registers[2], 0xFE);
+    assert_eq!(machine.registers[1], 1);
+}
+
+#[test]
+fn fails_without_the_core() {
+    let mut machine = Machine::builder(&[0x00, 0x00, 0x02, 0x10]).build().unwrap();
+    machine.cycle().unwrap_err();
+    assert_eq!(
+        machine.cycle(),
+        Err(Error::MachineCode {
+            routine: 0x210,
+            address: 0x202
+        })
+    );
+}
+
+#[test]
+fn gives_up_on_routines_that_never_return() {
+    let mut machine = machine(&[0x02, 0x10], &[0x30, 0x10]);
+    assert_eq!(
+        machine.cycle(),
+        Err(Error::MachineCodeTimeout { routine: 0x210 })
+    );
+}
+
+#[test]
+fn keeps_roms_out_of_the_work_area() {
+    // 0NNN to a routine that returns straight away, and data at 0xF00
+    let mut rom = vec![0x02, 0x04, 0x12, 0x02, 0xD4];
+    rom.resize(0xF00 - 0x200, 0);
+    rom.extend_from_slice(&[0xAB; 16]);
+
+    assert_eq!(
+        Machine::builder(&rom).cdp1802(true).build().err(),
+        Some(LoadError::RomTooLarge {
+            size: rom.len(),
+            max: VIP_STACK as usize - 0x200
+        })
+    );
+
+    // Without the core, the whole of memory is the ROM's
+    let mut machine = Machine::builder(&rom).build().unwrap();
+    assert_eq!(machine.memory[0xF00..0xF10], [0xAB; 16]);
+    machine.cycle().unwrap_err();
+
+    // A ROM that stops short of the work area keeps its data through a call
+    rom.truncate(VIP_STACK as usize - 0x200);
+    *rom.last_mut().unwrap() = 0xCD;
+    let mut machine = Machine::builder(&rom).cdp1802(true).build().unwrap();
+    machine.run_frame(1).unwrap();
+    assert_eq!(machine.pc, 0x202);
+    assert_eq!(machine.memory[VIP_STACK as usize - 1], 0xCD);
+}
@@ -0,0 +1,135 @@
+use machine::{
+    Instruction, Machine, Platform, BACKGROUND_COLORS, CHIP8X_PROGRAM_START, FOREGROUND_COLORS,
+};
+
+fn machine(rom: &[u8]) -> Machine {
+    Machine::builder(rom)
+        .platform(Platform::Chip8X)
+        .build()
+        .unwrap()
+}
+
+#[test]
+fn loads_at_0x300() {
+    let machine = machine(&[]);
+    assert_eq!(machine.pc, CHIP8X_PROGRAM_START);
+    assert_eq!(machine.platform(), Platform::Chip8X);
+}
+
+#[test]
+fn decodes_the_color_instructions() {
+    assert_eq!(
+        Instruction::decode_chip8x(0xB120),
+        Instruction::SetColorZones { x: 1, y: 2 }
+    );
+    assert_eq!(
+        Instruction::decode_chip8x(0xB123),
+        Instruction::SetColorRows {
+            x: 1,
+            y: 2,
+            height: 3
+        }
+    );
+    assert_eq!(
+        Instruction::decode(0xB120),
+        Instruction::JumpWithOffset {
+            address: 0x120,
+            x: 1
+        }
+    );
+    assert_eq!(Instruction::decode_chip8x(0x00E0), Instruction::ClearScreen);
+}
+
+#[test]
+fn cycles_the_background() {
+    let mut machine = machine(&[0x02, 0xA0, 0x02, 0xA0]);
+    machine.take_frame();
+    machine.run_frame(2).unwrap();
+
+    assert_eq!(machine.colors.background, 2);
+    assert!(machine.is_dirty());
+    assert_eq!(
+        machine.colors.palette_at(0, 0).background,
+        BACKGROUND_COLORS[2]
+    );
+}
+
+#[test]
+fn colors_zones() {
+    let mut machine = machine(&[
+        0x60, 0x11, // V0 = 0x11: From the second zone across, and one more
+        0x61, 0x02, // V1 = 0x02: Just the third zone down
+        0x62, 0x04, // V2 = green
+        0xB0, 0x20, // Color the zones
+    ]);
+    machine.run_frame(4).unwrap();
+
+    let foreground = &machine.colors.foreground;
+    for row in &foreground[8..12] {
+        assert_eq!(row[1..4], [4, 4, 1]);
+    }
+    assert_eq!(foreground[7][1], 1);
+    assert_eq!(foreground[12][1], 1);
+    assert_eq!(
+        machine.colors.palette_at(8, 8).foreground,
+        FOREGROUND_COLORS[4]
+    );
+}
+
+#[test]
+fn colors_rows() {
+    let mut machine = machine(&[
+        0x60, 0x14, // V0 = 20, in the third column
+        0x61, 0x05, // V1 = 5
+        0x62, 0x07, // V2 = white
+        0xB0, 0x23, // Color 3 rows
+    ]);
+    machine.run_frame(4).unwrap();
+
+    let column: Vec<_> = machine.colors.foreground.iter().map(|row| row[2]).collect();
+    assert_eq!(column[4..9], [1, 7, 7, 7, 1]);
+}
+
+#[test]
+fn checks_the_second_keypad() {
+    let mut machine = machine(&[
+        0x60, 0x03, // 300: V0 = 3
+        0xE0, 0xF2, // 302: Skip if key 3 is pressed on the second keypad
+        0x61, 0x01, // 304: V1 = 1
+        0xE0, 0xF5, // 306: Skip if it isn't
+        0x62, 0x01, // 308: V2 = 1
+    ]);
+    machine.keypad = 1 << 3;
+    machine.keypad2 = 1 << 3;
+    machine.run_frame(4).unwrap();
+
+    assert_eq!(machine.registers[1], 0);
+    assert_eq!(machine.registers[2], 1);
+}
+
+#[test]
+fn uses_the_io_port() {
+    let mut machine = machine(&[
+        0x60, 0x42, // V0 = 0x42
+        0xF0, 0xF8, // Output V0
+        0xF1, 0xFB, // Wait for input into V1
+    ]);
+    machine.run_frame(4).unwrap();
+    assert_eq!(machine.output_port, 0x42);
+    assert_eq!(machine.pc, 0x304);
+
+    machine.input_port = Some(0x99);
+    machine.cycle().unwrap();
+    assert_eq!(machine.registers[1], 0x99);
+    assert_eq!(machine.input_port, None);
+}
+
+#[test]
+fn saves_the_colors() {
+    let mut machine = machine(&[0x02, 0xA0, 0x62, 0x05, 0xB0, 0x21]);
+    machine.run_frame(3).unwrap();
+
+    let mut other = self::machine(&[]);
+    other.load_state(&machine.save_state()).unwrap();
+    assert_eq!(other.colors, machine.colors);
+}
@@ -0,0 +1,65 @@
+use machine::{Damage, DamageRect, Drivers, Machine, Quirks};
+
+#[test]
+fn take_frame_resets_damage() {
+    // 00E0, then draw the "0" glyph at (4, 2): 6000 6104 6202 F029 D125
+    let rom = [
+        0x00, 0xE0, 0x60, 0x00, 0x61, 0x04, 0x62, 0x02, 0xF0, 0x29, 0xD1, 0x25,
+    ];
+    let mut machine = Machine::from_rom(&rom, Quirks::modern_chip8(), Drivers::noop()).unwrap();
+
+    // The first frame covers the whole display
+    assert_eq!(machine.take_frame().damage, Damage::full(64, 32));
+    assert!(!machine.is_dirty());
+
+    machine.cycle().unwrap();
+    assert_eq!(machine.take_frame().damage, Damage::full(64, 32));
+
+    for _ in 0..4 {
+        machine.cycle().unwrap();
+    }
+    assert!(!machine.is_dirty());
+
+    machine.cycle().unwrap();
+    let frame = machine.take_frame();
+    assert!(frame.display.get(4, 2));
+    assert_eq!(frame.damage.rows().collect::<Vec<_>>(), [2, 3, 4, 5, 6]);
+    assert_eq!(frame.damage.columns(), Some(4..12));
+    assert!(machine.take_frame().damage.is_empty());
+}
+
+#[test]
+fn rects() {
+    let mut damage = Damage::default();
+    assert_eq!(damage.bounding_rect(), None);
+    assert_eq!(damage.rects().count(), 0);
+
+    damage.add(4..12, 2..4);
+    damage.add(60..64, 10..11);
+    assert_eq!(
+        damage.rects().collect::<Vec<_>>(),
+        [
+            DamageRect {
+                x: 4,
+                y: 2,
+                width: 60,
+                height: 2
+            },
+            DamageRect {
+                x: 4,
+                y: 10,
+                width: 60,
+                height: 1
+            },
+        ]
+    );
+    assert_eq!(
+        damage.bounding_rect(),
+        Some(DamageRect {
+            x: 4,
+            y: 2,
+            width: 60,
+            height: 9
+        })
+    );
+}
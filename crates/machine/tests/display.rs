@@ -0,0 +1,50 @@
+use machine::{Display, HiresDisplay, Palette};
+
+#[test]
+fn draw_sprite_xors_and_reports_collisions() {
+    let mut display = Display::new();
+
+    assert!(!display.draw_sprite(0, 0, &[0b1100_0000]));
+    assert!(display.get(0, 0));
+    assert!(display.get(1, 0));
+    assert!(!display.get(2, 0));
+    assert_eq!(display.row(0), 0xC000_0000_0000_0000);
+
+    assert!(display.draw_sprite(1, 0, &[0b1000_0000]));
+    assert!(display.get(0, 0));
+    assert!(!display.get(1, 0));
+    assert_eq!(display.lit_pixels().collect::<Vec<_>>(), [(0, 0)]);
+}
+
+#[test]
+fn draw_sprite_clips_at_edges() {
+    let mut display = Display::new();
+
+    display.draw_sprite(60, 30, &[0xFF, 0xFF, 0xFF]);
+    assert_eq!(display.lit_pixels().count(), 4 * 2);
+    assert!(display.get(63, 31));
+    assert!(!display.get(0, 30));
+    assert!(!display.get(60, 0));
+}
+
+#[test]
+fn hires_display() {
+    let mut display = HiresDisplay::new();
+    assert_eq!((display.width(), display.height()), (128, 64));
+
+    display.draw_sprite(124, 63, &[0xFF, 0xFF]);
+    assert_eq!(
+        display.lit_pixels().collect::<Vec<_>>(),
+        [(124, 63), (125, 63), (126, 63), (127, 63)]
+    );
+}
+
+#[test]
+fn to_rgba() {
+    let mut display = Display::new();
+    display.set(1, 0, true);
+
+    let rgba = display.to_rgba(&Palette::CLASSIC);
+    assert_eq!(rgba.len(), 64 * 32 * 4);
+    assert_eq!(rgba[..8], [0, 0, 0, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF]);
+}
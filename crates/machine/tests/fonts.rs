@@ -0,0 +1,78 @@
+use machine::{
+    fonts::{self, LARGE_FONT_CHAR_SIZE, LARGE_FONT_SIZE, SMALL_FONT_SIZE},
+    Machine, FONT, FONT_CHAR_SIZE, LARGE_FONT_START,
+};
+
+const SMALL_FONTS: &[[u8; SMALL_FONT_SIZE]] = &[
+    fonts::COSMAC_VIP,
+    fonts::DREAM_6800,
+    fonts::ETI_660,
+    fonts::FISH_N_CHIPS,
+    fonts::SCHIP,
+];
+
+#[test]
+fn small_fonts_are_4_pixels_wide() {
+    for font in SMALL_FONTS {
+        assert!(font.iter().all(|row| row & 0x0F == 0));
+        // Every character is different
+        let chars: Vec<_> = font.chunks(FONT_CHAR_SIZE).collect();
+        for (i, char) in chars.iter().enumerate() {
+            assert!(!chars[..i].contains(char));
+        }
+    }
+}
+
+#[test]
+fn loads_the_large_font() {
+    let mut machine = Machine::builder(&[
+        0x60, 0x07, // V0 = 7
+        0xF0, 0x30, // I = the large font character for V0
+        0x61, 0x00, // V1 = 0
+        0xD1, 0x1A, // Draw it at (0, 0)
+    ])
+    .large_font(fonts::SCHIP_LARGE)
+    .build()
+    .unwrap();
+    for _ in 0..4 {
+        machine.cycle().unwrap();
+    }
+
+    assert_eq!(
+        machine.index,
+        LARGE_FONT_START + 7 * LARGE_FONT_CHAR_SIZE as u16
+    );
+    let seven = &fonts::SCHIP_LARGE[7 * LARGE_FONT_CHAR_SIZE..][..LARGE_FONT_CHAR_SIZE];
+    for (y, row) in seven.iter().enumerate() {
+        assert_eq!(machine.display.row(y), (*row as u64) << 56);
+    }
+}
+
+#[test]
+fn defaults_to_the_schip_and_octo_fonts() {
+    let machine = Machine::builder(&[]).build().unwrap();
+    assert_eq!(FONT, fonts::SCHIP);
+    assert_eq!(
+        &machine.memory[LARGE_FONT_START as usize..][..LARGE_FONT_SIZE],
+        &fonts::OCTO_LARGE
+    );
+}
+
+#[test]
+fn uses_the_vip_glyphs() {
+    let mut machine = Machine::builder(&[
+        0x60, 0x07, // V0 = 7
+        0xF0, 0x29, // I = the font character for V0
+    ])
+    .font(fonts::COSMAC_VIP)
+    .build()
+    .unwrap();
+    machine.run_frame(2).unwrap();
+
+    // The VIP's 7 is a straight line down, unlike the slanted modern one
+    let index = machine.index as usize;
+    assert_eq!(
+        &machine.memory[index..index + FONT_CHAR_SIZE],
+        &[0xF0, 0x10, 0x10, 0x10, 0x10]
+    );
+}
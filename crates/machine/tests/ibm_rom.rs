@@ -5,10 +5,10 @@ const CYCLE_NUM: usize = 20;
 #[test]
 fn ibm_rom() {
     let rom = include_bytes!("../../../roms/ibm-logo.ch8");
-    let mut machine = Machine::from_rom(rom, Quirks::modern_chip8(), Drivers::noop());
+    let mut machine = Machine::from_rom(rom, Quirks::modern_chip8(), Drivers::noop()).unwrap();
 
     for _ in 0..CYCLE_NUM + 1 {
-        machine.cycle();
+        machine.cycle().unwrap();
     }
 
     assert_eq!(
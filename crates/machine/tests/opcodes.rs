@@ -0,0 +1,337 @@
+//! Checks each instruction against a separate description of what it should do,
+//! from random machine states and with every combination of quirks.
+
+use machine::{
+    Display, Drivers, Machine, Quirks, DISPLAY_HEIGHT, DISPLAY_WIDTH, FONT_CHAR_SIZE, FONT_START,
+    PROGRAM_START,
+};
+use proptest::prelude::*;
+
+/// What the machine holds before running an instruction.
+#[derive(Clone, Debug)]
+struct State {
+    registers: [u8; 16],
+    index: u16,
+    /// The 16 bytes of memory starting at `index`.
+    data: [u8; 16],
+    keypad: u16,
+    quirks: Quirks,
+}
+
+fn quirks() -> impl Strategy<Value = Quirks> {
+    any::<[bool; 4]>().prop_map(
+        |[set_vx_to_vy, fx_incr_index, set_vf_on_fx1e_overflow, bxnn]| Quirks {
+            set_vx_to_vy,
+            fx_incr_index,
+            set_vf_on_fx1e_overflow,
+            bxnn,
+        },
+    )
+}
+
+/// States with I pointing somewhere after the program, with room to spare.
+fn state() -> impl Strategy<Value = State> {
+    (
+        any::<[u8; 16]>(),
+        0x300u16..0xF00,
+        any::<[u8; 16]>(),
+        any::<u16>(),
+        quirks(),
+    )
+        .prop_map(|(registers, index, data, keypad, quirks)| State {
+            registers,
+            index,
+            data,
+            keypad,
+            quirks,
+        })
+}
+
+/// Loads `state` into a machine whose program is just `opcode`.
+fn machine(opcode: u16, state: &State) -> Machine {
+    let mut machine =
+        Machine::from_rom(&opcode.to_be_bytes(), state.quirks, Drivers::noop()).unwrap();
+    machine.registers = state.registers;
+    machine.index = state.index;
+    machine.memory[state.index as usize..][..16].copy_from_slice(&state.data);
+    machine.keypad = state.keypad;
+    machine
+}
+
+/// Runs `opcode` from `state`, returning the machine afterwards.
+fn run(opcode: u16, state: &State) -> Machine {
+    let mut machine = machine(opcode, state);
+    machine.cycle().unwrap();
+    machine
+}
+
+/// The new value of VX and VF for `8XYN`.
+fn alu(n: u16, vx: u8, vy: u8, quirks: Quirks) -> (u8, Option<u8>) {
+    // The shifts work on VY instead of VX on the original interpreter
+    let shifted = if quirks.set_vx_to_vy { vy } else { vx };
+    match n {
+        0x0 => (vy, None),
+        0x1 => (vx | vy, None),
+        0x2 => (vx & vy, None),
+        0x3 => (vx ^ vy, None),
+        0x4 => {
+            let sum = vx as u16 + vy as u16;
+            (sum as u8, Some((sum > 0xFF) as u8))
+        }
+        // VF is 1 when there's no borrow
+        0x5 => (vx.wrapping_sub(vy), Some((vx >= vy) as u8)),
+        0x7 => (vy.wrapping_sub(vx), Some((vy >= vx) as u8)),
+        // VF is the bit shifted out
+        0x6 => (shifted >> 1, Some(shifted & 1)),
+        0xE => (shifted << 1, Some(shifted >> 7)),
+        _ => unreachable!(),
+    }
+}
+
+proptest! {
+    #[test]
+    fn arithmetic(
+        state in state(),
+        x in 0..16u16,
+        y in 0..16u16,
+        n in prop::sample::select(vec![0x0, 0x1, 0x2, 0x3, 0x4, 0x5, 0x6, 0x7, 0xE]),
+    ) {
+        let machine = run(0x8000 | x << 8 | y << 4 | n, &state);
+
+        let (vx, vy) = (state.registers[x as usize], state.registers[y as usize]);
+        let (result, flag) = alu(n, vx, vy, state.quirks);
+        let mut expected = state.registers;
+        expected[x as usize] = result;
+        // The flag wins if VF is also the destination
+        if let Some(flag) = flag {
+            expected[0xF] = flag;
+        }
+        prop_assert_eq!(machine.registers, expected);
+        prop_assert_eq!(machine.pc, PROGRAM_START + 2);
+    }
+
+    #[test]
+    fn set_and_add(state in state(), x in 0..16u16, nn in any::<u8>()) {
+        let machine = run(0x6000 | x << 8 | nn as u16, &state);
+        let mut expected = state.registers;
+        expected[x as usize] = nn;
+        prop_assert_eq!(machine.registers, expected);
+
+        // 7XNN never touches VF, even when it overflows
+        let machine = run(0x7000 | x << 8 | nn as u16, &state);
+        let mut expected = state.registers;
+        expected[x as usize] = state.registers[x as usize].wrapping_add(nn);
+        prop_assert_eq!(machine.registers, expected);
+    }
+
+    #[test]
+    fn random(state in state(), x in 0..16u16, nn in any::<u8>()) {
+        let machine = run(0xC000 | x << 8 | nn as u16, &state);
+        prop_assert_eq!(machine.registers[x as usize] & !nn, 0);
+    }
+
+    #[test]
+    fn set_index(state in state(), nnn in 0..0x1000u16) {
+        let machine = run(0xA000 | nnn, &state);
+        prop_assert_eq!(machine.index, nnn);
+        prop_assert_eq!(machine.registers, state.registers);
+        prop_assert_eq!(machine.pc, PROGRAM_START + 2);
+    }
+
+    #[test]
+    fn wait_for_key(mut state in state(), x in 0..16u16) {
+        // With a key held, VX gets the lowest one
+        let machine = run(0xF00A | x << 8, &state);
+        let mut expected = state.registers;
+        if state.keypad != 0 {
+            expected[x as usize] = state.keypad.trailing_zeros() as u8;
+            prop_assert_eq!(machine.pc, PROGRAM_START + 2);
+        } else {
+            prop_assert_eq!(machine.pc, PROGRAM_START);
+        }
+        prop_assert_eq!(machine.registers, expected);
+
+        // Without one, the PC goes back to run FX0A again
+        state.keypad = 0;
+        let machine = run(0xF00A | x << 8, &state);
+        prop_assert_eq!(machine.pc, PROGRAM_START);
+        prop_assert_eq!(machine.registers, state.registers);
+    }
+
+    #[test]
+    fn skips(state in state(), x in 0..16u16, y in 0..16u16, nn in any::<u8>()) {
+        let vx = state.registers[x as usize];
+        let vy = state.registers[y as usize];
+        let key_pressed = state.keypad & (1 << (vx & 0xF)) != 0;
+
+        for (opcode, skips) in [
+            (0x3000 | x << 8 | nn as u16, vx == nn),
+            (0x4000 | x << 8 | nn as u16, vx != nn),
+            (0x5000 | x << 8 | y << 4, vx == vy),
+            (0x9000 | x << 8 | y << 4, vx != vy),
+            (0xE09E | x << 8, key_pressed),
+            (0xE0A1 | x << 8, !key_pressed),
+        ] {
+            let machine = run(opcode, &state);
+            let expected = PROGRAM_START + if skips { 4 } else { 2 };
+            prop_assert_eq!(machine.pc, expected, "{:04X}", opcode);
+            prop_assert_eq!(machine.registers, state.registers);
+        }
+    }
+
+    #[test]
+    fn jumps(state in state(), nnn in 0x300u16..0xE00) {
+        let machine = run(0x1000 | nnn, &state);
+        prop_assert_eq!(machine.pc, nnn);
+
+        // BXNN adds VX, where X is the top nibble of the address, and BNNN always adds V0
+        let offset_register = if state.quirks.bxnn { nnn >> 8 } else { 0 };
+        let machine = run(0xB000 | nnn, &state);
+        prop_assert_eq!(machine.pc, nnn + state.registers[offset_register as usize] as u16);
+
+        let mut machine = machine_with_return(nnn, &state);
+        machine.cycle().unwrap();
+        prop_assert_eq!(machine.pc, nnn);
+        prop_assert_eq!(&machine.stack, &[PROGRAM_START + 2]);
+        machine.cycle().unwrap();
+        prop_assert_eq!(machine.pc, PROGRAM_START + 2);
+        prop_assert!(machine.stack.is_empty());
+    }
+
+    #[test]
+    fn store_bcd(state in state(), x in 0..16u16) {
+        let machine = run(0xF033 | x << 8, &state);
+        let vx = state.registers[x as usize];
+        let index = state.index as usize;
+        prop_assert_eq!(&machine.memory[index..index + 3], &[vx / 100, vx / 10 % 10, vx % 10]);
+        prop_assert_eq!(&machine.memory[index + 3..index + 16], &state.data[3..]);
+        prop_assert_eq!(machine.index, state.index);
+    }
+
+    #[test]
+    fn add_to_index(mut state in state(), x in 0..16u16, index in 0x202u16..=0xFF0) {
+        // Close enough to the end of memory to go past it
+        state.index = index;
+        let machine = run(0xF01E | x << 8, &state);
+
+        let result = state.index + state.registers[x as usize] as u16;
+        prop_assert_eq!(machine.index, result);
+
+        let mut expected = state.registers;
+        if state.quirks.set_vf_on_fx1e_overflow {
+            expected[0xF] = (result > 0xFFF) as u8;
+        }
+        prop_assert_eq!(machine.registers, expected);
+    }
+
+    #[test]
+    fn store_and_load(state in state(), x in 0..16u16) {
+        let count = x as usize + 1;
+        let index = state.index as usize;
+        // The original interpreter leaves I pointing past the last register
+        let expected_index = if state.quirks.fx_incr_index {
+            state.index + count as u16
+        } else {
+            state.index
+        };
+
+        let machine = run(0xF055 | x << 8, &state);
+        prop_assert_eq!(&machine.memory[index..index + count], &state.registers[..count]);
+        prop_assert_eq!(&machine.memory[index + count..index + 16], &state.data[count..]);
+        prop_assert_eq!(machine.index, expected_index);
+
+        let machine = run(0xF065 | x << 8, &state);
+        prop_assert_eq!(&machine.registers[..count], &state.data[..count]);
+        prop_assert_eq!(&machine.registers[count..], &state.registers[count..]);
+        prop_assert_eq!(machine.index, expected_index);
+    }
+
+    #[test]
+    fn timers_and_font(state in state(), x in 0..16u16, timer in any::<u8>()) {
+        let vx = state.registers[x as usize];
+
+        let mut machine = machine(0xF007 | x << 8, &state);
+        machine.dt = timer;
+        machine.cycle().unwrap();
+        prop_assert_eq!(machine.registers[x as usize], timer);
+
+        prop_assert_eq!(run(0xF015 | x << 8, &state).dt, vx);
+        prop_assert_eq!(run(0xF018 | x << 8, &state).st, vx);
+
+        let machine = run(0xF029 | x << 8, &state);
+        prop_assert_eq!(
+            machine.index,
+            FONT_START + (vx & 0xF) as u16 * FONT_CHAR_SIZE as u16
+        );
+    }
+
+    #[test]
+    fn draw(
+        state in state(),
+        x in 0..16u16,
+        y in 0..16u16,
+        n in 0..16u16,
+        rows in any::<[u64; DISPLAY_HEIGHT]>(),
+    ) {
+        let mut machine = machine(0xD000 | x << 8 | y << 4 | n, &state);
+        for (row, bits) in rows.iter().enumerate() {
+            machine.display.set_row(row, *bits);
+        }
+        let before = machine.display.clone();
+        machine.cycle().unwrap();
+
+        // The starting position wraps around, but the sprite is clipped at the edges
+        let left = state.registers[x as usize] as usize % DISPLAY_WIDTH;
+        let top = state.registers[y as usize] as usize % DISPLAY_HEIGHT;
+        let mut expected = before.clone();
+        let mut collision = false;
+        for (i, byte) in state.data[..n as usize].iter().enumerate() {
+            for bit in 0..8 {
+                let (px, py) = (left + bit, top + i);
+                if byte & (0x80 >> bit) == 0 || px >= DISPLAY_WIDTH || py >= DISPLAY_HEIGHT {
+                    continue;
+                }
+                collision |= before.get(px, py);
+                expected.set(px, py, !before.get(px, py));
+            }
+        }
+
+        prop_assert_eq!(&machine.display, &expected);
+        let mut expected_registers = state.registers;
+        expected_registers[0xF] = collision as u8;
+        prop_assert_eq!(machine.registers, expected_registers);
+    }
+
+    #[test]
+    fn clear_screen(state in state(), rows in any::<[u64; DISPLAY_HEIGHT]>()) {
+        let mut machine = machine(0x00E0, &state);
+        for (row, bits) in rows.iter().enumerate() {
+            machine.display.set_row(row, *bits);
+        }
+        machine.cycle().unwrap();
+        prop_assert_eq!(machine.display, Display::new());
+    }
+}
+
+/// A machine that calls the subroutine at `address`, which returns straight away.
+fn machine_with_return(address: u16, state: &State) -> Machine {
+    let mut machine = machine(0x2000 | address, state);
+    machine.memory[address as usize..][..2].copy_from_slice(&[0x00, 0xEE]);
+    machine
+}
+
+#[test]
+fn random_covers_every_byte() {
+    // C0FF, then jump back to it
+    let mut machine = Machine::builder(&[0xC0, 0xFF, 0x12, 0x00])
+        .seed(1)
+        .build()
+        .unwrap();
+    let mut seen = [false; 256];
+    for _ in 0..4096 {
+        machine.run_frame(2).unwrap();
+        seen[machine.registers[0] as usize] = true;
+    }
+    assert!(seen[0xFF]);
+    assert!(seen.iter().all(|seen| *seen));
+}
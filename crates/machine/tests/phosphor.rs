@@ -0,0 +1,38 @@
+use machine::{Drivers, Machine, Palette, Phosphor, Quirks, Rgb};
+
+#[test]
+fn phosphor_fades_erased_pixels() {
+    let mut machine = Machine::from_rom(&[], Quirks::modern_chip8(), Drivers::noop()).unwrap();
+    let mut phosphor = Phosphor::new(0.5);
+
+    machine.display.set(3, 4, true);
+    phosphor.update(&machine.display);
+    assert_eq!(phosphor.intensity(3, 4), 1.0);
+
+    machine.display.set(3, 4, false);
+    phosphor.update(&machine.display);
+    assert_eq!(phosphor.intensity(3, 4), 0.5);
+    phosphor.update(&machine.display);
+    assert_eq!(phosphor.intensity(3, 4), 0.25);
+
+    for _ in 0..8 {
+        phosphor.update(&machine.display);
+    }
+    assert_eq!(phosphor.intensity(3, 4), 0.0);
+    assert_eq!(phosphor.lit_pixels().count(), 0);
+}
+
+#[test]
+fn palette_colors() {
+    assert_eq!(Rgb::from_hex("#FFB000"), Some(Palette::AMBER.foreground));
+    assert_eq!(
+        Rgb::from_hex("33ff66"),
+        Some(Palette::GREEN_PHOSPHOR.foreground)
+    );
+    assert_eq!(Rgb::from_hex("#FFF"), None);
+    assert_eq!(Rgb::from_hex("#GGGGGG"), None);
+
+    assert_eq!(Palette::CLASSIC.color(0.0), Rgb::new(0, 0, 0));
+    assert_eq!(Palette::CLASSIC.color(1.0), Rgb::new(0xFF, 0xFF, 0xFF));
+    assert_eq!(Palette::CLASSIC.color(0.5), Rgb::new(0x80, 0x80, 0x80));
+}
@@ -0,0 +1,111 @@
+//! Inputs that used to panic, found by the fuzz targets in `fuzz/`.
+
+use machine::{
+    Drivers, Error, LoadError, Machine, Quirks, MEMORY_SIZE, PROGRAM_START, STACK_DEPTH,
+};
+use proptest::prelude::*;
+
+fn machine(rom: &[u8]) -> Machine {
+    Machine::from_rom(rom, Quirks::cosmac_vip(), Drivers::noop()).unwrap()
+}
+
+#[test]
+fn rejects_oversize_roms() {
+    let max = MEMORY_SIZE - PROGRAM_START as usize;
+    assert!(Machine::from_rom(&vec![0; max], Quirks::modern_chip8(), Drivers::noop()).is_ok());
+    assert_eq!(
+        Machine::from_rom(&vec![0; max + 1], Quirks::modern_chip8(), Drivers::noop()).err(),
+        Some(LoadError::RomTooLarge { size: max + 1, max })
+    );
+}
+
+#[test]
+fn store_wraps_around_memory() {
+    let mut machine = machine(&[
+        0xAF, 0xFE, // I = 0xFFE
+        0x60, 0x01, // V0 = 1
+        0x61, 0x02, // V1 = 2
+        0x62, 0x03, // V2 = 3
+        0xF2, 0x55, // Store V0 to V2
+    ]);
+    for _ in 0..5 {
+        machine.cycle().unwrap();
+    }
+
+    assert_eq!(&machine.memory[0xFFE..], &[1, 2]);
+    assert_eq!(machine.memory[0], 3);
+    // The COSMAC VIP quirk moves I past the end of memory
+    assert_eq!(machine.index, 0x1001);
+}
+
+#[test]
+fn bcd_and_draw_wrap_around_memory() {
+    let mut machine = machine(&[
+        0xAF, 0xFF, // I = 0xFFF
+        0x60, 0xFF, // V0 = 255
+        0xF0, 0x33, // Store 2, 5, 5
+        0x61, 0x00, // V1 = 0
+        0xD1, 0x13, // Draw the three digits at (0, 0)
+    ]);
+    for _ in 0..5 {
+        machine.cycle().unwrap();
+    }
+
+    assert_eq!(machine.memory[0xFFF], 2);
+    assert_eq!(&machine.memory[..2], &[5, 5]);
+    assert_eq!(machine.display.row(0), 2 << 56);
+    assert_eq!(machine.display.row(1), 5 << 56);
+    assert_eq!(machine.display.row(2), 5 << 56);
+}
+
+#[test]
+fn pc_wraps_around_memory() {
+    let mut machine = machine(&[0x1F, 0xFE]); // Jump to 0xFFE
+    machine.memory[0xFFE..].copy_from_slice(&[0x6B, 0x01]); // VB = 1
+    machine.memory[..2].copy_from_slice(&[0x6C, 0x02]); // VC = 2
+    for _ in 0..3 {
+        machine.cycle().unwrap();
+    }
+
+    assert_eq!((machine.registers[0xB], machine.registers[0xC]), (1, 2));
+    assert_eq!(machine.pc, 2);
+}
+
+#[test]
+fn call_fails_on_a_full_stack() {
+    // Calls itself forever
+    let mut machine = machine(&[0x22, 0x00]);
+    for _ in 0..STACK_DEPTH {
+        machine.cycle().unwrap();
+    }
+
+    assert_eq!(
+        machine.cycle(),
+        Err(Error::StackOverflow {
+            address: PROGRAM_START
+        })
+    );
+    assert_eq!(machine.stack.len(), STACK_DEPTH);
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(64))]
+
+    // A short stand-in for the fuzz targets, so that regressions show up in `cargo test`
+    #[test]
+    fn arbitrary_roms_never_panic(
+        rom in prop::collection::vec(any::<u8>(), 0..=MEMORY_SIZE - PROGRAM_START as usize),
+        cached in any::<bool>(),
+    ) {
+        let mut machine = machine(&rom);
+        machine.set_block_cache(cached);
+        for _ in 0..100 {
+            machine.keypad = machine.keypad.rotate_left(1) ^ 1;
+            if machine.run_frame(20).is_err() {
+                break;
+            }
+            prop_assert!((machine.pc as usize) < MEMORY_SIZE);
+            prop_assert!(machine.stack.len() <= STACK_DEPTH);
+        }
+    }
+}
@@ -0,0 +1,76 @@
+#![cfg(feature = "serde")]
+
+use machine::{Display, Drivers, Machine, Quirks};
+
+fn ibm_logo() -> Machine {
+    let rom = include_bytes!("../../../roms/ibm-logo.ch8");
+    let mut machine = Machine::from_rom(rom, Quirks::cosmac_vip(), Drivers::noop()).unwrap();
+    machine.run_frame(30).unwrap();
+    machine
+}
+
+#[test]
+fn round_trips_machine() {
+    let mut machine = ibm_logo();
+    machine.stack.push(0x234);
+    machine.keypad = 0b1010;
+
+    let json = serde_json::to_string(&machine).unwrap();
+    let restored: Machine = serde_json::from_str(&json).unwrap();
+
+    assert_eq!(restored.memory, machine.memory);
+    assert_eq!(restored.display, machine.display);
+    assert_eq!(restored.pc, machine.pc);
+    assert_eq!(restored.index, machine.index);
+    assert_eq!(restored.stack, machine.stack);
+    assert_eq!(restored.registers, machine.registers);
+    assert_eq!(restored.keypad, machine.keypad);
+    assert_eq!(restored.quirks, machine.quirks);
+    assert_eq!(restored.rng.get_seed(), machine.rng.get_seed());
+    assert!(restored.is_dirty());
+}
+
+#[test]
+fn serializes_compactly() {
+    let value = serde_json::to_value(ibm_logo()).unwrap();
+
+    let memory = value["memory"].as_str().unwrap();
+    assert_eq!(memory.len(), 4096 * 2);
+    // The ROM starts with 00E0 A22A
+    assert_eq!(&memory[0x200 * 2..0x204 * 2], "00e0a22a");
+
+    // The display is one string per row
+    let rows = value["display"].as_array().unwrap();
+    assert_eq!(rows.len(), 32);
+    assert_eq!(rows[0].as_str().unwrap(), ".".repeat(64));
+    assert!(rows[8].as_str().unwrap()[12..].starts_with("########"));
+
+    assert_eq!(value["quirks"]["fx_incr_index"], true);
+}
+
+#[test]
+fn rejects_invalid_display() {
+    let mut rows = vec![".".repeat(64); 32];
+    assert!(serde_json::from_value::<Display>(rows.clone().into()).is_ok());
+
+    rows[3] = "x".repeat(64);
+    assert!(serde_json::from_value::<Display>(rows.clone().into()).is_err());
+    rows[3] = ".".repeat(63);
+    assert!(serde_json::from_value::<Display>(rows.clone().into()).is_err());
+    rows.pop();
+    assert!(serde_json::from_value::<Display>(rows.into()).is_err());
+}
+
+#[test]
+fn rejects_font_outside_memory() {
+    let mut value = serde_json::to_value(ibm_logo()).unwrap();
+    value["font_address"] = 65535.into();
+    assert!(serde_json::from_value::<Machine>(value).is_err());
+}
+
+#[test]
+fn rejects_large_font_outside_memory() {
+    let mut value = serde_json::to_value(ibm_logo()).unwrap();
+    value["large_font_address"] = 65535.into();
+    assert!(serde_json::from_value::<Machine>(value).is_err());
+}
@@ -0,0 +1,94 @@
+use machine::{state_size, Drivers, Machine, Quirks, StateError, STACK_DEPTH, VIP_STACK_DEPTH};
+
+fn ibm_logo() -> Machine {
+    let rom = include_bytes!("../../../roms/ibm-logo.ch8");
+    Machine::from_rom(rom, Quirks::modern_chip8(), Drivers::noop()).unwrap()
+}
+
+#[test]
+fn restores_saved_state() {
+    let mut machine = ibm_logo();
+    machine.run_frame(10).unwrap();
+    machine.stack.push(0x234);
+    let state = machine.save_state();
+    assert_eq!(state.len(), state_size(1));
+
+    let mut restored = ibm_logo();
+    restored.take_frame();
+    restored.load_state(&state).unwrap();
+
+    assert_eq!(restored.memory, machine.memory);
+    assert_eq!(restored.display, machine.display);
+    assert_eq!(restored.pc, machine.pc);
+    assert_eq!(restored.index, machine.index);
+    assert_eq!(restored.registers, machine.registers);
+    assert_eq!(restored.stack, machine.stack);
+    assert_eq!(restored.rng.get_seed(), machine.rng.get_seed());
+    // Frontends need to redraw everything after loading
+    assert!(restored.is_dirty());
+
+    // Padding after the state is ignored
+    let mut padded = state.clone();
+    padded.resize(state_size(16), 0);
+    restored.load_state(&padded).unwrap();
+    assert_eq!(restored.save_state(), state);
+}
+
+#[test]
+fn rejects_invalid_state() {
+    let mut machine = ibm_logo();
+    let mut state = machine.save_state();
+
+    assert_eq!(
+        machine.load_state(&state[..state.len() - 1]),
+        Err(StateError::Truncated)
+    );
+    assert_eq!(
+        machine.load_state(b"not a state"),
+        Err(StateError::InvalidHeader)
+    );
+    state[4] = 0xFF;
+    assert_eq!(
+        machine.load_state(&state),
+        Err(StateError::UnsupportedVersion(0xFF))
+    );
+}
+
+#[test]
+fn rejects_state_that_does_not_fit() {
+    let mut machine = ibm_logo();
+    machine.pc = 0x900;
+    let state = machine.save_state();
+
+    // The PC has to be inside memory
+    let mut small = Machine::builder(&[]).memory_size(0x800).build().unwrap();
+    assert_eq!(small.load_state(&state), Err(StateError::OutOfRange));
+    ibm_logo().load_state(&state).unwrap();
+
+    // So do return addresses
+    machine.pc = 0x200;
+    machine.stack.push(0x900);
+    let state = machine.save_state();
+    assert_eq!(small.load_state(&state), Err(StateError::OutOfRange));
+
+    // And the stack can't be deeper than 2NNN would let it grow
+    machine.stack = vec![0x200; STACK_DEPTH + 1];
+    let state = machine.save_state();
+    assert_eq!(ibm_logo().load_state(&state), Err(StateError::OutOfRange));
+
+    machine.stack = vec![0x200; VIP_STACK_DEPTH + 1];
+    let state = machine.save_state();
+    ibm_logo().load_state(&state).unwrap();
+    let mut vip = Machine::builder(&[]).vip_memory_map(true).build().unwrap();
+    assert_eq!(vip.load_state(&state), Err(StateError::OutOfRange));
+    // Without changing the machine
+    assert!(vip.stack.is_empty());
+    assert_eq!(vip.pc, 0x200);
+}
+
+#[test]
+fn max_state_size_holds_a_full_stack() {
+    let mut machine = ibm_logo();
+    machine.stack = vec![0x200; STACK_DEPTH];
+    assert_eq!(machine.save_state().len(), state_size(STACK_DEPTH));
+}
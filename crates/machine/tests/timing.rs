@@ -0,0 +1,76 @@
+use machine::{Machine, Quirks, VIP_AVAILABLE_CYCLES};
+
+fn machine(rom: &[u8]) -> Machine {
+    Machine::builder(rom)
+        .quirks(Quirks::cosmac_vip())
+        .build()
+        .unwrap()
+}
+
+#[test]
+fn charges_each_instruction() {
+    let mut machine = machine(&[
+        0x70, 0x01, // 200: V0 += 1, 19 cycles with the fetch
+        0x12, 0x00, // 202: Jump to 0x200, 32 cycles
+    ]);
+    machine.run_vip_frame().unwrap();
+
+    // The 51st jump goes past the end of the frame
+    assert_eq!(VIP_AVAILABLE_CYCLES.div_ceil(19 + 32), 51);
+    assert_eq!(machine.registers[0], 51);
+    assert_eq!(machine.pc, 0x200);
+
+    // The 3 cycles it went over come out of the next frame
+    machine.run_vip_frame().unwrap();
+    assert_eq!(machine.registers[0], 102);
+}
+
+#[test]
+fn slow_instructions_run_fewer_times() {
+    let mut machine = machine(&[
+        0xA3, 0x00, // 200: I = 0x300
+        0x70, 0x01, // 202: V0 += 1
+        0xFF, 0x33, // 204: Store the digits of VF
+        0x12, 0x02, // 206: Jump to 0x202
+    ]);
+    machine.run_vip_frame().unwrap();
+    // Each time round the loop is 264 cycles, most of them in FX33
+    assert_eq!(
+        machine.registers[0],
+        (VIP_AVAILABLE_CYCLES - 21).div_ceil(264) as u8
+    );
+}
+
+#[test]
+fn draw_waits_for_vblank() {
+    let mut machine = machine(&[
+        0xA2, 0x0A, // 200: I = 0x20A
+        0xD0, 0x11, // 202: Draw
+        0xD0, 0x11, // 204: Draw again
+        0x12, 0x06, // 206: Loop forever
+        0x00, 0x00, // 208
+        0x80, // 20A: Sprite
+    ]);
+
+    // The first draw waits for the next frame
+    machine.run_vip_frame().unwrap();
+    assert_eq!(machine.pc, 0x202);
+    assert!(!machine.display.get(0, 0));
+
+    machine.run_vip_frame().unwrap();
+    assert_eq!(machine.pc, 0x204);
+    assert!(machine.display.get(0, 0));
+
+    machine.run_vip_frame().unwrap();
+    assert!(!machine.display.get(0, 0));
+    assert_eq!(machine.pc, 0x206);
+}
+
+#[test]
+fn decrements_timers_once_per_frame() {
+    let mut machine = machine(&[0x12, 0x00]);
+    machine.dt = 10;
+    machine.run_vip_frame().unwrap();
+    machine.run_vip_frame().unwrap();
+    assert_eq!(machine.dt, 8);
+}
@@ -0,0 +1,110 @@
+use machine::{
+    Error, LoadError, Machine, PROGRAM_START, VIP_DISPLAY, VIP_REGISTERS, VIP_STACK,
+    VIP_STACK_DEPTH,
+};
+
+fn machine(rom: &[u8]) -> Machine {
+    Machine::builder(rom).vip_memory_map(true).build().unwrap()
+}
+
+#[test]
+fn keeps_registers_in_memory() {
+    let mut machine = machine(&[0x63, 0x42]); // V3 = 0x42
+    machine.cycle().unwrap();
+    assert_eq!(machine.memory[VIP_REGISTERS as usize + 3], 0x42);
+}
+
+#[test]
+fn stores_over_registers() {
+    let mut machine = machine(&[
+        0x60, 0x07, // V0 = 7
+        0xAE, 0xF5, // I = the address of V5
+        0xF0, 0x55, // Store V0 in V5
+    ]);
+    machine.run_frame(3).unwrap();
+    assert_eq!(machine.registers[5], 7);
+}
+
+#[test]
+fn loads_from_the_display() {
+    let mut machine = machine(&[
+        0xA2, 0x08, // I = 0x208
+        0xD0, 0x01, // Draw the sprite at (0, 0)
+        0xAF, 0x00, // I = the display
+        0xF0, 0x65, // Load the first byte into V0
+        0xC0, // 208: Sprite
+    ]);
+    machine.run_frame(4).unwrap();
+    assert_eq!(machine.registers[0], 0xC0);
+}
+
+#[test]
+fn stores_into_the_display() {
+    let mut machine = machine(&[
+        0x60, 0xFF, // V0 = 0xFF
+        0xAF, 0x08, // I = the start of the second row
+        0xF0, 0x55, // Store V0 there
+    ]);
+    machine.take_frame();
+    machine.run_frame(3).unwrap();
+
+    assert_eq!(machine.memory[VIP_DISPLAY as usize + 8], 0xFF);
+    assert_eq!(machine.display.row(1), 0xFF << 56);
+    assert!(machine.is_dirty());
+}
+
+#[test]
+fn stores_over_return_addresses() {
+    let mut machine = machine(&[
+        0x22, 0x0A, // 200: Call 0x20A
+        0x12, 0x02, // 202: Loop forever, if it returned normally
+        0x00, 0x00, // 204
+        0x6A, 0x01, // 206: VA = 1
+        0x12, 0x08, // 208: Loop forever
+        0x60, 0x02, // 20A: V0 = 0x02
+        0x61, 0x06, // 20C: V1 = 0x06
+        0xAE, 0xCE, // 20E: I = the return address
+        0xF1, 0x55, // 210: Overwrite it with 0x206
+        0x00, 0xEE, // 212: Return
+    ]);
+    machine.run_frame(7).unwrap();
+    assert_eq!(machine.pc, 0x208);
+    assert_eq!(machine.registers[0xA], 1);
+}
+
+#[test]
+fn stack_is_12_deep() {
+    let mut machine = machine(&[0x22, 0x00]); // Call itself
+    machine.run_frame(VIP_STACK_DEPTH).unwrap();
+    assert_eq!(
+        machine.cycle(),
+        Err(Error::StackOverflow { address: 0x200 })
+    );
+    // The return addresses go down from 0xECF
+    assert_eq!(&machine.memory[0xECE..0xED0], &[0x02, 0x02]);
+    let bottom = 0xED0 - 2 * VIP_STACK_DEPTH;
+    assert_eq!(&machine.memory[bottom..bottom + 2], &[0x02, 0x02]);
+}
+
+#[test]
+fn rom_cannot_overlap_the_work_area() {
+    let rom = vec![0; (VIP_STACK - PROGRAM_START) as usize + 1];
+    assert_eq!(
+        Machine::builder(&rom).vip_memory_map(true).build().err(),
+        Some(LoadError::RomTooLarge {
+            size: rom.len(),
+            max: rom.len() - 1,
+        })
+    );
+}
+
+#[test]
+fn moves_with_the_end_of_memory() {
+    let mut machine = Machine::builder(&[0x63, 0x42])
+        .memory_size(0x800)
+        .vip_memory_map(true)
+        .build()
+        .unwrap();
+    machine.cycle().unwrap();
+    assert_eq!(machine.memory[0x6F3], 0x42);
+}
@@ -0,0 +1,45 @@
+"""A CHIP-8 emulator."""
+
+import numpy as np
+import numpy.typing as npt
+
+DISPLAY_WIDTH: int
+DISPLAY_HEIGHT: int
+
+class MachineError(RuntimeError):
+    """Raised when an instruction can't be executed."""
+
+class Machine:
+    keypad: int
+    """Bitmask of the keys held down, with bit `n` set if key `n` is pressed."""
+    registers: bytes
+    """The registers V0 to VF."""
+    pc: int
+    index: int
+    dt: int
+    """The delay timer."""
+    st: int
+    """The sound timer."""
+    @property
+    def stack(self) -> list[int]:
+        """The return addresses of the subroutines being called, innermost last."""
+
+    def __init__(self, rom: bytes, quirks: str = "modern") -> None:
+        """Loads a ROM. `quirks` is one of "modern", "cosmac-vip" or "superchip"."""
+
+    def step(self) -> None:
+        """Executes a single instruction."""
+
+    def run_frame(self, cycles: int = 11) -> None:
+        """Decrements the timers, then executes `cycles` instructions."""
+
+    def display(self) -> npt.NDArray[np.bool_]:
+        """Returns the display as a `(height, width)` array of booleans."""
+
+    def set_key(self, key: int, pressed: bool) -> None: ...
+    def read_memory(self, address: int, length: int) -> bytes: ...
+    def write_memory(self, address: int, data: bytes) -> None: ...
+    def snapshot(self) -> bytes:
+        """Saves everything needed to resume execution later with `restore`."""
+
+    def restore(self, snapshot: bytes) -> None: ...
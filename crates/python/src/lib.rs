@@ -0,0 +1,195 @@
+//! Python bindings for the emulator, built with `maturin`. See `chip8.pyi` for the
+//! API as Python sees it.
+
+use machine::{Drivers, Machine, Quirks, DISPLAY_HEIGHT, DISPLAY_WIDTH, MEMORY_SIZE};
+use numpy::{PyArray1, PyArray2, PyArrayMethods};
+use pyo3::{
+    create_exception,
+    exceptions::{PyIndexError, PyRuntimeError, PyValueError},
+    prelude::*,
+    types::PyBytes,
+};
+
+create_exception!(
+    chip8,
+    MachineError,
+    PyRuntimeError,
+    "Raised when an instruction can't be executed."
+);
+
+fn quirks(preset: &str) -> PyResult<Quirks> {
+    match preset {
+        "modern" => Ok(Quirks::modern_chip8()),
+        "cosmac-vip" => Ok(Quirks::cosmac_vip()),
+        "superchip" => Ok(Quirks::superchip()),
+        _ => Err(PyValueError::new_err(format!(
+            "Unknown quirk preset {preset:?}, expected \"modern\", \"cosmac-vip\" or \"superchip\""
+        ))),
+    }
+}
+
+/// Checks that `len` bytes starting at `address` are within memory.
+fn check_range(address: usize, len: usize) -> PyResult<()> {
+    match address.checked_add(len) {
+        Some(end) if end <= MEMORY_SIZE => Ok(()),
+        _ => Err(PyIndexError::new_err(format!(
+            "{len} bytes at {address:#05X} are outside of memory"
+        ))),
+    }
+}
+
+#[pyclass(name = "Machine", module = "chip8")]
+struct PyMachine {
+    machine: Machine,
+}
+
+#[pymethods]
+impl PyMachine {
+    #[new]
+    #[pyo3(signature = (rom, quirks = "modern"))]
+    fn new(rom: &[u8], quirks: &str) -> PyResult<Self> {
+        let machine = Machine::from_rom(rom, self::quirks(quirks)?, Drivers::noop())
+            .map_err(|err| PyValueError::new_err(err.to_string()))?;
+        Ok(Self { machine })
+    }
+
+    /// Executes a single instruction.
+    fn step(&mut self) -> PyResult<()> {
+        self.machine
+            .cycle()
+            .map_err(|err| MachineError::new_err(err.to_string()))
+    }
+
+    /// Decrements the timers, then executes `cycles` instructions.
+    #[pyo3(signature = (cycles = machine::CYCLES_PER_SECOND / 60))]
+    fn run_frame(&mut self, cycles: usize) -> PyResult<()> {
+        self.machine
+            .run_frame(cycles)
+            .map_err(|err| MachineError::new_err(err.to_string()))
+    }
+
+    /// Returns the display as a `(height, width)` array of booleans.
+    fn display<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyArray2<bool>>> {
+        let pixels: Vec<bool> = self.machine.display.iter().map(|(_, on)| on).collect();
+        PyArray1::from_vec(py, pixels).reshape([DISPLAY_HEIGHT, DISPLAY_WIDTH])
+    }
+
+    fn set_key(&mut self, key: u8, pressed: bool) -> PyResult<()> {
+        if key > 0xF {
+            return Err(PyValueError::new_err(format!("Key {key} isn't 0 to 15")));
+        }
+        self.machine.set_key(key, pressed);
+        Ok(())
+    }
+
+    /// Bitmask of the keys held down, with bit `n` set if key `n` is pressed.
+    #[getter]
+    fn keypad(&self) -> u16 {
+        self.machine.keypad
+    }
+
+    #[setter]
+    fn set_keypad(&mut self, keypad: u16) {
+        self.machine.keypad = keypad;
+    }
+
+    fn read_memory<'py>(
+        &self,
+        py: Python<'py>,
+        address: usize,
+        length: usize,
+    ) -> PyResult<Bound<'py, PyBytes>> {
+        check_range(address, length)?;
+        Ok(PyBytes::new(
+            py,
+            &self.machine.memory[address..address + length],
+        ))
+    }
+
+    fn write_memory(&mut self, address: usize, data: &[u8]) -> PyResult<()> {
+        check_range(address, data.len())?;
+        self.machine.memory[address..address + data.len()].copy_from_slice(data);
+        Ok(())
+    }
+
+    /// The registers V0 to VF.
+    #[getter]
+    fn registers(&self) -> [u8; 16] {
+        self.machine.registers
+    }
+
+    #[setter]
+    fn set_registers(&mut self, registers: [u8; 16]) {
+        self.machine.registers = registers;
+    }
+
+    #[getter]
+    fn pc(&self) -> u16 {
+        self.machine.pc
+    }
+
+    #[setter]
+    fn set_pc(&mut self, pc: u16) -> PyResult<()> {
+        check_range(pc as usize, 2)?;
+        self.machine.pc = pc;
+        Ok(())
+    }
+
+    #[getter]
+    fn index(&self) -> u16 {
+        self.machine.index
+    }
+
+    #[setter]
+    fn set_index(&mut self, index: u16) {
+        self.machine.index = index;
+    }
+
+    /// The delay timer.
+    #[getter]
+    fn dt(&self) -> u8 {
+        self.machine.dt
+    }
+
+    #[setter]
+    fn set_dt(&mut self, dt: u8) {
+        self.machine.dt = dt;
+    }
+
+    /// The sound timer.
+    #[getter]
+    fn st(&self) -> u8 {
+        self.machine.st
+    }
+
+    #[setter]
+    fn set_st(&mut self, st: u8) {
+        self.machine.st = st;
+    }
+
+    /// The return addresses of the subroutines being called, innermost last.
+    #[getter]
+    fn stack(&self) -> Vec<u16> {
+        self.machine.stack.clone()
+    }
+
+    /// Saves everything needed to resume execution later with `restore`.
+    fn snapshot<'py>(&self, py: Python<'py>) -> Bound<'py, PyBytes> {
+        PyBytes::new(py, &self.machine.save_state())
+    }
+
+    fn restore(&mut self, snapshot: &[u8]) -> PyResult<()> {
+        self.machine
+            .load_state(snapshot)
+            .map_err(|err| PyValueError::new_err(err.to_string()))
+    }
+}
+
+#[pymodule]
+fn chip8(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyMachine>()?;
+    m.add("MachineError", m.py().get_type::<MachineError>())?;
+    m.add("DISPLAY_WIDTH", DISPLAY_WIDTH)?;
+    m.add("DISPLAY_HEIGHT", DISPLAY_HEIGHT)?;
+    Ok(())
+}
@@ -0,0 +1,77 @@
+from pathlib import Path
+
+import numpy as np
+import pytest
+
+import chip8
+
+IBM_LOGO = (Path(__file__).parents[3] / "roms" / "ibm-logo.ch8").read_bytes()
+
+
+def test_draws_ibm_logo():
+    machine = chip8.Machine(IBM_LOGO)
+    for _ in range(10):
+        machine.run_frame()
+
+    display = machine.display()
+    assert display.shape == (chip8.DISPLAY_HEIGHT, chip8.DISPLAY_WIDTH)
+    assert display.dtype == np.bool_
+    # The top left corner of the "I"
+    assert display[8, 12]
+    assert not display[0, 0]
+
+
+def test_registers_and_memory():
+    machine = chip8.Machine(bytes([0x60, 0x2A, 0xA3, 0x00]))
+    machine.step()
+    machine.step()
+    assert machine.registers[0] == 0x2A
+    assert machine.index == 0x300
+    assert machine.pc == 0x204
+
+    machine.write_memory(0x300, b"\x12\x34")
+    assert machine.read_memory(0x300, 2) == b"\x12\x34"
+    with pytest.raises(IndexError):
+        machine.read_memory(0xFFF, 2)
+
+    machine.registers = bytes(range(16))
+    assert machine.registers[15] == 15
+
+
+def test_snapshot_and_restore():
+    machine = chip8.Machine(IBM_LOGO)
+    machine.run_frame()
+    snapshot = machine.snapshot()
+    before = machine.display()
+
+    for _ in range(10):
+        machine.run_frame()
+    machine.restore(snapshot)
+    assert machine.snapshot() == snapshot
+    assert (machine.display() == before).all()
+
+    with pytest.raises(ValueError):
+        machine.restore(b"not a snapshot")
+
+
+def test_keys():
+    # FX0A waits for a key and stores it in V0
+    machine = chip8.Machine(bytes([0xF0, 0x0A]))
+    machine.step()
+    assert machine.pc == 0x200
+
+    machine.set_key(0xB, True)
+    assert machine.keypad == 1 << 0xB
+    machine.step()
+    assert machine.registers[0] == 0xB
+
+
+def test_errors():
+    with pytest.raises(ValueError):
+        chip8.Machine(b"", quirks="unknown")
+    with pytest.raises(ValueError):
+        chip8.Machine(bytes(4096))
+
+    machine = chip8.Machine(b"\xff\xff")
+    with pytest.raises(chip8.MachineError):
+        machine.step()
@@ -0,0 +1,192 @@
+use std::fmt::Write;
+
+use machine::{Instruction, PROGRAM_START};
+
+use crate::Block;
+
+/// The generated code that drives the compiled blocks, which is the same for
+/// every ROM.
+const RUNTIME: &str = r#"
+type BlockFn = fn(&mut Machine) -> Result<(), Error>;
+
+/// Runs one 60 Hz frame like [`Machine::run_frame`], using the compiled blocks
+/// wherever the code is unchanged from the ROM and the interpreter everywhere else.
+pub fn run_frame(m: &mut Machine, cycles: usize) -> Result<(), Error> {
+    m.decr_timers();
+
+    let mut remaining = cycles;
+    while remaining > 0 {
+        match compiled_block(m) {
+            // A block only runs if it fits in the frame, so that frames end on the
+            // same instruction as they do in the interpreter
+            Some((len, run)) if len <= remaining => {
+                run(m)?;
+                remaining -= len;
+            }
+            _ => {
+                m.cycle()?;
+                remaining -= 1;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns the number of instructions in the block at the PC and the function
+/// running it, unless the ROM has written over that code since it was loaded.
+fn compiled_block(m: &Machine) -> Option<(usize, BlockFn)> {
+    let (len, run): (usize, BlockFn) = match m.pc {
+"#;
+
+/// Generates the Rust source for `blocks`, discovered in `rom`.
+pub fn generate(rom: &[u8], name: &str, blocks: &[Block]) -> String {
+    let mut out = String::new();
+    let delegates = blocks
+        .iter()
+        .flat_map(|block| &block.instructions)
+        .any(|instruction| inline(*instruction, 0).is_none());
+
+    writeln!(out, "// @generated").unwrap();
+    writeln!(
+        out,
+        "//! {name}, recompiled to Rust. Generated by the recompiler crate, so don't edit it by hand."
+    )
+    .unwrap();
+    writeln!(out).unwrap();
+    if delegates {
+        writeln!(
+            out,
+            "use machine::{{Error, Instruction, Machine, PROGRAM_START}};"
+        )
+        .unwrap();
+    } else {
+        writeln!(out, "use machine::{{Error, Machine, PROGRAM_START}};").unwrap();
+    }
+    writeln!(out).unwrap();
+
+    writeln!(out, "/// The ROM, to load with [`Machine::from_rom`].").unwrap();
+    writeln!(out, "pub const ROM: &[u8] = &[").unwrap();
+    for line in rom.chunks(12) {
+        let bytes: Vec<_> = line.iter().map(|byte| format!("0x{byte:02X},")).collect();
+        writeln!(out, "    {}", bytes.join(" ")).unwrap();
+    }
+    writeln!(out, "];").unwrap();
+
+    out.push_str(RUNTIME);
+    for block in blocks {
+        writeln!(
+            out,
+            "        0x{:03X} => ({}, block_{:03x}),",
+            block.start,
+            block.instructions.len(),
+            block.start
+        )
+        .unwrap();
+    }
+    writeln!(out, "        _ => return None,").unwrap();
+    writeln!(out, "    }};").unwrap();
+    writeln!(out).unwrap();
+    writeln!(out, "    let start = m.pc as usize;").unwrap();
+    writeln!(
+        out,
+        "    let code = &ROM[start - PROGRAM_START as usize..][..len * 2];"
+    )
+    .unwrap();
+    writeln!(
+        out,
+        "    (m.memory[start..start + len * 2] == *code).then_some((len, run))"
+    )
+    .unwrap();
+    writeln!(out, "}}").unwrap();
+
+    for block in blocks {
+        writeln!(out).unwrap();
+        write_block(&mut out, rom, block);
+    }
+
+    out
+}
+
+fn write_block(out: &mut String, rom: &[u8], block: &Block) {
+    writeln!(
+        out,
+        "fn block_{:03x}(m: &mut Machine) -> Result<(), Error> {{",
+        block.start
+    )
+    .unwrap();
+
+    let mut address = block.start;
+    for &instruction in &block.instructions {
+        let offset = (address - PROGRAM_START) as usize;
+        writeln!(
+            out,
+            "    // {address:03X}: {:02X}{:02X}",
+            rom[offset],
+            rom[offset + 1]
+        )
+        .unwrap();
+
+        let next = address + 2;
+        match inline(instruction, next) {
+            Some(code) => writeln!(out, "    {code}").unwrap(),
+            None => {
+                // The machine has to see the same PC as when it runs the instruction itself
+                writeln!(out, "    m.pc = 0x{next:03X};").unwrap();
+                writeln!(out, "    m.execute(Instruction::{instruction:?})?;").unwrap();
+            }
+        }
+        address = next;
+    }
+
+    // Blocks that run off the end of the ROM carry on in the interpreter
+    let last = block.instructions.last();
+    if !last.is_some_and(|instruction| instruction.ends_block()) {
+        writeln!(out, "    m.pc = 0x{address:03X};").unwrap();
+    }
+
+    writeln!(out, "    Ok(())").unwrap();
+    writeln!(out, "}}").unwrap();
+}
+
+/// Returns Rust code for `instruction` if it's simple enough to inline, where `next`
+/// is the address of the next instruction. Anything else is left to
+/// [`machine::Machine::execute`], so that quirks and edge cases behave exactly the
+/// same as in the interpreter.
+fn inline(instruction: Instruction, next: u16) -> Option<String> {
+    let skip = |condition: String| {
+        format!(
+            "m.pc = if {condition} {{ 0x{:03X} }} else {{ 0x{next:03X} }};",
+            next + 2
+        )
+    };
+
+    Some(match instruction {
+        Instruction::Jump { address } => format!("m.pc = 0x{address:03X};"),
+        Instruction::SkipIfEqual { x, value } => {
+            skip(format!("m.registers[0x{x:X}] == 0x{value:02X}"))
+        }
+        Instruction::SkipIfNotEqual { x, value } => {
+            skip(format!("m.registers[0x{x:X}] != 0x{value:02X}"))
+        }
+        Instruction::SkipIfRegistersEqual { x, y } => {
+            skip(format!("m.registers[0x{x:X}] == m.registers[0x{y:X}]"))
+        }
+        Instruction::SkipIfRegistersNotEqual { x, y } => {
+            skip(format!("m.registers[0x{x:X}] != m.registers[0x{y:X}]"))
+        }
+        Instruction::Set { x, value } => format!("m.registers[0x{x:X}] = 0x{value:02X};"),
+        Instruction::Add { x, value } => {
+            format!("m.registers[0x{x:X}] = m.registers[0x{x:X}].wrapping_add(0x{value:02X});")
+        }
+        Instruction::SetIndex { address } => format!("m.index = 0x{address:03X};"),
+        Instruction::Copy { x, y } if x == y => "// Copies a register to itself".to_owned(),
+        Instruction::Copy { x, y } => format!("m.registers[0x{x:X}] = m.registers[0x{y:X}];"),
+        Instruction::Or { x, y } => format!("m.registers[0x{x:X}] |= m.registers[0x{y:X}];"),
+        Instruction::And { x, y } => format!("m.registers[0x{x:X}] &= m.registers[0x{y:X}];"),
+        Instruction::Xor { x, y } => format!("m.registers[0x{x:X}] ^= m.registers[0x{y:X}];"),
+        Instruction::GetDelayTimer { x } => format!("m.registers[0x{x:X}] = m.dt;"),
+        Instruction::SetDelayTimer { x } => format!("m.dt = m.registers[0x{x:X}];"),
+        _ => return None,
+    })
+}
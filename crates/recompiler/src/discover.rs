@@ -0,0 +1,87 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use machine::{Instruction, PROGRAM_START};
+
+/// A straight-line run of instructions, ending at the first one that can go
+/// somewhere other than the next instruction (or at the end of the ROM).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Block {
+    pub start: u16,
+    pub instructions: Vec<Instruction>,
+}
+
+impl Block {
+    /// The address just past the block's last instruction.
+    pub fn end(&self) -> u16 {
+        self.start + self.instructions.len() as u16 * 2
+    }
+}
+
+/// Finds the blocks reachable from `PROGRAM_START` by following jumps, calls and
+/// skips, sorted by address.
+///
+/// Computed jumps (BNNN) can't be followed, so code only reachable through them is
+/// left to the interpreter, along with anything outside of the ROM. Blocks can
+/// overlap when something jumps into the middle of another block.
+pub fn discover(rom: &[u8]) -> Vec<Block> {
+    let rom_end = PROGRAM_START as usize + rom.len();
+    let opcode_at = |address: u16| {
+        let offset = (address - PROGRAM_START) as usize;
+        ((rom[offset] as u16) << 8) | rom[offset + 1] as u16
+    };
+    let in_rom = |address: u16| address >= PROGRAM_START && address as usize + 1 < rom_end;
+
+    let mut blocks = BTreeMap::new();
+    let mut queue = vec![PROGRAM_START];
+    let mut seen = BTreeSet::new();
+    while let Some(start) = queue.pop() {
+        if !in_rom(start) || !seen.insert(start) {
+            continue;
+        }
+
+        let mut instructions = Vec::new();
+        let mut address = start;
+        while in_rom(address) {
+            let instruction = Instruction::decode(opcode_at(address));
+            instructions.push(instruction);
+            address += 2;
+
+            if instruction.ends_block() {
+                let last = address - 2;
+                queue.extend(successors(instruction, last));
+                break;
+            }
+        }
+
+        blocks.insert(
+            start,
+            Block {
+                start,
+                instructions,
+            },
+        );
+    }
+
+    blocks.into_values().collect()
+}
+
+/// Where execution can go after the block-ending `instruction` at `address`.
+fn successors(instruction: Instruction, address: u16) -> Vec<u16> {
+    let next = address + 2;
+    match instruction {
+        Instruction::Jump { address } => vec![address],
+        // Returning from the subroutine lands on the next instruction
+        Instruction::Call { address } => vec![address, next],
+        Instruction::SkipIfEqual { .. }
+        | Instruction::SkipIfNotEqual { .. }
+        | Instruction::SkipIfRegistersEqual { .. }
+        | Instruction::SkipIfRegistersNotEqual { .. }
+        | Instruction::SkipIfKey { .. }
+        | Instruction::SkipIfNotKey { .. } => vec![next, next + 2],
+        // Machine code returns to the next instruction, unless it changes R5
+        Instruction::MachineCall { .. } => vec![next],
+        // FX0A runs again until a key is pressed
+        Instruction::WaitForKey { .. } => vec![address, next],
+        _ => Vec::new(),
+    }
+}
@@ -0,0 +1,21 @@
+use std::fmt;
+
+/// An error raised while recompiling a ROM.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Error {
+    /// The ROM doesn't fit in memory.
+    RomTooLarge { size: usize, max: usize },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::RomTooLarge { size, max } => write!(
+                f,
+                "ROM is {size} bytes, but at most {max} bytes fit in memory"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
@@ -0,0 +1,30 @@
+//! An ahead-of-time recompiler, which turns a CHIP-8 ROM into Rust source with one
+//! function per basic block, for shipping games as native binaries.
+//!
+//! The generated module exports the `ROM` and a `run_frame` function that stands in
+//! for [`machine::Machine::run_frame`]. It falls back to the interpreter for code
+//! that can't be found ahead of time (like the targets of BNNN) and for code that
+//! the ROM has written over since it was loaded.
+
+mod codegen;
+mod discover;
+mod error;
+
+use machine::{MEMORY_SIZE, PROGRAM_START};
+
+pub use discover::{discover, Block};
+pub use error::Error;
+
+/// Recompiles `rom` to the source of a Rust module. `name` is only used in the
+/// generated docs.
+pub fn recompile(rom: &[u8], name: &str) -> Result<String, Error> {
+    let max = MEMORY_SIZE - PROGRAM_START as usize;
+    if rom.len() > max {
+        return Err(Error::RomTooLarge {
+            size: rom.len(),
+            max,
+        });
+    }
+
+    Ok(codegen::generate(rom, name, &discover(rom)))
+}
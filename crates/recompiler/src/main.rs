@@ -0,0 +1,36 @@
+use std::fs;
+use std::path::PathBuf;
+
+use clap::Parser;
+use color_eyre::{eyre::WrapErr, Result};
+
+#[derive(Parser)]
+#[command(version, about, long_about = None)]
+struct Cli {
+    /// The path to the CHIP-8 ROM.
+    path: PathBuf,
+
+    /// Where to write the generated Rust module. Defaults to stdout.
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+}
+
+fn main() -> Result<()> {
+    color_eyre::install()?;
+
+    let cli = Cli::parse();
+
+    let rom = fs::read(&cli.path).wrap_err("Failed to read ROM")?;
+    let name = cli
+        .path
+        .file_name()
+        .map_or_else(|| "ROM".into(), |name| name.to_string_lossy());
+    let source = recompiler::recompile(&rom, &name)?;
+
+    match cli.output {
+        Some(output) => fs::write(output, source).wrap_err("Failed to write the module")?,
+        None => print!("{source}"),
+    }
+
+    Ok(())
+}
@@ -0,0 +1,118 @@
+// @generated
+//! ibm-logo.ch8, recompiled to Rust. Generated by the recompiler crate, so don't edit it by hand.
+
+use machine::{Error, Instruction, Machine, PROGRAM_START};
+
+/// The ROM, to load with [`Machine::from_rom`].
+pub const ROM: &[u8] = &[
+    0x00, 0xE0, 0xA2, 0x2A, 0x60, 0x0C, 0x61, 0x08, 0xD0, 0x1F, 0x70, 0x09,
+    0xA2, 0x39, 0xD0, 0x1F, 0xA2, 0x48, 0x70, 0x08, 0xD0, 0x1F, 0x70, 0x04,
+    0xA2, 0x57, 0xD0, 0x1F, 0x70, 0x08, 0xA2, 0x66, 0xD0, 0x1F, 0x70, 0x08,
+    0xA2, 0x75, 0xD0, 0x1F, 0x12, 0x28, 0xFF, 0x00, 0xFF, 0x00, 0x3C, 0x00,
+    0x3C, 0x00, 0x3C, 0x00, 0x3C, 0x00, 0xFF, 0x00, 0xFF, 0xFF, 0x00, 0xFF,
+    0x00, 0x38, 0x00, 0x3F, 0x00, 0x3F, 0x00, 0x38, 0x00, 0xFF, 0x00, 0xFF,
+    0x80, 0x00, 0xE0, 0x00, 0xE0, 0x00, 0x80, 0x00, 0x80, 0x00, 0xE0, 0x00,
+    0xE0, 0x00, 0x80, 0xF8, 0x00, 0xFC, 0x00, 0x3E, 0x00, 0x3F, 0x00, 0x3B,
+    0x00, 0x39, 0x00, 0xF8, 0x00, 0xF8, 0x03, 0x00, 0x07, 0x00, 0x0F, 0x00,
+    0xBF, 0x00, 0xFB, 0x00, 0xF3, 0x00, 0xE3, 0x00, 0x43, 0xE5, 0x05, 0xE2,
+    0x00, 0x85, 0x07, 0x81, 0x01, 0x80, 0x02, 0x80, 0x02, 0xE6, 0x02, 0xE7,
+];
+
+type BlockFn = fn(&mut Machine) -> Result<(), Error>;
+
+/// Runs one 60 Hz frame like [`Machine::run_frame`], using the compiled blocks
+/// wherever the code is unchanged from the ROM and the interpreter everywhere else.
+pub fn run_frame(m: &mut Machine, cycles: usize) -> Result<(), Error> {
+    m.decr_timers();
+
+    let mut remaining = cycles;
+    while remaining > 0 {
+        match compiled_block(m) {
+            // A block only runs if it fits in the frame, so that frames end on the
+            // same instruction as they do in the interpreter
+            Some((len, run)) if len <= remaining => {
+                run(m)?;
+                remaining -= len;
+            }
+            _ => {
+                m.cycle()?;
+                remaining -= 1;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns the number of instructions in the block at the PC and the function
+/// running it, unless the ROM has written over that code since it was loaded.
+fn compiled_block(m: &Machine) -> Option<(usize, BlockFn)> {
+    let (len, run): (usize, BlockFn) = match m.pc {
+        0x200 => (21, block_200),
+        0x228 => (1, block_228),
+        _ => return None,
+    };
+
+    let start = m.pc as usize;
+    let code = &ROM[start - PROGRAM_START as usize..][..len * 2];
+    (m.memory[start..start + len * 2] == *code).then_some((len, run))
+}
+
+fn block_200(m: &mut Machine) -> Result<(), Error> {
+    // 200: 00E0
+    m.pc = 0x202;
+    m.execute(Instruction::ClearScreen)?;
+    // 202: A22A
+    m.index = 0x22A;
+    // 204: 600C
+    m.registers[0x0] = 0x0C;
+    // 206: 6108
+    m.registers[0x1] = 0x08;
+    // 208: D01F
+    m.pc = 0x20A;
+    m.execute(Instruction::Draw { x: 0, y: 1, height: 15 })?;
+    // 20A: 7009
+    m.registers[0x0] = m.registers[0x0].wrapping_add(0x09);
+    // 20C: A239
+    m.index = 0x239;
+    // 20E: D01F
+    m.pc = 0x210;
+    m.execute(Instruction::Draw { x: 0, y: 1, height: 15 })?;
+    // 210: A248
+    m.index = 0x248;
+    // 212: 7008
+    m.registers[0x0] = m.registers[0x0].wrapping_add(0x08);
+    // 214: D01F
+    m.pc = 0x216;
+    m.execute(Instruction::Draw { x: 0, y: 1, height: 15 })?;
+    // 216: 7004
+    m.registers[0x0] = m.registers[0x0].wrapping_add(0x04);
+    // 218: A257
+    m.index = 0x257;
+    // 21A: D01F
+    m.pc = 0x21C;
+    m.execute(Instruction::Draw { x: 0, y: 1, height: 15 })?;
+    // 21C: 7008
+    m.registers[0x0] = m.registers[0x0].wrapping_add(0x08);
+    // 21E: A266
+    m.index = 0x266;
+    // 220: D01F
+    m.pc = 0x222;
+    m.execute(Instruction::Draw { x: 0, y: 1, height: 15 })?;
+    // 222: 7008
+    m.registers[0x0] = m.registers[0x0].wrapping_add(0x08);
+    // 224: A275
+    m.index = 0x275;
+    // 226: D01F
+    m.pc = 0x228;
+    m.execute(Instruction::Draw { x: 0, y: 1, height: 15 })?;
+    // 228: 1228
+    m.pc = 0x228;
+    Ok(())
+}
+
+fn block_228(m: &mut Machine) -> Result<(), Error> {
+    // 228: 1228
+    m.pc = 0x228;
+    Ok(())
+}
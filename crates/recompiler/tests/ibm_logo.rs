@@ -0,0 +1,71 @@
+// The generated module is compared byte for byte with the recompiler's output
+#[rustfmt::skip]
+#[path = "generated/ibm_logo.rs"]
+mod ibm_logo;
+
+use machine::{Drivers, Instruction, Machine, Quirks, PROGRAM_START};
+
+const IBM_LOGO: &[u8] = include_bytes!("../../../roms/ibm-logo.ch8");
+
+#[test]
+fn generated_module_is_up_to_date() {
+    let source = recompiler::recompile(IBM_LOGO, "ibm-logo.ch8").unwrap();
+    assert!(
+        source == include_str!("generated/ibm_logo.rs"),
+        "Regenerate it with `cargo run -p recompiler -- roms/ibm-logo.ch8 -o crates/recompiler/tests/generated/ibm_logo.rs`"
+    );
+}
+
+#[test]
+fn matches_interpreter() {
+    let mut recompiled =
+        Machine::from_rom(ibm_logo::ROM, Quirks::modern_chip8(), Drivers::noop()).unwrap();
+    let mut interpreted =
+        Machine::from_rom(IBM_LOGO, Quirks::modern_chip8(), Drivers::noop()).unwrap();
+
+    // Both with frames long enough to run the whole logo as one block, and ones
+    // too short for it, which fall back to the interpreter
+    for cycles in [25, 7, 1, 11, 30].repeat(4) {
+        ibm_logo::run_frame(&mut recompiled, cycles).unwrap();
+        interpreted.run_frame(cycles).unwrap();
+
+        assert_eq!(recompiled.display, interpreted.display);
+        assert_eq!(recompiled.pc, interpreted.pc);
+        assert_eq!(recompiled.index, interpreted.index);
+        assert_eq!(recompiled.registers, interpreted.registers);
+    }
+}
+
+#[test]
+fn falls_back_for_overwritten_code() {
+    let mut machine =
+        Machine::from_rom(ibm_logo::ROM, Quirks::modern_chip8(), Drivers::noop()).unwrap();
+    // Replace the first instruction with 6B01, which the compiled block doesn't do
+    machine.memory[PROGRAM_START as usize..][..2].copy_from_slice(&[0x6B, 0x01]);
+
+    ibm_logo::run_frame(&mut machine, 1).unwrap();
+    assert_eq!(machine.registers[0xB], 1);
+}
+
+#[test]
+fn discovers_reachable_blocks() {
+    let blocks = recompiler::discover(&[
+        0x30, 0x00, // 200: Skip if V0 == 0
+        0x22, 0x0A, // 202: Call 0x20A
+        0xB2, 0x00, // 204: Jump to 0x200 + V0
+        0xFF, 0xFF, // 206: Never reached
+        0x00, 0x00, // 208: Never reached
+        0x00, 0xEE, // 20A: Return
+    ]);
+
+    let starts: Vec<_> = blocks.iter().map(|block| block.start).collect();
+    assert_eq!(starts, [0x200, 0x202, 0x204, 0x20A]);
+    assert_eq!(blocks[0].instructions.len(), 1);
+    assert_eq!(
+        blocks[2].instructions,
+        [Instruction::JumpWithOffset {
+            address: 0x200,
+            x: 2
+        }]
+    );
+}
@@ -0,0 +1,160 @@
+use std::time::{Duration, Instant};
+
+use crossterm::event::{KeyCode, KeyEvent, KeyEventKind};
+
+/// The keypad is mapped to the 4x4 block of keys starting at `1`, as in the viewer.
+pub fn chip8_key(code: KeyCode) -> Option<u8> {
+    let KeyCode::Char(c) = code else {
+        return None;
+    };
+
+    Some(match c.to_ascii_lowercase() {
+        '1' => 0x1,
+        '2' => 0x2,
+        '3' => 0x3,
+        '4' => 0xC,
+        'q' => 0x4,
+        'w' => 0x5,
+        'e' => 0x6,
+        'r' => 0xD,
+        'a' => 0x7,
+        's' => 0x8,
+        'd' => 0x9,
+        'f' => 0xE,
+        'z' => 0xA,
+        'x' => 0x0,
+        'c' => 0xB,
+        'v' => 0xF,
+        _ => return None,
+    })
+}
+
+/// Tracks which CHIP-8 keys are held down.
+///
+/// Most terminals only send key presses (and repeats while a key is held), never
+/// releases, so a key counts as held until `release_timeout` has passed without
+/// another press. Terminals that support the kitty keyboard protocol do report
+/// releases, which are used instead when available.
+///
+/// The time is passed in, rather than read from the clock, so that the timeout
+/// can be tested.
+pub struct Keypad {
+    pub release_timeout: Duration,
+    /// When each key was last pressed, or `None` if it's been released.
+    pressed_at: [Option<Instant>; 16],
+}
+
+impl Keypad {
+    pub fn new(release_timeout: Duration) -> Self {
+        Self {
+            release_timeout,
+            pressed_at: [None; 16],
+        }
+    }
+
+    /// Updates the keypad with a key event that arrived at `now`.
+    pub fn handle(&mut self, event: KeyEvent, now: Instant) {
+        let Some(key) = chip8_key(event.code) else {
+            return;
+        };
+
+        self.pressed_at[key as usize] = match event.kind {
+            KeyEventKind::Press | KeyEventKind::Repeat => Some(now),
+            KeyEventKind::Release => None,
+        };
+    }
+
+    /// Returns the state of the keypad at `now`, as a bitmask with bit `n` set if
+    /// key `n` is held down.
+    pub fn state(&self, now: Instant) -> u16 {
+        self.pressed_at
+            .iter()
+            .enumerate()
+            .filter(|(_, pressed_at)| {
+                pressed_at
+                    .is_some_and(|at| now.saturating_duration_since(at) < self.release_timeout)
+            })
+            .fold(0, |keypad, (key, _)| keypad | 1 << key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crossterm::event::KeyModifiers;
+
+    use super::*;
+
+    const TIMEOUT: Duration = Duration::from_millis(150);
+
+    fn event(c: char, kind: KeyEventKind) -> KeyEvent {
+        KeyEvent::new_with_kind(KeyCode::Char(c), KeyModifiers::NONE, kind)
+    }
+
+    #[test]
+    fn maps_the_qwerty_block() {
+        assert_eq!(chip8_key(KeyCode::Char('1')), Some(0x1));
+        assert_eq!(chip8_key(KeyCode::Char('V')), Some(0xF));
+        assert_eq!(chip8_key(KeyCode::Char('x')), Some(0x0));
+        assert_eq!(chip8_key(KeyCode::Char('5')), None);
+        assert_eq!(chip8_key(KeyCode::Enter), None);
+    }
+
+    #[test]
+    fn press_holds_until_the_timeout() {
+        let start = Instant::now();
+        let mut keypad = Keypad::new(TIMEOUT);
+        assert_eq!(keypad.state(start), 0);
+
+        // W is key 5
+        keypad.handle(event('w', KeyEventKind::Press), start);
+        assert_eq!(keypad.state(start), 1 << 5);
+        assert_eq!(keypad.state(start + TIMEOUT / 2), 1 << 5);
+        assert_eq!(keypad.state(start + TIMEOUT), 0);
+    }
+
+    #[test]
+    fn repeats_keep_the_key_held() {
+        let start = Instant::now();
+        let mut keypad = Keypad::new(TIMEOUT);
+        keypad.handle(event('w', KeyEventKind::Press), start);
+        let repeat = start + TIMEOUT * 3 / 4;
+        keypad.handle(event('w', KeyEventKind::Repeat), repeat);
+
+        assert_eq!(keypad.state(start + TIMEOUT), 1 << 5);
+        assert_eq!(keypad.state(repeat + TIMEOUT), 0);
+    }
+
+    #[test]
+    fn holds_several_keys() {
+        let start = Instant::now();
+        let mut keypad = Keypad::new(TIMEOUT);
+        keypad.handle(event('1', KeyEventKind::Press), start);
+        keypad.handle(event('v', KeyEventKind::Press), start + TIMEOUT / 2);
+
+        assert_eq!(keypad.state(start + TIMEOUT / 2), 1 << 0x1 | 1 << 0xF);
+        assert_eq!(keypad.state(start + TIMEOUT), 1 << 0xF);
+    }
+
+    #[test]
+    fn releases_end_the_hold() {
+        // With the kitty keyboard protocol, keys stay held until they're released
+        let start = Instant::now();
+        let mut keypad = Keypad::new(Duration::MAX);
+        keypad.handle(event('w', KeyEventKind::Press), start);
+        assert_eq!(keypad.state(start + Duration::from_secs(60)), 1 << 5);
+
+        keypad.handle(
+            event('w', KeyEventKind::Release),
+            start + Duration::from_secs(61),
+        );
+        assert_eq!(keypad.state(start + Duration::from_secs(61)), 0);
+    }
+
+    #[test]
+    fn ignores_other_keys() {
+        let start = Instant::now();
+        let mut keypad = Keypad::new(TIMEOUT);
+        keypad.handle(event('p', KeyEventKind::Press), start);
+        assert_eq!(keypad.state(start), 0);
+    }
+}
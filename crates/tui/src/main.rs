@@ -0,0 +1,157 @@
+mod input;
+mod render;
+
+use std::fs;
+use std::io::{self, Write};
+use std::panic;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use clap::Parser;
+use color_eyre::{eyre::WrapErr, Result};
+use crossterm::{
+    cursor::{Hide, Show},
+    event::{
+        self, Event, KeyCode, KeyEventKind, KeyModifiers, KeyboardEnhancementFlags,
+        PopKeyboardEnhancementFlags, PushKeyboardEnhancementFlags,
+    },
+    execute,
+    terminal::{
+        self, disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen,
+    },
+};
+use machine::{Damage, Drivers, Machine, Quirks, CYCLES_PER_SECOND, DISPLAY_HEIGHT, DISPLAY_WIDTH};
+
+use input::Keypad;
+
+const FRAMES_PER_SECOND: u32 = 60;
+
+const HELP: &str = "Esc to quit";
+/// How long an error stays in the status line before it goes back to the help.
+const ERROR_TIME: Duration = Duration::from_secs(3);
+
+#[derive(Parser)]
+#[command(version, about, long_about = None)]
+struct Cli {
+    /// The path to the CHIP-8 ROM.
+    path: PathBuf,
+
+    /// The number of cycles to execute per second.
+    #[arg(long, default_value_t = CYCLES_PER_SECOND)]
+    cycles_per_second: usize,
+
+    /// How long a key counts as held after the terminal last reported it, in
+    /// milliseconds. Only used if the terminal can't report key releases.
+    #[arg(long, default_value_t = 150)]
+    key_timeout: u64,
+}
+
+fn main() -> Result<()> {
+    color_eyre::install()?;
+
+    let cli = Cli::parse();
+
+    let rom = fs::read(&cli.path).wrap_err("Failed to read ROM")?;
+    let mut keypad = Keypad::new(Duration::from_millis(cli.key_timeout));
+    let mut machine = Machine::from_rom(&rom, Quirks::modern_chip8(), Drivers::noop())
+        .wrap_err("Failed to load ROM")?;
+
+    let mut stdout = io::stdout();
+    enable_raw_mode()?;
+    execute!(stdout, EnterAlternateScreen, Hide)?;
+
+    // Terminals using the kitty keyboard protocol can report key releases, so the
+    // keypad doesn't have to guess when keys are let go
+    let reports_releases = terminal::supports_keyboard_enhancement().unwrap_or(false);
+    if reports_releases {
+        keypad.release_timeout = Duration::MAX;
+        execute!(
+            stdout,
+            PushKeyboardEnhancementFlags(KeyboardEnhancementFlags::REPORT_EVENT_TYPES)
+        )?;
+    }
+
+    // Put the terminal back before the panic message is printed, or it's lost with
+    // the alternate screen and the shell is left in raw mode
+    let hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        let _ = restore_terminal(&mut io::stdout(), reports_releases);
+        hook(info);
+    }));
+
+    let result = run(&mut machine, &mut keypad, &cli, &mut stdout);
+
+    restore_terminal(&mut stdout, reports_releases)?;
+
+    result
+}
+
+/// Undoes the terminal setup in `main`.
+fn restore_terminal(out: &mut impl Write, reports_releases: bool) -> io::Result<()> {
+    if reports_releases {
+        execute!(out, PopKeyboardEnhancementFlags)?;
+    }
+    execute!(out, Show, LeaveAlternateScreen)?;
+    disable_raw_mode()
+}
+
+fn run(machine: &mut Machine, keypad: &mut Keypad, cli: &Cli, out: &mut impl Write) -> Result<()> {
+    let frame_time = Duration::from_secs(1) / FRAMES_PER_SECOND;
+    let cycles_per_frame = (cli.cycles_per_second / FRAMES_PER_SECOND as usize).max(1);
+    let pane_column = render::pane_column(&machine.display);
+
+    // The last error, and when it happened
+    let mut error: Option<(String, Instant)> = None;
+    let mut redraw_all = true;
+    loop {
+        let frame_start = Instant::now();
+
+        machine.keypad = keypad.state(frame_start);
+        if let Err(err) = machine.run_frame(cycles_per_frame) {
+            error = Some((err.to_string(), frame_start));
+        }
+        let status = match &error {
+            Some((message, at)) if at.elapsed() < ERROR_TIME => message,
+            _ => HELP,
+        };
+
+        let frame = machine.take_frame();
+        let mut damage = frame.damage;
+        if redraw_all {
+            render::draw_border(out, DISPLAY_WIDTH, DISPLAY_HEIGHT)?;
+            damage = Damage::full(DISPLAY_WIDTH, DISPLAY_HEIGHT);
+            redraw_all = false;
+        }
+        render::draw_display(out, frame.display, &damage)?;
+        render::draw_registers(out, machine, pane_column, status)?;
+        out.flush()?;
+
+        // Handle input until it's time for the next frame
+        while let Some(remaining) =
+            (frame_start + frame_time).checked_duration_since(Instant::now())
+        {
+            if !event::poll(remaining)? {
+                break;
+            }
+
+            match event::read()? {
+                Event::Key(key)
+                    if key.kind != KeyEventKind::Release && is_quit(key.code, key.modifiers) =>
+                {
+                    return Ok(())
+                }
+                Event::Key(key) => keypad.handle(key, Instant::now()),
+                Event::Resize(..) => {
+                    execute!(out, terminal::Clear(terminal::ClearType::All))?;
+                    redraw_all = true;
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+fn is_quit(code: KeyCode, modifiers: KeyModifiers) -> bool {
+    code == KeyCode::Esc
+        || (modifiers.contains(KeyModifiers::CONTROL) && code == KeyCode::Char('c'))
+}
@@ -0,0 +1,106 @@
+use std::io::{self, Write};
+
+use crossterm::{cursor::MoveTo, queue, style::Print};
+use machine::{Damage, Framebuffer, Machine, Row};
+
+/// The width of the register pane, in characters.
+const PANE_WIDTH: usize = 32;
+
+/// The column where the register pane starts, to the right of the display's border.
+pub fn pane_column<R: Row, const HEIGHT: usize>(display: &Framebuffer<R, HEIGHT>) -> u16 {
+    display.width() as u16 + 3
+}
+
+/// Draws the border around the display, which is drawn inside it starting at (1, 1).
+pub fn draw_border(out: &mut impl Write, width: usize, height: usize) -> io::Result<()> {
+    let horizontal = "─".repeat(width);
+    queue!(out, MoveTo(0, 0), Print(format!("┌{horizontal}┐")))?;
+    for row in 1..=height as u16 / 2 {
+        queue!(
+            out,
+            MoveTo(0, row),
+            Print("│"),
+            MoveTo(width as u16 + 1, row),
+            Print("│")
+        )?;
+    }
+    queue!(
+        out,
+        MoveTo(0, height as u16 / 2 + 1),
+        Print(format!("└{horizontal}┘"))
+    )
+}
+
+/// Redraws the damaged parts of the display, using half-block characters so that
+/// each character cell covers two rows of pixels.
+pub fn draw_display<R: Row, const HEIGHT: usize>(
+    out: &mut impl Write,
+    display: &Framebuffer<R, HEIGHT>,
+    damage: &Damage,
+) -> io::Result<()> {
+    for cell_row in 0..HEIGHT / 2 {
+        let (top, bottom) = (cell_row * 2, cell_row * 2 + 1);
+        if !damage.contains_row(top) && !damage.contains_row(bottom) {
+            continue;
+        }
+
+        let line: String = (0..display.width())
+            .map(|x| match (display.get(x, top), display.get(x, bottom)) {
+                (false, false) => ' ',
+                (true, false) => '▀',
+                (false, true) => '▄',
+                (true, true) => '█',
+            })
+            .collect();
+        queue!(out, MoveTo(1, cell_row as u16 + 1), Print(line))?;
+    }
+
+    Ok(())
+}
+
+/// Draws the registers, timers and stack in a pane starting at `column`.
+pub fn draw_registers(
+    out: &mut impl Write,
+    machine: &Machine,
+    column: u16,
+    status: &str,
+) -> io::Result<()> {
+    let mut lines = vec![
+        format!("PC {:03X}  I {:03X}", machine.pc, machine.index),
+        format!("DT {:02X}   ST {:02X}", machine.dt, machine.st),
+        String::new(),
+    ];
+    for (i, values) in machine.registers.chunks(2).enumerate() {
+        lines.push(format!(
+            "V{:X} {:02X}   V{:X} {:02X}",
+            i * 2,
+            values[0],
+            i * 2 + 1,
+            values[1]
+        ));
+    }
+    lines.push(String::new());
+    lines.push(format!(
+        "Stack {}",
+        machine
+            .stack
+            .iter()
+            .rev()
+            .take(4)
+            .map(|addr| format!("{addr:03X}"))
+            .collect::<Vec<_>>()
+            .join(" ")
+    ));
+    lines.push(status.to_owned());
+
+    for (row, line) in lines.iter().enumerate() {
+        // Pad each line so that it overwrites whatever was there last frame
+        queue!(
+            out,
+            MoveTo(column, row as u16),
+            Print(format!("{line:<PANE_WIDTH$.PANE_WIDTH$}"))
+        )?;
+    }
+
+    Ok(())
+}
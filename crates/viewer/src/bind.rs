@@ -0,0 +1,93 @@
+use macroquad::prelude::*;
+
+use crate::keys::{key_name, KeyMap, KEYPAD_ORDER};
+
+pub enum BindingOutcome {
+    Done,
+    Cancelled,
+}
+
+/// A screen that walks through the keypad, asking for the host keys to bind to
+/// each CHIP-8 key in turn.
+///
+/// Every key pressed is added to the current CHIP-8 key's bindings. Enter moves on
+/// to the next key (keeping the old bindings if nothing was pressed), Backspace
+/// clears the keys pressed so far, and Escape cancels the whole thing.
+pub struct BindingScreen {
+    key_map: KeyMap,
+    position: usize,
+    pending: Vec<KeyCode>,
+}
+
+impl BindingScreen {
+    pub fn new(key_map: KeyMap) -> Self {
+        Self {
+            key_map,
+            position: 0,
+            pending: Vec::new(),
+        }
+    }
+
+    /// The key map, including the bindings made so far.
+    pub fn key_map(&self) -> &KeyMap {
+        &self.key_map
+    }
+
+    pub fn update(&mut self) -> Option<BindingOutcome> {
+        match get_last_key_pressed()? {
+            KeyCode::Escape => return Some(BindingOutcome::Cancelled),
+            KeyCode::Backspace => self.pending.clear(),
+            KeyCode::Enter => {
+                let chip8_key = KEYPAD_ORDER[self.position] as usize;
+                if !self.pending.is_empty() {
+                    self.key_map.keys[chip8_key] = std::mem::take(&mut self.pending);
+                }
+
+                self.position += 1;
+                if self.position == KEYPAD_ORDER.len() {
+                    return Some(BindingOutcome::Done);
+                }
+            }
+            key if key_name(key).is_some() && !self.pending.contains(&key) => {
+                self.pending.push(key);
+            }
+            _ => {}
+        }
+
+        None
+    }
+
+    pub fn draw(&self, foreground: Color) {
+        let chip8_key = KEYPAD_ORDER[self.position];
+        let font_size = screen_height() / 12.;
+
+        let current = if self.pending.is_empty() {
+            &self.key_map.keys[chip8_key as usize]
+        } else {
+            &self.pending
+        };
+        let current = current
+            .iter()
+            .filter_map(|key| key_name(*key))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        let lines = [
+            format!(
+                "Press the keys for CHIP-8 key {chip8_key:X} ({}/16)",
+                self.position + 1
+            ),
+            format!("Bound to: {current}"),
+            "Enter: next key, Backspace: clear, Escape: cancel".to_owned(),
+        ];
+        for (i, line) in lines.iter().enumerate() {
+            draw_text(
+                line,
+                font_size / 2.,
+                font_size * (i as f32 + 1.5),
+                font_size,
+                foreground,
+            );
+        }
+    }
+}
@@ -0,0 +1,709 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use clap::ValueEnum;
+use color_eyre::{
+    eyre::{eyre, WrapErr},
+    Result,
+};
+use machine::{
+    fonts::{self, LARGE_FONT_SIZE, SMALL_FONT_SIZE},
+    Palette, Quirks, Rgb, CYCLES_PER_SECOND as DEFAULT_CYCLES_PER_SECOND,
+};
+use macroquad::prelude::Color;
+use serde::Deserialize;
+use toml_edit::{value, Array, DocumentMut, Item, Table};
+
+use crate::keys::{key_name, parse_key_code, KeyMap, Layout};
+use crate::Cli;
+
+pub const DEFAULT_SCALE: u32 = 10;
+/// The largest scale, which keeps the window a size that screens can show.
+pub const MAX_SCALE: u32 = 100;
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum QuirkPreset {
+    #[default]
+    Modern,
+    CosmacVip,
+    Superchip,
+}
+
+impl QuirkPreset {
+    pub fn quirks(self) -> Quirks {
+        match self {
+            Self::Modern => Quirks::modern_chip8(),
+            Self::CosmacVip => Quirks::cosmac_vip(),
+            Self::Superchip => Quirks::superchip(),
+        }
+    }
+
+    /// The fonts of the interpreter that the preset emulates.
+    pub fn fonts(self) -> (FontPreset, LargeFontPreset) {
+        match self {
+            Self::Modern => (FontPreset::Schip, LargeFontPreset::Octo),
+            Self::CosmacVip => (FontPreset::CosmacVip, LargeFontPreset::Octo),
+            Self::Superchip => (FontPreset::Schip, LargeFontPreset::Schip),
+        }
+    }
+}
+
+/// The system the ROM was written for.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum Platform {
+    #[default]
+    #[serde(rename = "chip-8")]
+    #[value(name = "chip-8")]
+    Chip8,
+    /// CHIP-8X, with the VP-590 color board, loaded at 0x300.
+    #[serde(rename = "chip-8x")]
+    #[value(name = "chip-8x")]
+    Chip8X,
+}
+
+impl Platform {
+    pub fn platform(self) -> machine::Platform {
+        match self {
+            Self::Chip8 => machine::Platform::Chip8,
+            Self::Chip8X => machine::Platform::Chip8X,
+        }
+    }
+}
+
+/// How fast instructions run.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum Timing {
+    /// Every instruction takes the same time, at `cycles-per-second`.
+    #[default]
+    Flat,
+    /// Every instruction takes as long as it did on the COSMAC VIP.
+    CosmacVip,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum FontPreset {
+    CosmacVip,
+    #[serde(rename = "dream-6800")]
+    #[value(name = "dream-6800")]
+    Dream6800,
+    #[serde(rename = "eti-660")]
+    #[value(name = "eti-660")]
+    Eti660,
+    FishNChips,
+    Schip,
+}
+
+impl FontPreset {
+    pub fn font(self) -> [u8; SMALL_FONT_SIZE] {
+        match self {
+            Self::CosmacVip => fonts::COSMAC_VIP,
+            Self::Dream6800 => fonts::DREAM_6800,
+            Self::Eti660 => fonts::ETI_660,
+            Self::FishNChips => fonts::FISH_N_CHIPS,
+            Self::Schip => fonts::SCHIP,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum LargeFontPreset {
+    Schip,
+    Octo,
+}
+
+impl LargeFontPreset {
+    pub fn font(self) -> [u8; LARGE_FONT_SIZE] {
+        match self {
+            Self::Schip => fonts::SCHIP_LARGE,
+            Self::Octo => fonts::OCTO_LARGE,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum PalettePreset {
+    #[default]
+    Classic,
+    Amber,
+    GreenPhosphor,
+    Lcd,
+}
+
+impl PalettePreset {
+    pub fn palette(self) -> Palette {
+        match self {
+            Self::Classic => Palette::CLASSIC,
+            Self::Amber => Palette::AMBER,
+            Self::GreenPhosphor => Palette::GREEN_PHOSPHOR,
+            Self::Lcd => Palette::LCD,
+        }
+    }
+}
+
+/// The contents of a config file. Every field is optional, so that a per-ROM
+/// override file only needs to contain the settings it changes.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Config {
+    pub cycles_per_second: Option<usize>,
+    pub timing: Option<Timing>,
+    pub platform: Option<Platform>,
+    pub quirks: Option<QuirkPreset>,
+    /// Where the ROM is loaded, e.g. `0x600` for ETI-660 programs.
+    pub load_address: Option<u16>,
+    /// Defaults to the font of the quirk preset's interpreter.
+    pub font: Option<FontPreset>,
+    pub large_font: Option<LargeFontPreset>,
+    /// A file holding a small font, optionally followed by a large font,
+    /// overriding `font` and `large-font`.
+    pub font_file: Option<PathBuf>,
+    /// Runs 0NNN machine code subroutines on an emulated RCA 1802.
+    pub cdp1802: Option<bool>,
+    /// Keeps the registers, call stack and display in memory like the VIP did.
+    pub vip_memory_map: Option<bool>,
+    pub scale: Option<u32>,
+    pub palette: Option<PalettePreset>,
+    /// Overrides the palette's colors.
+    pub colors: ColorConfig,
+    pub persistence: Option<f32>,
+    pub layout: Option<Layout>,
+    pub keypad: Option<bool>,
+    /// Maps CHIP-8 keys (`"0"` to `"F"`) to host key names, overriding the layout.
+    pub keys: HashMap<String, HostKeys>,
+    /// The layout of CHIP-8X's second keypad, which defaults to the numpad.
+    pub layout2: Option<Layout>,
+    /// Like `keys`, for CHIP-8X's second keypad.
+    pub keys2: HashMap<String, HostKeys>,
+    /// The byte that CHIP-8X's FXFB reads from the input port. Without it, FXFB
+    /// waits forever.
+    pub input_port: Option<u8>,
+}
+
+/// One or more host key names bound to a CHIP-8 key.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+pub enum HostKeys {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl HostKeys {
+    fn names(&self) -> &[String] {
+        match self {
+            Self::One(name) => std::slice::from_ref(name),
+            Self::Many(names) => names,
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ColorConfig {
+    pub foreground: Option<String>,
+    pub background: Option<String>,
+}
+
+impl Config {
+    /// Reads a config file.
+    pub fn load(path: &Path) -> Result<Self> {
+        let contents = fs::read_to_string(path)
+            .wrap_err_with(|| format!("Failed to read config file {}", path.display()))?;
+        toml::from_str(&contents)
+            .wrap_err_with(|| format!("Failed to parse config file {}", path.display()))
+    }
+
+    /// Reads a config file, returning an empty config if it doesn't exist.
+    pub fn load_if_exists(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        Self::load(path)
+    }
+
+    /// Returns the path of the user's config file (e.g. `~/.config/chip8/config.toml`).
+    pub fn default_path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("chip8").join("config.toml"))
+    }
+
+    /// Returns the path of the per-ROM override file, which sits next to the ROM
+    /// (e.g. `roms/pong.toml` for `roms/pong.ch8`).
+    pub fn rom_override_path(rom_path: &Path) -> PathBuf {
+        rom_path.with_extension("toml")
+    }
+
+    /// Layers `other` on top of `self`, with `other` taking precedence.
+    pub fn merge(mut self, other: Self) -> Self {
+        self.cycles_per_second = other.cycles_per_second.or(self.cycles_per_second);
+        self.timing = other.timing.or(self.timing);
+        self.platform = other.platform.or(self.platform);
+        self.quirks = other.quirks.or(self.quirks);
+        self.load_address = other.load_address.or(self.load_address);
+        self.font = other.font.or(self.font);
+        self.large_font = other.large_font.or(self.large_font);
+        self.font_file = other.font_file.or(self.font_file);
+        self.cdp1802 = other.cdp1802.or(self.cdp1802);
+        self.vip_memory_map = other.vip_memory_map.or(self.vip_memory_map);
+        self.scale = other.scale.or(self.scale);
+        self.palette = other.palette.or(self.palette);
+        self.persistence = other.persistence.or(self.persistence);
+        self.colors.foreground = other.colors.foreground.or(self.colors.foreground);
+        self.colors.background = other.colors.background.or(self.colors.background);
+        self.layout = other.layout.or(self.layout);
+        self.keypad = other.keypad.or(self.keypad);
+        self.keys.extend(other.keys);
+        self.layout2 = other.layout2.or(self.layout2);
+        self.keys2.extend(other.keys2);
+        self.input_port = other.input_port.or(self.input_port);
+        self
+    }
+
+    /// Writes `key_map` into the `[keys]` table of the config file at `path`,
+    /// leaving the rest of the file (including comments) as it was.
+    pub fn save_key_map(path: &Path, key_map: &KeyMap) -> Result<()> {
+        let contents = if path.exists() {
+            fs::read_to_string(path)
+                .wrap_err_with(|| format!("Failed to read config file {}", path.display()))?
+        } else {
+            String::new()
+        };
+        let mut document: DocumentMut = contents
+            .parse()
+            .wrap_err_with(|| format!("Failed to parse config file {}", path.display()))?;
+
+        let mut keys = Table::new();
+        for (chip8_key, host_keys) in key_map.keys.iter().enumerate() {
+            let names = host_keys
+                .iter()
+                .filter_map(|key| key_name(*key))
+                .collect::<Array>();
+            keys.insert(&format!("{chip8_key:X}"), value(names));
+        }
+        document.insert("keys", Item::Table(keys));
+
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)
+                .wrap_err_with(|| format!("Failed to create config directory {}", dir.display()))?;
+        }
+        fs::write(path, document.to_string())
+            .wrap_err_with(|| format!("Failed to write config file {}", path.display()))
+    }
+}
+
+/// The final viewer settings, after combining the config files and CLI flags.
+pub struct Settings {
+    pub cycles_per_second: usize,
+    pub timing: Timing,
+    pub platform: Platform,
+    pub quirks: QuirkPreset,
+    pub load_address: u16,
+    pub font: [u8; SMALL_FONT_SIZE],
+    pub large_font: [u8; LARGE_FONT_SIZE],
+    pub cdp1802: bool,
+    pub vip_memory_map: bool,
+    pub scale: u32,
+    pub palette: Palette,
+    pub persistence: f32,
+    pub key_map: KeyMap,
+    /// The bindings for CHIP-8X's second keypad.
+    pub key_map2: KeyMap,
+    pub input_port: Option<u8>,
+    pub keypad: bool,
+    /// The config file that the key bindings screen saves to.
+    pub config_path: Option<PathBuf>,
+}
+
+impl Settings {
+    /// Loads the user's config file and the ROM's override file, then applies the CLI flags.
+    pub fn resolve(cli: &Cli) -> Result<Self> {
+        let config_path = cli.config.clone().or_else(Config::default_path);
+        // Only an explicit --config has to exist
+        let mut config = match (&cli.config, &config_path) {
+            (Some(path), _) => Config::load(path)?,
+            (None, Some(path)) => Config::load_if_exists(path)?,
+            (None, None) => Config::default(),
+        };
+        let rom_override_path = Config::rom_override_path(&cli.path);
+        config = config.merge(Config::load_if_exists(&rom_override_path)?);
+
+        let key_map = resolve_key_map(
+            cli.layout.or(config.layout).unwrap_or_default(),
+            &config.keys,
+        )?;
+        let key_map2 = resolve_key_map(
+            cli.layout2.or(config.layout2).unwrap_or(Layout::Numpad),
+            &config.keys2,
+        )?;
+
+        let mut palette = cli.palette.or(config.palette).unwrap_or_default().palette();
+        if let Some(hex) = cli
+            .foreground
+            .as_ref()
+            .or(config.colors.foreground.as_ref())
+        {
+            palette.foreground = parse_color(hex)?;
+        }
+        if let Some(hex) = cli
+            .background
+            .as_ref()
+            .or(config.colors.background.as_ref())
+        {
+            palette.background = parse_color(hex)?;
+        }
+
+        let scale = cli.scale.or(config.scale).unwrap_or(DEFAULT_SCALE);
+        if !(1..=MAX_SCALE).contains(&scale) {
+            return Err(eyre!("Invalid scale {scale}, expected 1 to {MAX_SCALE}"));
+        }
+
+        let platform = cli.platform.or(config.platform).unwrap_or_default();
+        let quirks = cli.quirks.or(config.quirks).unwrap_or_default();
+        let (default_font, default_large_font) = quirks.fonts();
+        let mut font = cli.font.or(config.font).unwrap_or(default_font).font();
+        let mut large_font = cli
+            .large_font
+            .or(config.large_font)
+            .unwrap_or(default_large_font)
+            .font();
+        if let Some(path) = cli.font_file.as_ref().or(config.font_file.as_ref()) {
+            let bytes = fs::read(path)
+                .wrap_err_with(|| format!("Failed to read font file {}", path.display()))?;
+            (font, large_font) = parse_font_file(&bytes, large_font)
+                .wrap_err_with(|| format!("Invalid font file {}", path.display()))?;
+        }
+
+        Ok(Self {
+            cycles_per_second: cli
+                .cycles_per_second
+                .or(config.cycles_per_second)
+                .unwrap_or(DEFAULT_CYCLES_PER_SECOND),
+            timing: cli.timing.or(config.timing).unwrap_or_default(),
+            platform,
+            quirks,
+            load_address: cli
+                .load_address
+                .or(config.load_address)
+                .unwrap_or(platform.platform().load_address()),
+            font,
+            large_font,
+            cdp1802: cli.cdp1802 || config.cdp1802.unwrap_or(false),
+            vip_memory_map: cli.vip_memory_map || config.vip_memory_map.unwrap_or(false),
+            scale,
+            palette,
+            persistence: cli.persistence.or(config.persistence).unwrap_or(0.0),
+            key_map,
+            key_map2,
+            input_port: cli.input_port.or(config.input_port),
+            keypad: cli.keypad || config.keypad.unwrap_or(false),
+            config_path,
+        })
+    }
+
+    pub fn foreground(&self) -> Color {
+        to_color(self.palette.foreground)
+    }
+
+    pub fn background(&self) -> Color {
+        to_color(self.palette.background)
+    }
+}
+
+pub fn to_color(rgb: Rgb) -> Color {
+    Color::from_rgba(rgb.r, rgb.g, rgb.b, 0xFF)
+}
+
+/// Binds the keys of `layout`, overridden by the CHIP-8 key to host key names
+/// in `keys`.
+fn resolve_key_map(layout: Layout, keys: &HashMap<String, HostKeys>) -> Result<KeyMap> {
+    let mut key_map = KeyMap::from_layout(layout);
+    for (chip8_key, host_keys) in keys {
+        let index = u8::from_str_radix(chip8_key, 16)
+            .ok()
+            .filter(|key| *key < 16)
+            .ok_or_else(|| eyre!("Invalid CHIP-8 key {chip8_key:?}, expected 0 to F"))?;
+        key_map.keys[index as usize] = host_keys
+            .names()
+            .iter()
+            .map(|name| {
+                parse_key_code(name)
+                    .ok_or_else(|| eyre!("Unknown host key {name:?} for CHIP-8 key {chip8_key}"))
+            })
+            .collect::<Result<_>>()?;
+    }
+    Ok(key_map)
+}
+
+/// Splits a font file into a small font and, if it has one, a large font, which
+/// otherwise stays as `large_font`.
+fn parse_font_file(
+    bytes: &[u8],
+    large_font: [u8; LARGE_FONT_SIZE],
+) -> Result<([u8; SMALL_FONT_SIZE], [u8; LARGE_FONT_SIZE])> {
+    let (small, large) = match bytes.len() {
+        SMALL_FONT_SIZE => (bytes, None),
+        len if len == SMALL_FONT_SIZE + LARGE_FONT_SIZE => {
+            let (small, large) = bytes.split_at(SMALL_FONT_SIZE);
+            (small, Some(large))
+        }
+        len => {
+            return Err(eyre!(
+                "Font file is {len} bytes, expected {SMALL_FONT_SIZE} for a small font, or {} for a small font followed by a large one",
+                SMALL_FONT_SIZE + LARGE_FONT_SIZE
+            ))
+        }
+    };
+    Ok((
+        small.try_into().unwrap(),
+        large.map_or(large_font, |large| large.try_into().unwrap()),
+    ))
+}
+
+/// Parses an address in hex, with or without a `0x` prefix.
+pub fn parse_address(hex: &str) -> Result<u16> {
+    let digits = hex.strip_prefix("0x").unwrap_or(hex);
+    u16::from_str_radix(digits, 16)
+        .map_err(|_| eyre!("Invalid address {hex:?}, expected hex like 0x600"))
+}
+
+/// Parses a byte in hex, with or without a `0x` prefix.
+pub fn parse_byte(hex: &str) -> Result<u8> {
+    let digits = hex.strip_prefix("0x").unwrap_or(hex);
+    u8::from_str_radix(digits, 16)
+        .map_err(|_| eyre!("Invalid byte {hex:?}, expected hex like 0x2A"))
+}
+
+/// Parses a color in `#RRGGBB` form.
+pub fn parse_color(hex: &str) -> Result<Rgb> {
+    Rgb::from_hex(hex).ok_or_else(|| eyre!("Invalid color {hex:?}, expected #RRGGBB"))
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+    use macroquad::prelude::KeyCode;
+
+    use super::*;
+
+    fn parse(toml: &str) -> Config {
+        toml::from_str(toml).unwrap()
+    }
+
+    /// A directory of its own for each test that writes files.
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("chip8-viewer-{}-{name}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn resolve(args: &[&str]) -> Result<Settings> {
+        Settings::resolve(&Cli::parse_from(["viewer"].iter().chain(args)))
+    }
+
+    #[test]
+    fn later_layers_take_precedence() {
+        let dir = temp_dir("precedence");
+        let config = dir.join("config.toml");
+        fs::write(
+            &config,
+            "cycles-per-second = 100\nscale = 2\npalette = \"amber\"\n",
+        )
+        .unwrap();
+        let rom = dir.join("game.ch8");
+        fs::write(
+            dir.join("game.toml"),
+            "cycles-per-second = 200\nscale = 3\n",
+        )
+        .unwrap();
+
+        let settings = resolve(&[
+            rom.to_str().unwrap(),
+            "--config",
+            config.to_str().unwrap(),
+            "--scale",
+            "4",
+        ])
+        .unwrap();
+        // The CLI beats the ROM's file, which beats the user's config
+        assert_eq!(settings.scale, 4);
+        assert_eq!(settings.cycles_per_second, 200);
+        assert_eq!(settings.palette, Palette::AMBER);
+        assert_eq!(settings.config_path, Some(config));
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn merge_keeps_unset_fields() {
+        let config = parse("timing = \"cosmac-vip\"\nscale = 2").merge(parse("scale = 3"));
+        assert_eq!(config.timing, Some(Timing::CosmacVip));
+        assert_eq!(config.scale, Some(3));
+    }
+
+    #[test]
+    fn explicit_config_has_to_exist() {
+        let dir = temp_dir("missing");
+        let rom = dir.join("game.ch8");
+        let missing = dir.join("missing.toml");
+        assert!(resolve(&[rom.to_str().unwrap(), "--config", missing.to_str().unwrap()]).is_err());
+
+        // The ROM's file is optional
+        let config = dir.join("config.toml");
+        fs::write(&config, "").unwrap();
+        assert!(resolve(&[rom.to_str().unwrap(), "--config", config.to_str().unwrap()]).is_ok());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn rejects_scales_out_of_range() {
+        let dir = temp_dir("scale");
+        let config = dir.join("config.toml");
+        fs::write(&config, "").unwrap();
+        let rom = dir.join("game.ch8");
+        let with_scale = |scale: &str| {
+            resolve(&[
+                rom.to_str().unwrap(),
+                "--config",
+                config.to_str().unwrap(),
+                "--scale",
+                scale,
+            ])
+        };
+
+        assert!(with_scale("0").is_err());
+        assert!(with_scale("1").is_ok());
+        assert!(with_scale(&MAX_SCALE.to_string()).is_ok());
+        assert!(with_scale(&(MAX_SCALE + 1).to_string()).is_err());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn binds_one_or_many_host_keys() {
+        let config = parse(
+            r#"
+            [keys]
+            0 = "X"
+            a = ["Z", "Kp0"]
+            "#,
+        );
+        let key_map = resolve_key_map(Layout::Qwerty, &config.keys).unwrap();
+        assert_eq!(key_map.keys[0], [KeyCode::X]);
+        assert_eq!(key_map.keys[0xA], [KeyCode::Z, KeyCode::Kp0]);
+        assert_eq!(key_map.keys[1], [KeyCode::Key1]);
+    }
+
+    #[test]
+    fn rejects_invalid_keys() {
+        for toml in [
+            "[keys]\nG = \"X\"",
+            "[keys]\n10 = \"X\"",
+            "[keys]\n0 = \"Nope\"",
+        ] {
+            let config = parse(toml);
+            assert!(
+                resolve_key_map(Layout::Qwerty, &config.keys).is_err(),
+                "{toml}"
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_unknown_fields() {
+        assert!(toml::from_str::<Config>("cycles = 700").is_err());
+        assert!(toml::from_str::<Config>("[colors]\nforground = \"#FFFFFF\"").is_err());
+    }
+
+    #[test]
+    fn splits_font_files() {
+        let large_font = [0xAA; LARGE_FONT_SIZE];
+
+        let (small, large) = parse_font_file(&[1; SMALL_FONT_SIZE], large_font).unwrap();
+        assert_eq!(small, [1; SMALL_FONT_SIZE]);
+        assert_eq!(large, large_font);
+
+        let mut bytes = vec![1; SMALL_FONT_SIZE];
+        bytes.extend([2; LARGE_FONT_SIZE]);
+        let (small, large) = parse_font_file(&bytes, large_font).unwrap();
+        assert_eq!(small, [1; SMALL_FONT_SIZE]);
+        assert_eq!(large, [2; LARGE_FONT_SIZE]);
+
+        assert!(parse_font_file(&[1; SMALL_FONT_SIZE + 1], large_font).is_err());
+        assert!(parse_font_file(&[], large_font).is_err());
+    }
+
+    #[test]
+    fn parses_addresses() {
+        assert_eq!(parse_address("0x600").unwrap(), 0x600);
+        assert_eq!(parse_address("200").unwrap(), 0x200);
+        assert_eq!(parse_address("FFFF").unwrap(), 0xFFFF);
+        assert!(parse_address("0x10000").is_err());
+        assert!(parse_address("0xG00").is_err());
+        assert!(parse_address("").is_err());
+    }
+
+    #[test]
+    fn second_keypad_and_input_port() {
+        let config = parse(
+            r#"
+            layout2 = "hex"
+            input-port = 0x2A
+
+            [keys2]
+            5 = "Kp5"
+            "#,
+        );
+        assert_eq!(config.layout2, Some(Layout::Hex));
+        assert_eq!(config.input_port, Some(0x2A));
+
+        let key_map2 = resolve_key_map(config.layout2.unwrap(), &config.keys2).unwrap();
+        assert_eq!(key_map2.keys[5], [KeyCode::Kp5]);
+        // The rest of the keys come from the layout
+        assert_eq!(key_map2.keys[0xA], [KeyCode::A]);
+    }
+
+    #[test]
+    fn second_keypad_defaults_to_numpad() {
+        let key_map2 = resolve_key_map(Layout::Numpad, &HashMap::new()).unwrap();
+        assert_eq!(key_map2.keys[0], [KeyCode::Kp0]);
+        assert_eq!(key_map2.keys[0xF], [KeyCode::KpDecimal]);
+    }
+
+    #[test]
+    fn merges_second_keypad() {
+        let user = parse(
+            r#"
+            layout2 = "hex"
+            input-port = 1
+
+            [keys2]
+            0 = "Kp0"
+            1 = "Kp1"
+            "#,
+        );
+        let rom = parse(
+            r#"
+            input-port = 2
+
+            [keys2]
+            1 = "Kp9"
+            "#,
+        );
+
+        let config = user.merge(rom);
+        assert_eq!(config.layout2, Some(Layout::Hex));
+        assert_eq!(config.input_port, Some(2));
+        assert!(matches!(&config.keys2["0"], HostKeys::One(name) if name == "Kp0"));
+        assert!(matches!(&config.keys2["1"], HostKeys::One(name) if name == "Kp9"));
+    }
+
+    #[test]
+    fn parses_bytes() {
+        assert_eq!(parse_byte("0x2A").unwrap(), 0x2A);
+        assert_eq!(parse_byte("ff").unwrap(), 0xFF);
+        assert!(parse_byte("0x100").is_err());
+    }
+}
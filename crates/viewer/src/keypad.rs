@@ -0,0 +1,99 @@
+use macroquad::prelude::*;
+
+use crate::keys::KEYPAD_ORDER;
+
+/// The fraction of each key's cell taken up by the gap around it.
+const KEY_GAP: f32 = 0.1;
+
+/// An on-screen 4x4 COSMAC VIP-style keypad, which can be pressed with the mouse or touch.
+///
+/// Keys light up while they're held down (on the keypad or the keyboard), and are
+/// highlighted while the ROM is checking them.
+pub struct OnScreenKeypad {
+    area: Rect,
+}
+
+impl OnScreenKeypad {
+    /// Places the keypad in a square along the right edge of the window.
+    pub fn new() -> Self {
+        let size = f32::min(screen_height(), screen_width() / 3.);
+        Self {
+            area: Rect::new(
+                screen_width() - size,
+                (screen_height() - size) / 2.,
+                size,
+                size,
+            ),
+        }
+    }
+
+    /// The part of the window left over for the display.
+    pub fn remaining_area(&self) -> Rect {
+        Rect::new(0., 0., self.area.x, screen_height())
+    }
+
+    /// Returns the key held down on the keypad with the mouse or touch screen, if any.
+    pub fn update(&self) -> Option<u8> {
+        let mut pointers = touches()
+            .into_iter()
+            .filter(|touch| !matches!(touch.phase, TouchPhase::Ended | TouchPhase::Cancelled))
+            .map(|touch| touch.position);
+        let pointer = pointers.next().or_else(|| {
+            is_mouse_button_down(MouseButton::Left).then(|| Vec2::from(mouse_position()))
+        });
+
+        pointer.and_then(|position| self.key_at(position))
+    }
+
+    /// Draws the keypad, given the keys held down and the keys the ROM has checked
+    /// (both as bitmasks).
+    pub fn draw(&self, keypad: u16, polled_keys: u16, foreground: Color, background: Color) {
+        for (i, chip8_key) in KEYPAD_ORDER.iter().enumerate() {
+            let rect = self.key_rect(i);
+            let pressed = keypad & (1 << chip8_key) != 0;
+            let polled = polled_keys & (1 << chip8_key) != 0;
+
+            let (fill, label) = if pressed {
+                (foreground, background)
+            } else if polled {
+                (
+                    Color::new(foreground.r, foreground.g, foreground.b, 0.35),
+                    foreground,
+                )
+            } else {
+                (background, foreground)
+            };
+            draw_rectangle(rect.x, rect.y, rect.w, rect.h, fill);
+            draw_rectangle_lines(rect.x, rect.y, rect.w, rect.h, 2., foreground);
+
+            let text = format!("{chip8_key:X}");
+            let font_size = rect.h * 0.6;
+            let dimensions = measure_text(&text, None, font_size as u16, 1.);
+            draw_text(
+                &text,
+                rect.x + (rect.w - dimensions.width) / 2.,
+                rect.y + (rect.h + dimensions.offset_y) / 2.,
+                font_size,
+                label,
+            );
+        }
+    }
+
+    /// The rectangle of the `i`th key, in [`KEYPAD_ORDER`].
+    fn key_rect(&self, i: usize) -> Rect {
+        let cell = self.area.w / 4.;
+        let gap = cell * KEY_GAP;
+        Rect::new(
+            self.area.x + (i % 4) as f32 * cell + gap / 2.,
+            self.area.y + (i / 4) as f32 * cell + gap / 2.,
+            cell - gap,
+            cell - gap,
+        )
+    }
+
+    fn key_at(&self, position: Vec2) -> Option<u8> {
+        (0..KEYPAD_ORDER.len())
+            .find(|i| self.key_rect(*i).contains(position))
+            .map(|i| KEYPAD_ORDER[i])
+    }
+}
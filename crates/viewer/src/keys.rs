@@ -0,0 +1,283 @@
+use clap::ValueEnum;
+use macroquad::prelude::{is_key_down, KeyCode};
+use serde::Deserialize;
+
+/// The order of the keys on the COSMAC VIP's hex keypad, left to right and top to bottom.
+#[rustfmt::skip]
+pub const KEYPAD_ORDER: [u8; 16] = [
+    0x1, 0x2, 0x3, 0xC,
+    0x4, 0x5, 0x6, 0xD,
+    0x7, 0x8, 0x9, 0xE,
+    0xA, 0x0, 0xB, 0xF,
+];
+
+/// A named keyboard layout preset. All of the typing layouts put the keypad on
+/// the same physical keys (the 4x4 block starting at `1`), while [`Layout::Hex`]
+/// maps each CHIP-8 key to the host key with the same label. [`Layout::Numpad`]
+/// only uses the numpad, which keeps it clear of the other layouts for CHIP-8X's
+/// second keypad.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum Layout {
+    #[default]
+    Qwerty,
+    Azerty,
+    Qwertz,
+    Dvorak,
+    Hex,
+    Numpad,
+}
+
+impl Layout {
+    /// The 4x4 block of host keys used for the keypad, in [`KEYPAD_ORDER`].
+    #[rustfmt::skip]
+    fn block(self) -> Option<[KeyCode; 16]> {
+        use KeyCode::*;
+
+        Some(match self {
+            Self::Qwerty => [
+                Key1, Key2, Key3, Key4,
+                Q,    W,    E,    R,
+                A,    S,    D,    F,
+                Z,    X,    C,    V,
+            ],
+            Self::Azerty => [
+                Key1, Key2, Key3, Key4,
+                A,    Z,    E,    R,
+                Q,    S,    D,    F,
+                W,    X,    C,    V,
+            ],
+            Self::Qwertz => [
+                Key1, Key2, Key3, Key4,
+                Q,    W,    E,    R,
+                A,    S,    D,    F,
+                Y,    X,    C,    V,
+            ],
+            Self::Dvorak => [
+                Key1,       Key2,  Key3,   Key4,
+                Apostrophe, Comma, Period, P,
+                A,          O,     E,      U,
+                Semicolon,  Q,     J,      K,
+            ],
+            Self::Hex | Self::Numpad => return None,
+        })
+    }
+}
+
+/// The host keys bound to each CHIP-8 key, indexed by the CHIP-8 key value (0x0 to 0xF).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct KeyMap {
+    pub keys: [Vec<KeyCode>; 16],
+}
+
+impl KeyMap {
+    pub fn from_layout(layout: Layout) -> Self {
+        let mut keys: [Vec<KeyCode>; 16] = Default::default();
+
+        if let Some(block) = layout.block() {
+            for (chip8_key, host_key) in KEYPAD_ORDER.iter().zip(block) {
+                keys[*chip8_key as usize] = vec![host_key];
+            }
+        } else if layout == Layout::Numpad {
+            use KeyCode::*;
+            #[rustfmt::skip]
+            let numpad = [
+                Kp0, Kp1, Kp2, Kp3, Kp4, Kp5, Kp6, Kp7,
+                Kp8, Kp9, KpDivide, KpMultiply, KpSubtract, KpAdd, KpEnter, KpDecimal,
+            ];
+            for (chip8_key, host_key) in numpad.into_iter().enumerate() {
+                keys[chip8_key] = vec![host_key];
+            }
+        } else {
+            use KeyCode::*;
+            #[rustfmt::skip]
+            let labelled = [
+                [Key0, Kp0], [Key1, Kp1], [Key2, Kp2], [Key3, Kp3],
+                [Key4, Kp4], [Key5, Kp5], [Key6, Kp6], [Key7, Kp7],
+                [Key8, Kp8], [Key9, Kp9],
+            ];
+            for (chip8_key, host_keys) in labelled.into_iter().enumerate() {
+                keys[chip8_key] = host_keys.to_vec();
+            }
+            for (chip8_key, host_key) in [A, B, C, D, E, F].into_iter().enumerate() {
+                keys[0xA + chip8_key] = vec![host_key];
+            }
+        }
+
+        Self { keys }
+    }
+
+    /// Returns whether any of the host keys bound to `chip8_key` are held down.
+    pub fn is_down(&self, chip8_key: u8) -> bool {
+        self.keys[chip8_key as usize]
+            .iter()
+            .any(|key| is_key_down(*key))
+    }
+
+    /// Returns the state of the keypad, as a bitmask with bit `n` set if key `n`
+    /// has at least one of its host keys held down.
+    pub fn keypad(&self) -> u16 {
+        (0..16)
+            .filter(|chip8_key| self.is_down(*chip8_key))
+            .fold(0, |keypad, chip8_key| keypad | 1 << chip8_key)
+    }
+}
+
+impl Default for KeyMap {
+    fn default() -> Self {
+        Self::from_layout(Layout::default())
+    }
+}
+
+/// Host key names, as used in the config file. Names are matched case-insensitively.
+const KEY_NAMES: &[(&str, KeyCode)] = &[
+    ("0", KeyCode::Key0),
+    ("1", KeyCode::Key1),
+    ("2", KeyCode::Key2),
+    ("3", KeyCode::Key3),
+    ("4", KeyCode::Key4),
+    ("5", KeyCode::Key5),
+    ("6", KeyCode::Key6),
+    ("7", KeyCode::Key7),
+    ("8", KeyCode::Key8),
+    ("9", KeyCode::Key9),
+    ("A", KeyCode::A),
+    ("B", KeyCode::B),
+    ("C", KeyCode::C),
+    ("D", KeyCode::D),
+    ("E", KeyCode::E),
+    ("F", KeyCode::F),
+    ("G", KeyCode::G),
+    ("H", KeyCode::H),
+    ("I", KeyCode::I),
+    ("J", KeyCode::J),
+    ("K", KeyCode::K),
+    ("L", KeyCode::L),
+    ("M", KeyCode::M),
+    ("N", KeyCode::N),
+    ("O", KeyCode::O),
+    ("P", KeyCode::P),
+    ("Q", KeyCode::Q),
+    ("R", KeyCode::R),
+    ("S", KeyCode::S),
+    ("T", KeyCode::T),
+    ("U", KeyCode::U),
+    ("V", KeyCode::V),
+    ("W", KeyCode::W),
+    ("X", KeyCode::X),
+    ("Y", KeyCode::Y),
+    ("Z", KeyCode::Z),
+    (",", KeyCode::Comma),
+    (".", KeyCode::Period),
+    ("/", KeyCode::Slash),
+    (";", KeyCode::Semicolon),
+    ("'", KeyCode::Apostrophe),
+    ("-", KeyCode::Minus),
+    ("=", KeyCode::Equal),
+    ("[", KeyCode::LeftBracket),
+    ("]", KeyCode::RightBracket),
+    ("\\", KeyCode::Backslash),
+    ("`", KeyCode::GraveAccent),
+    ("Space", KeyCode::Space),
+    ("Tab", KeyCode::Tab),
+    ("Backspace", KeyCode::Backspace),
+    ("Enter", KeyCode::Enter),
+    // An alias, after "Enter" so that key_name picks that
+    ("Return", KeyCode::Enter),
+    ("Up", KeyCode::Up),
+    ("Down", KeyCode::Down),
+    ("Left", KeyCode::Left),
+    ("Right", KeyCode::Right),
+    ("Kp0", KeyCode::Kp0),
+    ("Kp1", KeyCode::Kp1),
+    ("Kp2", KeyCode::Kp2),
+    ("Kp3", KeyCode::Kp3),
+    ("Kp4", KeyCode::Kp4),
+    ("Kp5", KeyCode::Kp5),
+    ("Kp6", KeyCode::Kp6),
+    ("Kp7", KeyCode::Kp7),
+    ("Kp8", KeyCode::Kp8),
+    ("Kp9", KeyCode::Kp9),
+    ("KpDecimal", KeyCode::KpDecimal),
+    ("KpDivide", KeyCode::KpDivide),
+    ("KpMultiply", KeyCode::KpMultiply),
+    ("KpSubtract", KeyCode::KpSubtract),
+    ("KpAdd", KeyCode::KpAdd),
+    ("KpEnter", KeyCode::KpEnter),
+];
+
+/// Parses a host key name, as used in the config file (e.g. `"Q"`, `"1"`, `"Kp0"`, `"Space"`).
+pub fn parse_key_code(name: &str) -> Option<KeyCode> {
+    let name = name.trim();
+    KEY_NAMES
+        .iter()
+        .find(|(key_name, _)| key_name.eq_ignore_ascii_case(name))
+        .map(|(_, key)| *key)
+}
+
+/// Returns the config file name of a host key, if it can be bound.
+pub fn key_name(key: KeyCode) -> Option<&'static str> {
+    KEY_NAMES
+        .iter()
+        .find(|(_, key_code)| *key_code == key)
+        .map(|(name, _)| *name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn key_names_round_trip() {
+        for (name, key) in KEY_NAMES {
+            assert_eq!(parse_key_code(name), Some(*key), "{name}");
+            assert_eq!(parse_key_code(&name.to_lowercase()), Some(*key), "{name}");
+            // Aliases name their key by the first name in the table
+            let canonical = key_name(*key).unwrap();
+            assert_eq!(parse_key_code(canonical), Some(*key), "{name}");
+        }
+        assert_eq!(parse_key_code(" Space "), Some(KeyCode::Space));
+        assert_eq!(parse_key_code("Nope"), None);
+    }
+
+    #[test]
+    fn return_is_an_alias_for_enter() {
+        assert_eq!(parse_key_code("Return"), Some(KeyCode::Enter));
+        assert_eq!(key_name(KeyCode::Enter), Some("Enter"));
+    }
+
+    #[test]
+    fn key_names_are_unique() {
+        for (i, (name, _)) in KEY_NAMES.iter().enumerate() {
+            assert!(
+                KEY_NAMES[i + 1..]
+                    .iter()
+                    .all(|(other, _)| !other.eq_ignore_ascii_case(name)),
+                "{name}"
+            );
+        }
+    }
+
+    #[test]
+    fn layouts_bind_every_key_once() {
+        for layout in Layout::value_variants() {
+            if let Some(block) = layout.block() {
+                for (i, key) in block.iter().enumerate() {
+                    assert!(!block[i + 1..].contains(key), "{layout:?} {key:?}");
+                }
+            }
+
+            let key_map = KeyMap::from_layout(*layout);
+            let bound: Vec<KeyCode> = key_map.keys.iter().flatten().copied().collect();
+            for (chip8_key, host_keys) in key_map.keys.iter().enumerate() {
+                assert!(!host_keys.is_empty(), "{layout:?} {chip8_key:X}");
+            }
+            for (i, key) in bound.iter().enumerate() {
+                assert!(!bound[i + 1..].contains(key), "{layout:?} {key:?}");
+                // Every bound key can be saved to the config file and read back
+                let name = key_name(*key).unwrap_or_else(|| panic!("{layout:?} {key:?}"));
+                assert_eq!(parse_key_code(name), Some(*key));
+            }
+        }
+    }
+}
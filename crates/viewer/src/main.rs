@@ -1,23 +1,35 @@
+mod bind;
+mod config;
+mod keypad;
+mod keys;
+mod render;
+
 use std::fs;
 use std::path::PathBuf;
 
 use color_eyre::{eyre::WrapErr, Result};
 
-use machine::{
-    AudioDriver, Drivers, InputDriver, Machine, Quirks,
-    CYCLES_PER_SECOND as DEFAULT_CYCLES_PER_SECOND, DISPLAY_HEIGHT, DISPLAY_WIDTH,
-};
+use machine::{AudioDriver, Drivers, Machine, DISPLAY_HEIGHT, DISPLAY_WIDTH};
 use macroquad::prelude::*;
 
 use clap::Parser;
 
-#[rustfmt::skip]
-const KEY_MAP: [KeyCode; 16] = [
-    KeyCode::Key1, KeyCode::Key2, KeyCode::Key3, KeyCode::Key4,
-    KeyCode::Q, KeyCode::W, KeyCode::E, KeyCode::R,
-    KeyCode::A, KeyCode::S, KeyCode::D, KeyCode::F,
-    KeyCode::Z, KeyCode::X, KeyCode::C, KeyCode::V,
-];
+use bind::{BindingOutcome, BindingScreen};
+use config::{
+    Config, FontPreset, LargeFontPreset, PalettePreset, Platform, QuirkPreset, Settings, Timing,
+};
+use keypad::OnScreenKeypad;
+use keys::Layout;
+use render::DisplayRenderer;
+
+/// The length of a COSMAC VIP frame, in seconds.
+const VIP_FRAME_TIME: f32 = 1.0 / 60.0;
+
+/// The most time the emulator catches up on in one host frame, in seconds.
+/// Anything beyond this after a stall, like a dragged window, is dropped rather
+/// than run in a burst that makes the next frame slower still. Very slow clock
+/// speeds get at least one cycle's worth.
+const MAX_CATCH_UP: f32 = 4.0 * VIP_FRAME_TIME;
 
 #[derive(Parser)]
 #[command(version, about, long_about = None)]
@@ -33,24 +45,110 @@ struct Cli {
     #[arg(long, short, default_value_t = false)]
     show_fps: bool,
 
-    /// The number of cycles to execute per second.
-    #[arg(long, default_value_t = DEFAULT_CYCLES_PER_SECOND)]
-    cycles_per_second: usize,
+    /// The number of cycles to execute per second [default: 700]
+    #[arg(long)]
+    cycles_per_second: Option<usize>,
+
+    /// How long each instruction takes. `cosmac-vip` runs ROMs at their original
+    /// speed, ignoring --cycles-per-second [default: flat]
+    #[arg(long, value_enum)]
+    timing: Option<Timing>,
+
+    /// The system the ROM was written for. `chip-8x` also moves the default load
+    /// address to 0x300 [default: chip-8]
+    #[arg(long, value_enum)]
+    platform: Option<Platform>,
+
+    /// The config file to use, instead of the one in the user's config directory.
+    #[arg(long)]
+    config: Option<PathBuf>,
+
+    /// The quirk preset to emulate [default: modern]
+    #[arg(long, value_enum)]
+    quirks: Option<QuirkPreset>,
+
+    /// The address to load the ROM at, in hex (e.g. 0x600 for ETI-660 programs) [default: 0x200]
+    #[arg(long, value_parser = config::parse_address)]
+    load_address: Option<u16>,
+
+    /// The font that FX29 draws digits with [default: the quirk preset's]
+    #[arg(long, value_enum)]
+    font: Option<FontPreset>,
+
+    /// The 8x10 font that FX30 draws digits with [default: the quirk preset's]
+    #[arg(long, value_enum)]
+    large_font: Option<LargeFontPreset>,
+
+    /// Run 0NNN machine code subroutines on an emulated RCA 1802, for hybrid
+    /// COSMAC VIP programs.
+    #[arg(long, default_value_t = false)]
+    cdp1802: bool,
+
+    /// Keep the registers, call stack and display in memory where the COSMAC VIP
+    /// did, for ROMs that read or write them directly.
+    #[arg(long, default_value_t = false)]
+    vip_memory_map: bool,
+
+    /// A file with a custom 80 byte font, optionally followed by a 160 byte large font
+    #[arg(long)]
+    font_file: Option<PathBuf>,
+
+    /// The size of each CHIP-8 pixel in the initial window, in screen pixels, from 1 to 100 [default: 10]
+    #[arg(long)]
+    scale: Option<u32>,
+
+    /// The color palette to draw the display with [default: classic]
+    #[arg(long, value_enum)]
+    palette: Option<PalettePreset>,
+
+    /// The color of lit pixels, as #RRGGBB, overriding the palette
+    #[arg(long)]
+    foreground: Option<String>,
+
+    /// The color of unlit pixels, as #RRGGBB, overriding the palette
+    #[arg(long)]
+    background: Option<String>,
+
+    /// How much of each pixel's brightness to keep for the next frame after it's turned off,
+    /// from 0 to 1. This reduces the flicker from sprites being erased and redrawn [default: 0]
+    #[arg(long)]
+    persistence: Option<f32>,
+
+    /// The keyboard layout preset to bind the keypad to [default: qwerty]
+    #[arg(long, value_enum)]
+    layout: Option<Layout>,
+
+    /// The keyboard layout preset to bind CHIP-8X's second keypad to [default: numpad]
+    #[arg(long, value_enum)]
+    layout2: Option<Layout>,
+
+    /// The byte that CHIP-8X's FXFB reads from the input port, in hex. Without it,
+    /// FXFB waits forever
+    #[arg(long, value_parser = config::parse_byte)]
+    input_port: Option<u8>,
+
+    /// Show a clickable keypad to the right of the display.
+    #[arg(long, default_value_t = false)]
+    keypad: bool,
 }
 
-#[macroquad::main("CHIP-8 Emulator")]
-async fn main() -> Result<()> {
+fn main() -> Result<()> {
     color_eyre::install()?;
     env_logger::init();
 
     let cli = Cli::parse();
+    let settings = Settings::resolve(&cli)?;
 
-    let rom = fs::read(cli.path).wrap_err("Failed to read ROM")?;
-
-    let mut machine = Machine::from_rom(
-        &rom,
-        Quirks::modern_chip8(),
-        Drivers {
+    let rom = fs::read(&cli.path).wrap_err("Failed to read ROM")?;
+    let machine = Machine::builder(&rom)
+        .platform(settings.platform.platform())
+        .load_address(settings.load_address)
+        .font(settings.font)
+        .large_font(settings.large_font)
+        .vip_memory_map(settings.vip_memory_map)
+        .cdp1802(settings.cdp1802)
+        .quirks(settings.quirks.quirks())
+        .drivers(Drivers {
             audio: AudioDriver {
                 start_beep: || {
                     log::info!("BEEP");
@@ -59,50 +157,85 @@ async fn main() -> Result<()> {
                     log::info!("BEEP STOP");
                 },
             },
-            input: InputDriver {
-                get_key_pressed: || {
-                    KEY_MAP
-                        .iter()
-                        .filter_map(|key| {
-                            if is_key_down(*key) {
-                                Some(match key {
-                                    KeyCode::Key1 => 0x1,
-                                    KeyCode::Key2 => 0x2,
-                                    KeyCode::Key3 => 0x3,
-                                    KeyCode::Key4 => 0xC,
-                                    KeyCode::Q => 0x4,
-                                    KeyCode::W => 0x5,
-                                    KeyCode::E => 0x6,
-                                    KeyCode::R => 0xD,
-                                    KeyCode::A => 0x7,
-                                    KeyCode::S => 0x8,
-                                    KeyCode::D => 0x9,
-                                    KeyCode::F => 0xE,
-                                    KeyCode::Z => 0xA,
-                                    KeyCode::X => 0x0,
-                                    KeyCode::C => 0xB,
-                                    KeyCode::V => 0xF,
-                                    _ => unreachable!(),
-                                })
-                            } else {
-                                None
-                            }
-                        })
-                        .next()
-                },
-            },
-        },
-    );
+        })
+        .build()
+        .wrap_err_with(|| format!("Failed to load ROM {}", cli.path.display()))?;
+
+    // The keypad is a square as tall as the display
+    let keypad_width = if settings.keypad { DISPLAY_HEIGHT } else { 0 };
+    let conf = Conf {
+        window_title: "CHIP-8 Emulator".to_owned(),
+        window_width: ((DISPLAY_WIDTH + keypad_width) as u32 * settings.scale) as i32,
+        window_height: (DISPLAY_HEIGHT as u32 * settings.scale) as i32,
+        ..Default::default()
+    };
+    macroquad::Window::from_config(conf, run(cli, settings, machine));
+
+    Ok(())
+}
+
+async fn run(cli: Cli, settings: Settings, mut machine: Machine) {
+    let mut key_map = settings.key_map.clone();
 
     let mut current_cycle = 1;
     let mut accumulator = 0.0;
-    let cps = cli.cycles_per_second as f32;
+    let cps = settings.cycles_per_second as f32;
+    let max_catch_up = MAX_CATCH_UP.max(1.0 / cps);
+    let mut binding_screen: Option<BindingScreen> = None;
+    let mut show_keypad = settings.keypad;
+    let mut renderer = DisplayRenderer::new(settings.palette, settings.persistence);
     loop {
-        machine.decr_timers();
-        accumulator += get_frame_time();
-        while accumulator >= 1.0 / cps {
-            machine.cycle();
-            accumulator -= 1.0 / cps;
+        if let Some(screen) = &mut binding_screen {
+            match screen.update() {
+                Some(BindingOutcome::Done) => {
+                    save_key_map(&settings, screen.key_map());
+                    key_map = screen.key_map().clone();
+                    binding_screen = None;
+                }
+                Some(BindingOutcome::Cancelled) => binding_screen = None,
+                None => {
+                    clear_background(settings.background());
+                    screen.draw(settings.foreground());
+                    next_frame().await;
+                    continue;
+                }
+            }
+        } else if is_key_released(KeyCode::F1) {
+            binding_screen = Some(BindingScreen::new(key_map.clone()));
+            next_frame().await;
+            continue;
+        }
+
+        if is_key_released(KeyCode::F2) {
+            show_keypad = !show_keypad;
+        }
+        let keypad = show_keypad.then(OnScreenKeypad::new);
+        let pointer_key = keypad.as_ref().and_then(OnScreenKeypad::update);
+        machine.keypad = key_map.keypad() | pointer_key.map_or(0, |key| 1 << key);
+        machine.keypad2 = settings.key_map2.keypad();
+        if machine.input_port.is_none() {
+            machine.input_port = settings.input_port;
+        }
+
+        accumulator = (accumulator + get_frame_time()).min(max_catch_up);
+        match settings.timing {
+            Timing::Flat => {
+                machine.decr_timers();
+                while accumulator >= 1.0 / cps {
+                    if let Err(err) = machine.cycle() {
+                        log::error!("{err}");
+                    }
+                    accumulator -= 1.0 / cps;
+                }
+            }
+            Timing::CosmacVip => {
+                while accumulator >= VIP_FRAME_TIME {
+                    if let Err(err) = machine.run_vip_frame() {
+                        log::error!("{err}");
+                    }
+                    accumulator -= VIP_FRAME_TIME;
+                }
+            }
         }
         current_cycle += 1;
 
@@ -116,32 +249,45 @@ async fn main() -> Result<()> {
             }
         }
 
-        clear_background(BLACK);
+        clear_background(settings.background());
+
+        let display_area = match &keypad {
+            Some(keypad) => keypad.remaining_area(),
+            None => Rect::new(0., 0., screen_width(), screen_height()),
+        };
+        renderer.update(&mut machine);
+        renderer.draw(display_area);
 
         if cli.show_fps {
             draw_text(format!("FPS: {}", get_fps()).as_str(), 0., 16., 32., RED);
         }
 
-        let scale_factor: f32 = f32::min(
-            screen_width() / DISPLAY_WIDTH as f32,
-            screen_height() / DISPLAY_HEIGHT as f32,
-        );
-        for ((x, y), pixel_on) in machine.display.indexed_iter() {
-            if *pixel_on {
-                draw_rectangle(
-                    x as f32 * scale_factor,
-                    y as f32 * scale_factor,
-                    scale_factor,
-                    scale_factor,
-                    WHITE,
-                );
-            }
+        if let Some(keypad) = &keypad {
+            keypad.draw(
+                machine.keypad,
+                machine.polled_keys,
+                settings.foreground(),
+                settings.background(),
+            );
         }
+        machine.polled_keys = 0;
 
         next_frame().await;
     }
 }
 
+fn save_key_map(settings: &Settings, key_map: &keys::KeyMap) {
+    let Some(path) = &settings.config_path else {
+        log::warn!("No config directory found, key bindings will not be saved");
+        return;
+    };
+
+    match Config::save_key_map(path, key_map) {
+        Ok(()) => log::info!("Saved key bindings to {}", path.display()),
+        Err(err) => log::error!("{err:?}"),
+    }
+}
+
 fn log_debug_info(machine: &Machine, cycle_count: u32) {
     println!("=====BEGIN DEBUG INFO FOR CYCLE {cycle_count}=====");
     dbg!(&machine.registers);
@@ -0,0 +1,103 @@
+use machine::{Machine, Palette, Phosphor, Platform, DISPLAY_HEIGHT, DISPLAY_WIDTH};
+use macroquad::prelude::*;
+
+use crate::config::to_color;
+
+/// Keeps a texture of the display up to date, uploading only the parts that changed.
+pub struct DisplayRenderer {
+    texture: Texture2D,
+    palette: Palette,
+    phosphor: Phosphor,
+}
+
+impl DisplayRenderer {
+    pub fn new(palette: Palette, persistence: f32) -> Self {
+        let texture = Texture2D::from_image(&Image::gen_image_color(
+            DISPLAY_WIDTH as u16,
+            DISPLAY_HEIGHT as u16,
+            to_color(palette.background),
+        ));
+        texture.set_filter(FilterMode::Nearest);
+
+        Self {
+            texture,
+            palette,
+            phosphor: Phosphor::new(persistence),
+        }
+    }
+
+    pub fn update(&mut self, machine: &mut Machine) {
+        // CHIP-8X colors each 8 pixels of a row separately
+        let colors = (machine.platform() == Platform::Chip8X).then(|| machine.colors.clone());
+        let palette = |x, y| {
+            colors
+                .as_ref()
+                .map_or(self.palette, |colors| colors.palette_at(x, y))
+        };
+        let frame = machine.take_frame();
+
+        if self.phosphor.decay > 0.0 {
+            // The afterglow fades every frame, whether or not the display changed
+            self.phosphor.update(frame.display);
+            let mut image = Image::gen_image_color(
+                DISPLAY_WIDTH as u16,
+                DISPLAY_HEIGHT as u16,
+                to_color(palette(0, 0).background),
+            );
+            for ((x, y), intensity) in self.phosphor.lit_pixels() {
+                image.set_pixel(x as u32, y as u32, to_color(palette(x, y).color(intensity)));
+            }
+            self.texture.update(&image);
+            return;
+        }
+
+        for rect in frame.damage.rects() {
+            let mut bytes = Vec::with_capacity(rect.width * rect.height * 4);
+            for y in rect.y..rect.y + rect.height {
+                for x in rect.x..rect.x + rect.width {
+                    let palette = palette(x, y);
+                    let color = if frame.display.get(x, y) {
+                        palette.foreground
+                    } else {
+                        palette.background
+                    };
+                    bytes.extend([color.r, color.g, color.b, 0xFF]);
+                }
+            }
+
+            let part = Image {
+                bytes,
+                width: rect.width as u16,
+                height: rect.height as u16,
+            };
+            self.texture.update_part(
+                &part,
+                rect.x as i32,
+                rect.y as i32,
+                rect.width as i32,
+                rect.height as i32,
+            );
+        }
+    }
+
+    /// Draws the display as large as it fits in `area`, keeping its aspect ratio.
+    pub fn draw(&self, area: Rect) {
+        let scale_factor: f32 = f32::min(
+            area.w / DISPLAY_WIDTH as f32,
+            area.h / DISPLAY_HEIGHT as f32,
+        );
+        draw_texture_ex(
+            &self.texture,
+            area.x,
+            area.y,
+            WHITE,
+            DrawTextureParams {
+                dest_size: Some(vec2(
+                    DISPLAY_WIDTH as f32 * scale_factor,
+                    DISPLAY_HEIGHT as f32 * scale_factor,
+                )),
+                ..Default::default()
+            },
+        );
+    }
+}
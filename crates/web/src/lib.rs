@@ -0,0 +1,109 @@
+use machine::{
+    Drivers, Machine, Palette, Quirks, Rgb, SquareWave, CYCLES_PER_SECOND, DISPLAY_HEIGHT,
+    DISPLAY_WIDTH,
+};
+use wasm_bindgen::prelude::*;
+
+const FRAMES_PER_SECOND: usize = 60;
+const DEFAULT_SAMPLE_RATE: f32 = 44100.0;
+const VOLUME: f32 = 0.2;
+
+/// A CHIP-8 machine, driven one frame at a time from JavaScript.
+#[wasm_bindgen]
+pub struct Emulator {
+    machine: Machine,
+    palette: Palette,
+    beep: SquareWave,
+    cycles_per_frame: usize,
+}
+
+#[wasm_bindgen]
+impl Emulator {
+    /// Loads a ROM from its bytes.
+    #[wasm_bindgen(constructor)]
+    pub fn new(rom: &[u8]) -> Result<Emulator, JsError> {
+        Ok(Self {
+            machine: Machine::from_rom(rom, Quirks::modern_chip8(), Drivers::noop())?,
+            palette: Palette::CLASSIC,
+            beep: SquareWave::new(DEFAULT_SAMPLE_RATE),
+            cycles_per_frame: CYCLES_PER_SECOND / FRAMES_PER_SECOND,
+        })
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn width(&self) -> usize {
+        DISPLAY_WIDTH
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn height(&self) -> usize {
+        DISPLAY_HEIGHT
+    }
+
+    #[wasm_bindgen(getter, js_name = cyclesPerFrame)]
+    pub fn cycles_per_frame(&self) -> usize {
+        self.cycles_per_frame
+    }
+
+    #[wasm_bindgen(setter, js_name = cyclesPerFrame)]
+    pub fn set_cycles_per_frame(&mut self, cycles_per_frame: usize) {
+        self.cycles_per_frame = cycles_per_frame;
+    }
+
+    /// Runs one 60 Hz frame.
+    #[wasm_bindgen(js_name = runFrame)]
+    pub fn run_frame(&mut self) -> Result<(), JsError> {
+        self.machine
+            .run_frame(self.cycles_per_frame)
+            .map_err(|err| JsError::new(&err.to_string()))
+    }
+
+    /// Returns whether the display has changed since the framebuffer was last read.
+    #[wasm_bindgen(js_name = isDirty)]
+    pub fn is_dirty(&self) -> bool {
+        self.machine.is_dirty()
+    }
+
+    /// Returns the display as row-major RGBA pixels, ready for an `ImageData`.
+    pub fn framebuffer(&mut self) -> Vec<u8> {
+        let frame = self.machine.take_frame();
+        frame.display.to_rgba(&self.palette)
+    }
+
+    /// Sets the colors of the display, as `#RRGGBB`.
+    #[wasm_bindgen(js_name = setPalette)]
+    pub fn set_palette(&mut self, foreground: &str, background: &str) -> Result<(), JsError> {
+        let parse = |hex: &str| {
+            Rgb::from_hex(hex).ok_or_else(|| JsError::new(&format!("Invalid color {hex:?}")))
+        };
+        self.palette = Palette::new(parse(background)?, parse(foreground)?);
+        // Everything needs to be redrawn in the new colors
+        self.machine.damage = machine::Damage::full(DISPLAY_WIDTH, DISPLAY_HEIGHT);
+
+        Ok(())
+    }
+
+    /// Presses or releases a key on the keypad, from 0x0 to 0xF.
+    #[wasm_bindgen(js_name = setKey)]
+    pub fn set_key(&mut self, key: u8, pressed: bool) {
+        self.machine.set_key(key, pressed);
+    }
+
+    #[wasm_bindgen(js_name = setSampleRate)]
+    pub fn set_sample_rate(&mut self, sample_rate: f32) {
+        self.beep.sample_rate = sample_rate;
+    }
+
+    /// Returns the next `count` mono audio samples, which are a beep while the
+    /// sound timer is running and silence otherwise.
+    #[wasm_bindgen(js_name = audioSamples)]
+    pub fn audio_samples(&mut self, count: usize) -> Vec<f32> {
+        if self.machine.st == 0 {
+            self.beep.reset();
+            return vec![0.0; count];
+        }
+        (0..count)
+            .map(|_| self.beep.next_sample() * VOLUME)
+            .collect()
+    }
+}
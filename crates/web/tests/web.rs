@@ -0,0 +1,49 @@
+//! Run with `cargo test --target wasm32-unknown-unknown` from `crates/web`, which
+//! needs `wasm-bindgen-test-runner` from `wasm-bindgen-cli` and runs the tests in Node.
+#![cfg(target_arch = "wasm32")]
+
+use wasm_bindgen_test::wasm_bindgen_test;
+use web::Emulator;
+
+#[wasm_bindgen_test]
+fn draws_ibm_logo() {
+    let rom = include_bytes!("../../../roms/ibm-logo.ch8");
+    let mut emulator = Emulator::new(rom).unwrap();
+    for _ in 0..10 {
+        emulator.run_frame().unwrap();
+    }
+
+    assert!(emulator.is_dirty());
+    let framebuffer = emulator.framebuffer();
+    assert_eq!(framebuffer.len(), emulator.width() * emulator.height() * 4);
+    // Reading the framebuffer takes the damage with it
+    assert!(!emulator.is_dirty());
+
+    // Pixels come out as opaque RGBA in the classic palette: (12, 8) is part of
+    // the logo, and (0, 0) is background
+    let rgba = |x: usize, y: usize| &framebuffer[(y * emulator.width() + x) * 4..][..4];
+    assert_eq!(rgba(12, 8), [0xFF, 0xFF, 0xFF, 0xFF]);
+    assert_eq!(rgba(0, 0), [0x00, 0x00, 0x00, 0xFF]);
+}
+
+#[wasm_bindgen_test]
+fn rejects_oversize_rom() {
+    assert!(Emulator::new(&[0; 4096]).is_err());
+}
+
+#[wasm_bindgen_test]
+fn beeps_once_a_key_is_pressed() {
+    // 200: V0 = the next key pressed
+    // 202: Sound the beep for V0 frames
+    // 204: Spin in place
+    let rom = [0xF0, 0x0A, 0xF0, 0x18, 0x12, 0x04];
+    let mut emulator = Emulator::new(&rom).unwrap();
+    emulator.run_frame().unwrap();
+    let silent = emulator.audio_samples(64);
+    assert!(silent.iter().all(|sample| *sample == 0.0));
+
+    emulator.set_key(0x9, true);
+    emulator.run_frame().unwrap();
+    let beep = emulator.audio_samples(64);
+    assert!(beep.iter().all(|sample| sample.abs() == 0.2));
+}
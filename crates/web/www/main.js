@@ -0,0 +1,104 @@
+import init, { Emulator } from "./pkg/web.js";
+
+// The keypad is mapped by physical key position, so it's the same 4x4 block on
+// any keyboard layout
+const KEYS = {
+  Digit1: 0x1, Digit2: 0x2, Digit3: 0x3, Digit4: 0xc,
+  KeyQ: 0x4, KeyW: 0x5, KeyE: 0x6, KeyR: 0xd,
+  KeyA: 0x7, KeyS: 0x8, KeyD: 0x9, KeyF: 0xe,
+  KeyZ: 0xa, KeyX: 0x0, KeyC: 0xb, KeyV: 0xf,
+};
+
+const canvas = document.getElementById("display");
+const context = canvas.getContext("2d");
+const status = document.getElementById("status");
+
+let emulator = null;
+let audio = null;
+let nextAudioTime = 0;
+
+function setKey(event, pressed) {
+  const key = KEYS[event.code];
+  if (emulator && key !== undefined) {
+    emulator.setKey(key, pressed);
+    event.preventDefault();
+  }
+}
+
+document.addEventListener("keydown", (event) => setKey(event, true));
+document.addEventListener("keyup", (event) => setKey(event, false));
+
+// Queues one frame's worth of audio just after whatever is already queued
+function queueAudio() {
+  const samples = emulator.audioSamples(Math.round(audio.sampleRate / 60));
+  const buffer = audio.createBuffer(1, samples.length, audio.sampleRate);
+  buffer.copyToChannel(samples, 0);
+
+  const source = audio.createBufferSource();
+  source.buffer = buffer;
+  source.connect(audio.destination);
+  nextAudioTime = Math.max(nextAudioTime, audio.currentTime);
+  source.start(nextAudioTime);
+  nextAudioTime += buffer.duration;
+}
+
+const FRAME_TIME = 1000 / 60;
+
+let running = false;
+let lastTime = null;
+let pending = 0;
+
+// Runs as many 60 Hz frames as have passed since the last animation frame, since
+// displays don't all refresh at 60 Hz
+function animate(time) {
+  pending += lastTime === null ? FRAME_TIME : time - lastTime;
+  lastTime = time;
+
+  try {
+    for (; pending >= FRAME_TIME; pending -= FRAME_TIME) {
+      emulator.runFrame();
+      queueAudio();
+    }
+  } catch (error) {
+    status.textContent = error.message;
+    running = false;
+    return;
+  }
+
+  if (emulator.isDirty()) {
+    const pixels = new Uint8ClampedArray(emulator.framebuffer());
+    context.putImageData(new ImageData(pixels, emulator.width, emulator.height), 0, 0);
+  }
+
+  requestAnimationFrame(animate);
+}
+
+function start(rom) {
+  emulator?.free();
+  emulator = new Emulator(rom);
+
+  // Browsers only allow audio to start after a user gesture, like picking a file
+  audio ??= new AudioContext();
+  emulator.setSampleRate(audio.sampleRate);
+
+  status.textContent = "Running";
+  if (!running) {
+    running = true;
+    lastTime = null;
+    pending = 0;
+    requestAnimationFrame(animate);
+  }
+}
+
+await init();
+
+document.getElementById("rom").addEventListener("change", async (event) => {
+  const file = event.target.files[0];
+  if (file) {
+    try {
+      start(new Uint8Array(await file.arrayBuffer()));
+    } catch (error) {
+      status.textContent = error.message;
+    }
+  }
+});
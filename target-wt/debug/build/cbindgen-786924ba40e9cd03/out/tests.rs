@@ -0,0 +1,139 @@
+test_file!(test_global_variable, "global_variable", "/root/.cargo/registry/src/index.crates.io-1949cf8c6b5b557f/cbindgen-0.29.4/tests/rust/global_variable.rs");
+test_file!(test_monomorph_3, "monomorph_3", "/root/.cargo/registry/src/index.crates.io-1949cf8c6b5b557f/cbindgen-0.29.4/tests/rust/monomorph_3.rs");
+test_file!(test_include_specific, "include_specific", "/root/.cargo/registry/src/index.crates.io-1949cf8c6b5b557f/cbindgen-0.29.4/tests/rust/include_specific.rs");
+test_file!(test_typedef, "typedef", "/root/.cargo/registry/src/index.crates.io-1949cf8c6b5b557f/cbindgen-0.29.4/tests/rust/typedef.rs");
+test_file!(test_asserted_cast, "asserted_cast", "/root/.cargo/registry/src/index.crates.io-1949cf8c6b5b557f/cbindgen-0.29.4/tests/rust/asserted_cast.rs");
+test_file!(test_constant_sort_none, "constant_sort_none", "/root/.cargo/registry/src/index.crates.io-1949cf8c6b5b557f/cbindgen-0.29.4/tests/rust/constant_sort_none.rs");
+test_file!(test_reserved, "reserved", "/root/.cargo/registry/src/index.crates.io-1949cf8c6b5b557f/cbindgen-0.29.4/tests/rust/reserved.rs");
+test_file!(test_size_types, "size_types", "/root/.cargo/registry/src/index.crates.io-1949cf8c6b5b557f/cbindgen-0.29.4/tests/rust/size_types.rs");
+test_file!(test_enum, "enum", "/root/.cargo/registry/src/index.crates.io-1949cf8c6b5b557f/cbindgen-0.29.4/tests/rust/enum.rs");
+test_file!(test_doclength_short, "doclength_short", "/root/.cargo/registry/src/index.crates.io-1949cf8c6b5b557f/cbindgen-0.29.4/tests/rust/doclength_short.rs");
+test_file!(test_ptrs_as_arrays, "ptrs_as_arrays", "/root/.cargo/registry/src/index.crates.io-1949cf8c6b5b557f/cbindgen-0.29.4/tests/rust/ptrs_as_arrays.rs");
+test_file!(test_const_generics_thru, "const_generics_thru", "/root/.cargo/registry/src/index.crates.io-1949cf8c6b5b557f/cbindgen-0.29.4/tests/rust/const_generics_thru.rs");
+test_file!(test_nullable, "nullable", "/root/.cargo/registry/src/index.crates.io-1949cf8c6b5b557f/cbindgen-0.29.4/tests/rust/nullable.rs");
+test_file!(test_char, "char", "/root/.cargo/registry/src/index.crates.io-1949cf8c6b5b557f/cbindgen-0.29.4/tests/rust/char.rs");
+test_file!(test_function_noreturn, "function_noreturn", "/root/.cargo/registry/src/index.crates.io-1949cf8c6b5b557f/cbindgen-0.29.4/tests/rust/function_noreturn.rs");
+test_file!(test_const_conflict, "const_conflict", "/root/.cargo/registry/src/index.crates.io-1949cf8c6b5b557f/cbindgen-0.29.4/tests/rust/const_conflict.rs");
+test_file!(test_assoc_constant, "assoc_constant", "/root/.cargo/registry/src/index.crates.io-1949cf8c6b5b557f/cbindgen-0.29.4/tests/rust/assoc_constant.rs");
+test_file!(test_cdecl, "cdecl", "/root/.cargo/registry/src/index.crates.io-1949cf8c6b5b557f/cbindgen-0.29.4/tests/rust/cdecl.rs");
+test_file!(test_display_list, "display_list", "/root/.cargo/registry/src/index.crates.io-1949cf8c6b5b557f/cbindgen-0.29.4/tests/rust/display_list.rs");
+test_file!(test_global_attr, "global_attr", "/root/.cargo/registry/src/index.crates.io-1949cf8c6b5b557f/cbindgen-0.29.4/tests/rust/global_attr.rs");
+test_file!(test_constant_user_defined_type, "constant_user_defined_type", "/root/.cargo/registry/src/index.crates.io-1949cf8c6b5b557f/cbindgen-0.29.4/tests/rust/constant_user_defined_type.rs");
+test_file!(test_nullable_attribute, "nullable_attribute", "/root/.cargo/registry/src/index.crates.io-1949cf8c6b5b557f/cbindgen-0.29.4/tests/rust/nullable_attribute.rs");
+test_file!(test_prefix, "prefix", "/root/.cargo/registry/src/index.crates.io-1949cf8c6b5b557f/cbindgen-0.29.4/tests/rust/prefix.rs");
+test_file!(test_no_includes, "no_includes", "/root/.cargo/registry/src/index.crates.io-1949cf8c6b5b557f/cbindgen-0.29.4/tests/rust/no_includes.rs");
+test_file!(test_generic_defaults, "generic_defaults", "/root/.cargo/registry/src/index.crates.io-1949cf8c6b5b557f/cbindgen-0.29.4/tests/rust/generic_defaults.rs");
+test_file!(test_cython_options, "cython_options", "/root/.cargo/registry/src/index.crates.io-1949cf8c6b5b557f/cbindgen-0.29.4/tests/rust/cython_options.rs");
+test_file!(test_item_types_renamed, "item_types_renamed", "/root/.cargo/registry/src/index.crates.io-1949cf8c6b5b557f/cbindgen-0.29.4/tests/rust/item_types_renamed.rs");
+test_file!(test_swift_name, "swift_name", "/root/.cargo/registry/src/index.crates.io-1949cf8c6b5b557f/cbindgen-0.29.4/tests/rust/swift_name.rs");
+test_file!(test_docstyle_auto, "docstyle_auto", "/root/.cargo/registry/src/index.crates.io-1949cf8c6b5b557f/cbindgen-0.29.4/tests/rust/docstyle_auto.rs");
+test_file!(test_layout_packed_opaque, "layout_packed_opaque", "/root/.cargo/registry/src/index.crates.io-1949cf8c6b5b557f/cbindgen-0.29.4/tests/rust/layout_packed_opaque.rs");
+test_file!(test_associated_in_body, "associated_in_body", "/root/.cargo/registry/src/index.crates.io-1949cf8c6b5b557f/cbindgen-0.29.4/tests/rust/associated_in_body.rs");
+test_file!(test_body, "body", "/root/.cargo/registry/src/index.crates.io-1949cf8c6b5b557f/cbindgen-0.29.4/tests/rust/body.rs");
+test_file!(test_array, "array", "/root/.cargo/registry/src/index.crates.io-1949cf8c6b5b557f/cbindgen-0.29.4/tests/rust/array.rs");
+test_file!(test_struct_self, "struct_self", "/root/.cargo/registry/src/index.crates.io-1949cf8c6b5b557f/cbindgen-0.29.4/tests/rust/struct_self.rs");
+test_file!(test_monomorph_2, "monomorph_2", "/root/.cargo/registry/src/index.crates.io-1949cf8c6b5b557f/cbindgen-0.29.4/tests/rust/monomorph_2.rs");
+test_file!(test_zst, "zst", "/root/.cargo/registry/src/index.crates.io-1949cf8c6b5b557f/cbindgen-0.29.4/tests/rust/zst.rs");
+test_file!(test_const_enum, "const_enum", "/root/.cargo/registry/src/index.crates.io-1949cf8c6b5b557f/cbindgen-0.29.4/tests/rust/const_enum.rs");
+test_file!(test_rename, "rename", "/root/.cargo/registry/src/index.crates.io-1949cf8c6b5b557f/cbindgen-0.29.4/tests/rust/rename.rs");
+test_file!(test_va_list, "va_list", "/root/.cargo/registry/src/index.crates.io-1949cf8c6b5b557f/cbindgen-0.29.4/tests/rust/va_list.rs");
+test_file!(test_transparent, "transparent", "/root/.cargo/registry/src/index.crates.io-1949cf8c6b5b557f/cbindgen-0.29.4/tests/rust/transparent.rs");
+test_file!(test_rename_case, "rename_case", "/root/.cargo/registry/src/index.crates.io-1949cf8c6b5b557f/cbindgen-0.29.4/tests/rust/rename_case.rs");
+test_file!(test_lifetime_arg, "lifetime_arg", "/root/.cargo/registry/src/index.crates.io-1949cf8c6b5b557f/cbindgen-0.29.4/tests/rust/lifetime_arg.rs");
+test_file!(test_namespaces_constant, "namespaces_constant", "/root/.cargo/registry/src/index.crates.io-1949cf8c6b5b557f/cbindgen-0.29.4/tests/rust/namespaces_constant.rs");
+test_file!(test_renaming_overrides_prefixing, "renaming_overrides_prefixing", "/root/.cargo/registry/src/index.crates.io-1949cf8c6b5b557f/cbindgen-0.29.4/tests/rust/renaming_overrides_prefixing.rs");
+test_file!(test_bitflags, "bitflags", "/root/.cargo/registry/src/index.crates.io-1949cf8c6b5b557f/cbindgen-0.29.4/tests/rust/bitflags.rs");
+test_file!(test_opaque, "opaque", "/root/.cargo/registry/src/index.crates.io-1949cf8c6b5b557f/cbindgen-0.29.4/tests/rust/opaque.rs");
+test_file!(test_constant, "constant", "/root/.cargo/registry/src/index.crates.io-1949cf8c6b5b557f/cbindgen-0.29.4/tests/rust/constant.rs");
+test_file!(test_pragma_once_skip_warning_as_error, "pragma_once.skip_warning_as_error", "/root/.cargo/registry/src/index.crates.io-1949cf8c6b5b557f/cbindgen-0.29.4/tests/rust/pragma_once.skip_warning_as_error.rs");
+test_file!(test_function_sort_none, "function_sort_none", "/root/.cargo/registry/src/index.crates.io-1949cf8c6b5b557f/cbindgen-0.29.4/tests/rust/function_sort_none.rs");
+test_file!(test_decl_name_conflicting, "decl_name_conflicting", "/root/.cargo/registry/src/index.crates.io-1949cf8c6b5b557f/cbindgen-0.29.4/tests/rust/decl_name_conflicting.rs");
+test_file!(test_linestyle_lf, "linestyle_lf", "/root/.cargo/registry/src/index.crates.io-1949cf8c6b5b557f/cbindgen-0.29.4/tests/rust/linestyle_lf.rs");
+test_file!(test_manuallydrop, "manuallydrop", "/root/.cargo/registry/src/index.crates.io-1949cf8c6b5b557f/cbindgen-0.29.4/tests/rust/manuallydrop.rs");
+test_file!(test_const_generics_char, "const_generics_char", "/root/.cargo/registry/src/index.crates.io-1949cf8c6b5b557f/cbindgen-0.29.4/tests/rust/const_generics_char.rs");
+test_file!(test_static, "static", "/root/.cargo/registry/src/index.crates.io-1949cf8c6b5b557f/cbindgen-0.29.4/tests/rust/static.rs");
+test_file!(test_nonnull, "nonnull", "/root/.cargo/registry/src/index.crates.io-1949cf8c6b5b557f/cbindgen-0.29.4/tests/rust/nonnull.rs");
+test_file!(test_raw_ident, "raw_ident", "/root/.cargo/registry/src/index.crates.io-1949cf8c6b5b557f/cbindgen-0.29.4/tests/rust/raw_ident.rs");
+test_file!(test_nonnull_attribute, "nonnull_attribute", "/root/.cargo/registry/src/index.crates.io-1949cf8c6b5b557f/cbindgen-0.29.4/tests/rust/nonnull_attribute.rs");
+test_file!(test_custom_header, "custom_header", "/root/.cargo/registry/src/index.crates.io-1949cf8c6b5b557f/cbindgen-0.29.4/tests/rust/custom_header.rs");
+test_file!(test_unsafe_cell, "unsafe_cell", "/root/.cargo/registry/src/index.crates.io-1949cf8c6b5b557f/cbindgen-0.29.4/tests/rust/unsafe_cell.rs");
+test_file!(test_function_sort_name, "function_sort_name", "/root/.cargo/registry/src/index.crates.io-1949cf8c6b5b557f/cbindgen-0.29.4/tests/rust/function_sort_name.rs");
+test_file!(test_enum_self, "enum_self", "/root/.cargo/registry/src/index.crates.io-1949cf8c6b5b557f/cbindgen-0.29.4/tests/rust/enum_self.rs");
+test_file!(test_duplicated_constants, "duplicated_constants", "/root/.cargo/registry/src/index.crates.io-1949cf8c6b5b557f/cbindgen-0.29.4/tests/rust/duplicated_constants.rs");
+test_file!(test_function_ptr, "function_ptr", "/root/.cargo/registry/src/index.crates.io-1949cf8c6b5b557f/cbindgen-0.29.4/tests/rust/function_ptr.rs");
+test_file!(test_forward_declaration, "forward_declaration", "/root/.cargo/registry/src/index.crates.io-1949cf8c6b5b557f/cbindgen-0.29.4/tests/rust/forward_declaration.rs");
+test_file!(test_prefixed_struct_literal, "prefixed_struct_literal", "/root/.cargo/registry/src/index.crates.io-1949cf8c6b5b557f/cbindgen-0.29.4/tests/rust/prefixed_struct_literal.rs");
+test_file!(test_linestyle_cr, "linestyle_cr", "/root/.cargo/registry/src/index.crates.io-1949cf8c6b5b557f/cbindgen-0.29.4/tests/rust/linestyle_cr.rs");
+test_file!(test_prefixed_struct_literal_deep, "prefixed_struct_literal_deep", "/root/.cargo/registry/src/index.crates.io-1949cf8c6b5b557f/cbindgen-0.29.4/tests/rust/prefixed_struct_literal_deep.rs");
+test_file!(test_nonzero, "nonzero", "/root/.cargo/registry/src/index.crates.io-1949cf8c6b5b557f/cbindgen-0.29.4/tests/rust/nonzero.rs");
+test_file!(test_cmse, "cmse", "/root/.cargo/registry/src/index.crates.io-1949cf8c6b5b557f/cbindgen-0.29.4/tests/rust/cmse.rs");
+test_file!(test_constant_sort_name, "constant_sort_name", "/root/.cargo/registry/src/index.crates.io-1949cf8c6b5b557f/cbindgen-0.29.4/tests/rust/constant_sort_name.rs");
+test_file!(test_extern_2, "extern_2", "/root/.cargo/registry/src/index.crates.io-1949cf8c6b5b557f/cbindgen-0.29.4/tests/rust/extern_2.rs");
+test_file!(test_deprecated, "deprecated", "/root/.cargo/registry/src/index.crates.io-1949cf8c6b5b557f/cbindgen-0.29.4/tests/rust/deprecated.rs");
+test_file!(test_simplify_option_ptr, "simplify_option_ptr", "/root/.cargo/registry/src/index.crates.io-1949cf8c6b5b557f/cbindgen-0.29.4/tests/rust/simplify_option_ptr.rs");
+test_file!(test_docstyle_c99, "docstyle_c99", "/root/.cargo/registry/src/index.crates.io-1949cf8c6b5b557f/cbindgen-0.29.4/tests/rust/docstyle_c99.rs");
+test_file!(test_union, "union", "/root/.cargo/registry/src/index.crates.io-1949cf8c6b5b557f/cbindgen-0.29.4/tests/rust/union.rs");
+test_file!(test_abi_string, "abi_string", "/root/.cargo/registry/src/index.crates.io-1949cf8c6b5b557f/cbindgen-0.29.4/tests/rust/abi_string.rs");
+test_file!(test_struct, "struct", "/root/.cargo/registry/src/index.crates.io-1949cf8c6b5b557f/cbindgen-0.29.4/tests/rust/struct.rs");
+test_file!(test_layout, "layout", "/root/.cargo/registry/src/index.crates.io-1949cf8c6b5b557f/cbindgen-0.29.4/tests/rust/layout.rs");
+test_file!(test_nested_import, "nested_import", "/root/.cargo/registry/src/index.crates.io-1949cf8c6b5b557f/cbindgen-0.29.4/tests/rust/nested_import.rs");
+test_file!(test_rename_associated_constant, "rename_associated_constant", "/root/.cargo/registry/src/index.crates.io-1949cf8c6b5b557f/cbindgen-0.29.4/tests/rust/rename_associated_constant.rs");
+test_file!(test_extern, "extern", "/root/.cargo/registry/src/index.crates.io-1949cf8c6b5b557f/cbindgen-0.29.4/tests/rust/extern.rs");
+test_file!(test_transform_op, "transform_op", "/root/.cargo/registry/src/index.crates.io-1949cf8c6b5b557f/cbindgen-0.29.4/tests/rust/transform_op.rs");
+test_file!(test_const_generics_arrayvec, "const_generics_arrayvec", "/root/.cargo/registry/src/index.crates.io-1949cf8c6b5b557f/cbindgen-0.29.4/tests/rust/const_generics_arrayvec.rs");
+test_file!(test_const_generics_bool, "const_generics_bool", "/root/.cargo/registry/src/index.crates.io-1949cf8c6b5b557f/cbindgen-0.29.4/tests/rust/const_generics_bool.rs");
+test_file!(test_exclude_generic_monomorph, "exclude_generic_monomorph", "/root/.cargo/registry/src/index.crates.io-1949cf8c6b5b557f/cbindgen-0.29.4/tests/rust/exclude_generic_monomorph.rs");
+test_file!(test_pin, "pin", "/root/.cargo/registry/src/index.crates.io-1949cf8c6b5b557f/cbindgen-0.29.4/tests/rust/pin.rs");
+test_file!(test_cfg, "cfg", "/root/.cargo/registry/src/index.crates.io-1949cf8c6b5b557f/cbindgen-0.29.4/tests/rust/cfg.rs");
+test_file!(test_enum_discriminant, "enum_discriminant", "/root/.cargo/registry/src/index.crates.io-1949cf8c6b5b557f/cbindgen-0.29.4/tests/rust/enum_discriminant.rs");
+test_file!(test_const_generics, "const_generics", "/root/.cargo/registry/src/index.crates.io-1949cf8c6b5b557f/cbindgen-0.29.4/tests/rust/const_generics.rs");
+test_file!(test_derive_ostream, "derive_ostream", "/root/.cargo/registry/src/index.crates.io-1949cf8c6b5b557f/cbindgen-0.29.4/tests/rust/derive_ostream.rs");
+test_file!(test_namespace_constant, "namespace_constant", "/root/.cargo/registry/src/index.crates.io-1949cf8c6b5b557f/cbindgen-0.29.4/tests/rust/namespace_constant.rs");
+test_file!(test_style_crash, "style_crash", "/root/.cargo/registry/src/index.crates.io-1949cf8c6b5b557f/cbindgen-0.29.4/tests/rust/style_crash.rs");
+test_file!(test_inner_mod, "inner_mod", "/root/.cargo/registry/src/index.crates.io-1949cf8c6b5b557f/cbindgen-0.29.4/tests/rust/inner_mod.rs");
+test_file!(test_non_pub_extern, "non_pub_extern", "/root/.cargo/registry/src/index.crates.io-1949cf8c6b5b557f/cbindgen-0.29.4/tests/rust/non_pub_extern.rs");
+test_file!(test_item_types, "item_types", "/root/.cargo/registry/src/index.crates.io-1949cf8c6b5b557f/cbindgen-0.29.4/tests/rust/item_types.rs");
+test_file!(test_documentation_attr, "documentation_attr", "/root/.cargo/registry/src/index.crates.io-1949cf8c6b5b557f/cbindgen-0.29.4/tests/rust/documentation_attr.rs");
+test_file!(test_include, "include", "/root/.cargo/registry/src/index.crates.io-1949cf8c6b5b557f/cbindgen-0.29.4/tests/rust/include.rs");
+test_file!(test_documentation_block, "documentation_block", "/root/.cargo/registry/src/index.crates.io-1949cf8c6b5b557f/cbindgen-0.29.4/tests/rust/documentation_block.rs");
+test_file!(test_cell, "cell", "/root/.cargo/registry/src/index.crates.io-1949cf8c6b5b557f/cbindgen-0.29.4/tests/rust/cell.rs");
+test_file!(test_ignore, "ignore", "/root/.cargo/registry/src/index.crates.io-1949cf8c6b5b557f/cbindgen-0.29.4/tests/rust/ignore.rs");
+test_file!(test_sentinel, "sentinel", "/root/.cargo/registry/src/index.crates.io-1949cf8c6b5b557f/cbindgen-0.29.4/tests/rust/sentinel.rs");
+test_file!(test_const_transparent, "const_transparent", "/root/.cargo/registry/src/index.crates.io-1949cf8c6b5b557f/cbindgen-0.29.4/tests/rust/const_transparent.rs");
+test_file!(test_export_name, "export_name", "/root/.cargo/registry/src/index.crates.io-1949cf8c6b5b557f/cbindgen-0.29.4/tests/rust/export_name.rs");
+test_file!(test_mangle, "mangle", "/root/.cargo/registry/src/index.crates.io-1949cf8c6b5b557f/cbindgen-0.29.4/tests/rust/mangle.rs");
+test_file!(test_docstyle_doxy, "docstyle_doxy", "/root/.cargo/registry/src/index.crates.io-1949cf8c6b5b557f/cbindgen-0.29.4/tests/rust/docstyle_doxy.rs");
+test_file!(test_generic_pointer, "generic_pointer", "/root/.cargo/registry/src/index.crates.io-1949cf8c6b5b557f/cbindgen-0.29.4/tests/rust/generic_pointer.rs");
+test_file!(test_include_guard, "include_guard", "/root/.cargo/registry/src/index.crates.io-1949cf8c6b5b557f/cbindgen-0.29.4/tests/rust/include_guard.rs");
+test_file!(test_alias, "alias", "/root/.cargo/registry/src/index.crates.io-1949cf8c6b5b557f/cbindgen-0.29.4/tests/rust/alias.rs");
+test_file!(test_impl_unsafe_attr_method, "impl_unsafe_attr_method", "/root/.cargo/registry/src/index.crates.io-1949cf8c6b5b557f/cbindgen-0.29.4/tests/rust/impl_unsafe_attr_method.rs");
+test_file!(test_union_self, "union_self", "/root/.cargo/registry/src/index.crates.io-1949cf8c6b5b557f/cbindgen-0.29.4/tests/rust/union_self.rs");
+test_file!(test_destructor_and_copy_ctor, "destructor_and_copy_ctor", "/root/.cargo/registry/src/index.crates.io-1949cf8c6b5b557f/cbindgen-0.29.4/tests/rust/destructor_and_copy_ctor.rs");
+test_file!(test_annotation, "annotation", "/root/.cargo/registry/src/index.crates.io-1949cf8c6b5b557f/cbindgen-0.29.4/tests/rust/annotation.rs");
+test_file!(test_struct_literal_order, "struct_literal_order", "/root/.cargo/registry/src/index.crates.io-1949cf8c6b5b557f/cbindgen-0.29.4/tests/rust/struct_literal_order.rs");
+test_file!(test_euclid, "euclid", "/root/.cargo/registry/src/index.crates.io-1949cf8c6b5b557f/cbindgen-0.29.4/tests/rust/euclid.rs");
+test_file!(test_linestyle_crlf, "linestyle_crlf", "/root/.cargo/registry/src/index.crates.io-1949cf8c6b5b557f/cbindgen-0.29.4/tests/rust/linestyle_crlf.rs");
+test_file!(test_layout_aligned_opaque, "layout_aligned_opaque", "/root/.cargo/registry/src/index.crates.io-1949cf8c6b5b557f/cbindgen-0.29.4/tests/rust/layout_aligned_opaque.rs");
+test_file!(test_assoc_const_conflict, "assoc_const_conflict", "/root/.cargo/registry/src/index.crates.io-1949cf8c6b5b557f/cbindgen-0.29.4/tests/rust/assoc_const_conflict.rs");
+test_file!(test_include_item, "include_item", "/root/.cargo/registry/src/index.crates.io-1949cf8c6b5b557f/cbindgen-0.29.4/tests/rust/include_item.rs");
+test_file!(test_must_use, "must_use", "/root/.cargo/registry/src/index.crates.io-1949cf8c6b5b557f/cbindgen-0.29.4/tests/rust/must_use.rs");
+test_file!(test_constant_constexpr, "constant_constexpr", "/root/.cargo/registry/src/index.crates.io-1949cf8c6b5b557f/cbindgen-0.29.4/tests/rust/constant_constexpr.rs");
+test_file!(test_monomorph_1, "monomorph_1", "/root/.cargo/registry/src/index.crates.io-1949cf8c6b5b557f/cbindgen-0.29.4/tests/rust/monomorph_1.rs");
+test_file!(test_infinite_recursion_typedef_monomorph, "infinite_recursion_typedef_monomorph", "/root/.cargo/registry/src/index.crates.io-1949cf8c6b5b557f/cbindgen-0.29.4/tests/rust/infinite_recursion_typedef_monomorph.rs");
+test_file!(test_cfg_field, "cfg_field", "/root/.cargo/registry/src/index.crates.io-1949cf8c6b5b557f/cbindgen-0.29.4/tests/rust/cfg_field.rs");
+test_file!(test_const_generics_constant, "const_generics_constant", "/root/.cargo/registry/src/index.crates.io-1949cf8c6b5b557f/cbindgen-0.29.4/tests/rust/const_generics_constant.rs");
+test_file!(test_function_args, "function_args", "/root/.cargo/registry/src/index.crates.io-1949cf8c6b5b557f/cbindgen-0.29.4/tests/rust/function_args.rs");
+test_file!(test_associated_constant_panic, "associated_constant_panic", "/root/.cargo/registry/src/index.crates.io-1949cf8c6b5b557f/cbindgen-0.29.4/tests/rust/associated_constant_panic.rs");
+test_file!(test_maybeuninit, "maybeuninit", "/root/.cargo/registry/src/index.crates.io-1949cf8c6b5b557f/cbindgen-0.29.4/tests/rust/maybeuninit.rs");
+test_file!(test_using_namespaces, "using_namespaces", "/root/.cargo/registry/src/index.crates.io-1949cf8c6b5b557f/cbindgen-0.29.4/tests/rust/using_namespaces.rs");
+test_file!(test_as_ty, "as_ty", "/root/.cargo/registry/src/index.crates.io-1949cf8c6b5b557f/cbindgen-0.29.4/tests/rust/as_ty.rs");
+test_file!(test_enum_self_flags, "enum_self_flags", "/root/.cargo/registry/src/index.crates.io-1949cf8c6b5b557f/cbindgen-0.29.4/tests/rust/enum_self_flags.rs");
+test_file!(test_cfg_2, "cfg_2", "/root/.cargo/registry/src/index.crates.io-1949cf8c6b5b557f/cbindgen-0.29.4/tests/rust/cfg_2.rs");
+test_file!(test_box, "box", "/root/.cargo/registry/src/index.crates.io-1949cf8c6b5b557f/cbindgen-0.29.4/tests/rust/box.rs");
+test_file!(test_std_lib, "std_lib", "/root/.cargo/registry/src/index.crates.io-1949cf8c6b5b557f/cbindgen-0.29.4/tests/rust/std_lib.rs");
+test_file!(test_fns, "fns", "/root/.cargo/registry/src/index.crates.io-1949cf8c6b5b557f/cbindgen-0.29.4/tests/rust/fns.rs");
+test_file!(test_documentation, "documentation", "/root/.cargo/registry/src/index.crates.io-1949cf8c6b5b557f/cbindgen-0.29.4/tests/rust/documentation.rs");
+test_file!(test_struct_literal, "struct_literal", "/root/.cargo/registry/src/index.crates.io-1949cf8c6b5b557f/cbindgen-0.29.4/tests/rust/struct_literal.rs");
+test_file!(test_raw_lines, "raw_lines", "/root/.cargo/registry/src/index.crates.io-1949cf8c6b5b557f/cbindgen-0.29.4/tests/rust/raw_lines.rs");
+test_file!(test_const_generics_byte, "const_generics_byte", "/root/.cargo/registry/src/index.crates.io-1949cf8c6b5b557f/cbindgen-0.29.4/tests/rust/const_generics_byte.rs");
+test_file!(test_constant_big, "constant_big", "/root/.cargo/registry/src/index.crates.io-1949cf8c6b5b557f/cbindgen-0.29.4/tests/rust/constant_big.rs");